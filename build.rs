@@ -9,4 +9,11 @@ fn main() {
     if NIGHTLY {
         println!("cargo:rustc-cfg=nightly");
     }
+
+    // the io_uring backend is only available on linux
+    println!("cargo:rustc-check-cfg=cfg(uring)");
+    let is_linux = std::env::var("CARGO_CFG_TARGET_OS").is_ok_and(|os| os == "linux");
+    if is_linux && std::env::var_os("CARGO_FEATURE_IO_URING").is_some() {
+        println!("cargo:rustc-cfg=uring");
+    }
 }
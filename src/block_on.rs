@@ -0,0 +1,47 @@
+use std::future::Future;
+use std::pin::pin;
+use std::sync::Arc;
+use std::task::{Context, Poll, Wake, Waker};
+
+use crate::sync::Blocker;
+
+// the waker that unpark the blocked coroutine or thread
+struct BlockerWaker(Arc<Blocker>);
+
+impl Wake for BlockerWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// run the future to completion in current coroutine context
+///
+/// the coroutine is parked when the future is pending and unparked by
+/// the waker of the future, so other coroutines can run in the meantime.
+/// if called in thread context, the thread is parked instead.
+///
+/// # Examples
+///
+/// ```
+/// use may::coroutine;
+///
+/// let v = coroutine::block_on(async { 40 + 2 });
+/// assert_eq!(v, 42);
+/// ```
+pub fn block_on<F: Future>(fut: F) -> F::Output {
+    let blocker = Blocker::current();
+    let waker = Waker::from(Arc::new(BlockerWaker(blocker.clone())));
+    let mut cx = Context::from_waker(&waker);
+    let mut fut = pin!(fut);
+    loop {
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(v) => return v,
+            // the park token is kept if the waker is already called
+            Poll::Pending => blocker.park(None).ok(),
+        };
+    }
+}
@@ -0,0 +1,185 @@
+//! a thread pool to run the blocking calls without stalling the workers
+
+use std::collections::VecDeque;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::OnceLock;
+use std::thread;
+use std::time::Duration;
+
+use crate::coroutine_impl::{current_cancel_data, is_coroutine};
+use crate::sync::Blocker;
+use parking_lot::{Condvar, Mutex};
+
+// the max number of the blocking threads
+const MAX_THREADS: usize = 512;
+// the idle blocking thread would exit after this duration
+const KEEP_ALIVE: Duration = Duration::from_secs(10);
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct State {
+    jobs: VecDeque<Job>,
+    // the threads that are waiting for jobs
+    idle: usize,
+    // all the alive threads
+    threads: usize,
+}
+
+#[derive(Default)]
+struct Pool {
+    state: Mutex<State>,
+    cvar: Condvar,
+}
+
+impl Pool {
+    // queue the job, it's given back if there is no thread to run it
+    fn execute(&'static self, job: Job) -> Result<(), Job> {
+        let mut state = self.state.lock();
+        // spawn a new thread if there are not enough idle threads
+        if state.jobs.len() >= state.idle && state.threads < MAX_THREADS {
+            let spawn = thread::Builder::new()
+                .name("may-blocking".into())
+                .spawn(move || self.run());
+            match spawn {
+                Ok(_) => state.threads += 1,
+                Err(e) => {
+                    error!("failed to spawn blocking thread, err={e}");
+                    if state.threads == 0 {
+                        return Err(job);
+                    }
+                }
+            }
+        }
+        state.jobs.push_back(job);
+        self.cvar.notify_one();
+        Ok(())
+    }
+
+    fn run(&self) {
+        let mut state = self.state.lock();
+        loop {
+            if let Some(job) = state.jobs.pop_front() {
+                drop(state);
+                job();
+                state = self.state.lock();
+                continue;
+            }
+
+            state.idle += 1;
+            let timeout = self.cvar.wait_for(&mut state, KEEP_ALIVE).timed_out();
+            state.idle -= 1;
+            if timeout && state.jobs.is_empty() {
+                state.threads -= 1;
+                return;
+            }
+        }
+    }
+}
+
+fn pool() -> &'static Pool {
+    static POOL: OnceLock<Pool> = OnceLock::new();
+    POOL.get_or_init(Pool::default)
+}
+
+/// run the blocking function in the thread pool and wait for the result
+///
+/// the calling coroutine is parked until the function returns, so the
+/// function can borrow from the coroutine stack. the cancellation of the
+/// coroutine is delayed after the function returns. in thread context
+/// the function is called directly.
+pub(crate) fn run<F, T>(f: F) -> T
+where
+    F: FnOnce() -> T + Send,
+    T: Send,
+{
+    if !is_coroutine() {
+        return f();
+    }
+
+    let blocker = Blocker::current();
+    let ret = Mutex::new(None);
+    let job = {
+        let blocker = blocker.clone();
+        let ret = &ret;
+        Box::new(move || {
+            *ret.lock() = Some(panic::catch_unwind(AssertUnwindSafe(f)));
+            blocker.unpark();
+        })
+    };
+    // SAFETY: we wait for the job done before return
+    let job: Box<dyn FnOnce() + Send + '_> = job;
+    let job: Job = unsafe { mem::transmute(job) };
+
+    // the job may still use the stack, can't be canceled when waiting
+    let cancel = current_cancel_data();
+    cancel.disable_cancel();
+    if let Err(job) = pool().execute(job) {
+        // no thread in the pool, run it in place
+        job();
+    }
+    let ret = loop {
+        if let Some(ret) = ret.lock().take() {
+            break ret;
+        }
+        // wake up either by the job or by the cancel
+        blocker.park(None).ok();
+    };
+    cancel.enable_cancel();
+    cancel.check_cancel();
+
+    match ret {
+        Ok(v) => v,
+        Err(panic) => panic::resume_unwind(panic),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn blocking_borrow() {
+        let h = go!(|| {
+            let mut buf = vec![0u8; 4];
+            let n = run(|| {
+                buf.copy_from_slice(b"may!");
+                thread::current().name().map(str::to_owned)
+            });
+            (buf, n)
+        });
+        let (buf, name) = h.join().unwrap();
+        assert_eq!(buf, b"may!");
+        assert_eq!(name.as_deref(), Some("may-blocking"));
+    }
+
+    #[test]
+    #[should_panic(expected = "blocking panic")]
+    fn blocking_panic() {
+        let h = go!(|| run(|| panic!("blocking panic")));
+        if let Err(panic) = h.join() {
+            panic::resume_unwind(panic);
+        }
+    }
+
+    #[test]
+    fn blocking_cancel() {
+        let done = Arc::new(AtomicBool::new(false));
+        let d = done.clone();
+        let h = go!(move || {
+            run(|| {
+                thread::sleep(Duration::from_millis(50));
+                d.store(true, Ordering::Release);
+            });
+            unreachable!("should be canceled");
+        });
+        thread::sleep(Duration::from_millis(10));
+        unsafe { h.coroutine().cancel() };
+        // the cancel takes effect after the blocking call
+        assert!(h.join().is_err());
+        assert!(done.load(Ordering::Acquire));
+    }
+}
@@ -0,0 +1,51 @@
+use std::io;
+
+use super::{Decoder, Encoder};
+
+/// A codec that passes the bytes through without framing.
+///
+/// Each decoded frame is all the bytes read so far.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BytesCodec(());
+
+impl BytesCodec {
+    /// Creates a new `BytesCodec`
+    pub fn new() -> Self {
+        BytesCodec(())
+    }
+}
+
+impl Decoder for BytesCodec {
+    type Item = Vec<u8>;
+
+    fn decode(&mut self, src: &mut Vec<u8>) -> io::Result<Option<Vec<u8>>> {
+        if src.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(std::mem::take(src)))
+    }
+}
+
+impl<T: AsRef<[u8]>> Encoder<T> for BytesCodec {
+    fn encode(&mut self, item: T, dst: &mut Vec<u8>) -> io::Result<()> {
+        dst.extend_from_slice(item.as_ref());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes_codec() {
+        let mut codec = BytesCodec::new();
+        let mut buf = vec![];
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+
+        codec.encode(b"hello ", &mut buf).unwrap();
+        codec.encode("world", &mut buf).unwrap();
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), b"hello world");
+        assert!(buf.is_empty());
+    }
+}
@@ -0,0 +1,282 @@
+use std::fmt;
+use std::io::{self, Read, Write};
+
+use super::{Decoder, Encoder};
+
+// the size of each read from the stream
+const READ_SIZE: usize = 8 * 1024;
+
+/// A stream of frames over an io object.
+///
+/// The reads are available when `T` is `Read` and the writes when `T` is
+/// `Write`, so it could also wrap one part of a split stream.
+///
+/// # Examples
+///
+/// ```no_run
+/// use may::codec::{Framed, LinesCodec};
+/// use may::io::SplitIo;
+/// use may::net::TcpStream;
+///
+/// let stream = TcpStream::connect("127.0.0.1:8080").unwrap();
+/// let (r, w) = stream.split().unwrap();
+/// let mut lines = Framed::new(r, LinesCodec::new());
+/// let mut sink = Framed::new(w, LinesCodec::new());
+///
+/// may::go!(move || sink.send_frame("hello").unwrap());
+/// while let Some(line) = lines.next_frame().unwrap() {
+///     println!("{line}");
+/// }
+/// ```
+pub struct Framed<T, C> {
+    io: T,
+    codec: C,
+    rd: Vec<u8>,
+    wr: Vec<u8>,
+    eof: bool,
+}
+
+impl<T, C> Framed<T, C> {
+    /// Creates a new `Framed` over the io with the codec
+    pub fn new(io: T, codec: C) -> Self {
+        Framed {
+            io,
+            codec,
+            rd: Vec::new(),
+            wr: Vec::new(),
+            eof: false,
+        }
+    }
+
+    /// Returns a reference to the underlying io object
+    pub fn get_ref(&self) -> &T {
+        &self.io
+    }
+
+    /// Returns a mutable reference to the underlying io object.
+    ///
+    /// Reading or writing it directly may corrupt the frames.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.io
+    }
+
+    /// Returns a reference to the codec
+    pub fn codec(&self) -> &C {
+        &self.codec
+    }
+
+    /// Returns a mutable reference to the codec
+    pub fn codec_mut(&mut self) -> &mut C {
+        &mut self.codec
+    }
+
+    /// Returns the bytes that are read but not decoded yet
+    pub fn read_buffer(&self) -> &[u8] {
+        &self.rd
+    }
+
+    /// Returns the bytes that are encoded but not written yet
+    pub fn write_buffer(&self) -> &[u8] {
+        &self.wr
+    }
+
+    /// Consumes the `Framed` and returns the underlying io object.
+    ///
+    /// The bytes in the buffers are dropped.
+    pub fn into_inner(self) -> T {
+        self.io
+    }
+}
+
+impl<T: Read, C: Decoder> Framed<T, C> {
+    /// Receives the next frame from the io.
+    ///
+    /// The coroutine is blocked until a whole frame is received. Returns
+    /// `None` when the io reaches the end.
+    pub fn next_frame(&mut self) -> io::Result<Option<C::Item>> {
+        loop {
+            if self.eof {
+                return self.codec.decode_eof(&mut self.rd);
+            }
+            if let Some(frame) = self.codec.decode(&mut self.rd)? {
+                return Ok(Some(frame));
+            }
+
+            let len = self.rd.len();
+            self.rd.resize(len + READ_SIZE, 0);
+            let n = match self.io.read(&mut self.rd[len..]) {
+                Ok(n) => n,
+                Err(e) => {
+                    self.rd.truncate(len);
+                    if e.kind() == io::ErrorKind::Interrupted {
+                        continue;
+                    }
+                    return Err(e);
+                }
+            };
+            self.rd.truncate(len + n);
+            self.eof = n == 0;
+        }
+    }
+}
+
+impl<T: Write, C> Framed<T, C> {
+    /// Encodes the frame into the write buffer without writing it.
+    ///
+    /// Call `flush` to write the buffered frames.
+    pub fn feed_frame<I>(&mut self, item: I) -> io::Result<()>
+    where
+        C: Encoder<I>,
+    {
+        self.codec.encode(item, &mut self.wr)
+    }
+
+    /// Writes all the buffered frames to the io.
+    ///
+    /// On error the bytes that are not written are kept in the write
+    /// buffer, so calling `flush` again would continue from there.
+    pub fn flush(&mut self) -> io::Result<()> {
+        let mut written = 0;
+        let ret = loop {
+            if written == self.wr.len() {
+                break Ok(());
+            }
+            match self.io.write(&self.wr[written..]) {
+                Ok(0) => break Err(io::Error::from(io::ErrorKind::WriteZero)),
+                Ok(n) => written += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => break Err(e),
+            }
+        };
+        self.wr.drain(..written);
+        ret?;
+        self.io.flush()
+    }
+
+    /// Sends the frame to the io, the coroutine is blocked until the frame
+    /// is written.
+    pub fn send_frame<I>(&mut self, item: I) -> io::Result<()>
+    where
+        C: Encoder<I>,
+    {
+        self.feed_frame(item)?;
+        self.flush()
+    }
+}
+
+impl<T: Read, C: Decoder> Iterator for Framed<T, C> {
+    type Item = io::Result<C::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_frame().transpose()
+    }
+}
+
+impl<T: fmt::Debug, C: fmt::Debug> fmt::Debug for Framed<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Framed")
+            .field("io", &self.io)
+            .field("codec", &self.codec)
+            .finish()
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::codec::{LengthDelimitedCodec, LinesCodec};
+    use crate::io::SplitIo;
+    use crate::net::{TcpListener, TcpStream};
+
+    #[test]
+    fn framed_split_stream() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // echo the frames back in upper case
+        let h = go!(move || {
+            let (s, _) = listener.accept().unwrap();
+            let mut framed = Framed::new(s, LengthDelimitedCodec::new());
+            while let Some(frame) = framed.next_frame().unwrap() {
+                framed.send_frame(frame.to_ascii_uppercase()).unwrap();
+            }
+        });
+
+        let s = TcpStream::connect(addr).unwrap();
+        let (r, w) = s.split().unwrap();
+        let mut reader = Framed::new(r, LengthDelimitedCodec::new());
+        let mut writer = Framed::new(w, LengthDelimitedCodec::new());
+
+        // one coroutine writes the frames while another reads them
+        let sender = go!(move || {
+            for i in 0..100 {
+                writer.feed_frame(format!("frame {i}")).unwrap();
+            }
+            writer.flush().unwrap();
+            writer.get_ref().inner().shutdown(std::net::Shutdown::Write)
+        });
+        for i in 0..100 {
+            let frame = reader.next_frame().unwrap().unwrap();
+            assert_eq!(frame, format!("FRAME {i}").as_bytes());
+        }
+        assert!(reader.next_frame().unwrap().is_none());
+        sender.join().unwrap().unwrap();
+        h.join().unwrap();
+    }
+
+    #[test]
+    fn framed_lines_eof() {
+        let data: &[u8] = b"first\r\nsecond\nlast";
+        let framed = Framed::new(data, LinesCodec::new());
+        let lines: Vec<String> = framed.map(Result::unwrap).collect();
+        assert_eq!(lines, ["first", "second", "last"]);
+
+        // a frame is cut by the end
+        let data: &[u8] = &[0, 0, 0, 8, 1, 2];
+        let mut framed = Framed::new(data, LengthDelimitedCodec::new());
+        let err = framed.next_frame().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+    }
+
+    // accepts `left` bytes and then fails until more room is given
+    struct Limited {
+        data: Vec<u8>,
+        left: usize,
+    }
+
+    impl Write for Limited {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.left == 0 {
+                return Err(io::ErrorKind::BrokenPipe.into());
+            }
+            let n = buf.len().min(self.left).min(2);
+            self.data.extend_from_slice(&buf[..n]);
+            self.left -= n;
+            Ok(n)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn framed_flush_partial() {
+        let io = Limited {
+            data: Vec::new(),
+            left: 5,
+        };
+        let mut framed = Framed::new(io, LinesCodec::new());
+        framed.feed_frame("hello").unwrap();
+        framed.feed_frame("world").unwrap();
+
+        let err = framed.flush().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(framed.write_buffer(), b"\nworld\n");
+
+        framed.get_mut().left = 100;
+        framed.flush().unwrap();
+        assert!(framed.write_buffer().is_empty());
+        assert_eq!(framed.get_ref().data, b"hello\nworld\n");
+    }
+}
@@ -0,0 +1,123 @@
+use std::io;
+
+use super::{Decoder, Encoder};
+
+// the size of the length field
+const HEAD_LEN: usize = 4;
+// the default frame length limit
+const DEFAULT_MAX_FRAME_LENGTH: usize = 8 * 1024 * 1024;
+
+/// A codec for the frames prefixed by their length.
+///
+/// The length is a 4 bytes big endian `u32` that doesn't count itself.
+/// The frames longer than the limit are rejected, by default it's 8MB.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LengthDelimitedCodec {
+    max_frame_length: usize,
+}
+
+impl LengthDelimitedCodec {
+    /// Creates a new `LengthDelimitedCodec` with the default frame length limit
+    pub fn new() -> Self {
+        Self::new_with_max_length(DEFAULT_MAX_FRAME_LENGTH)
+    }
+
+    /// Creates a new `LengthDelimitedCodec` that rejects the frames longer
+    /// than `max_frame_length` bytes.
+    pub fn new_with_max_length(max_frame_length: usize) -> Self {
+        LengthDelimitedCodec { max_frame_length }
+    }
+
+    /// Returns the frame length limit
+    pub fn max_frame_length(&self) -> usize {
+        self.max_frame_length
+    }
+}
+
+impl Default for LengthDelimitedCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Decoder for LengthDelimitedCodec {
+    type Item = Vec<u8>;
+
+    fn decode(&mut self, src: &mut Vec<u8>) -> io::Result<Option<Vec<u8>>> {
+        if src.len() < HEAD_LEN {
+            return Ok(None);
+        }
+        let mut head = [0; HEAD_LEN];
+        head.copy_from_slice(&src[..HEAD_LEN]);
+        let len = u32::from_be_bytes(head) as usize;
+        if len > self.max_frame_length {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "frame length limit exceeded",
+            ));
+        }
+
+        let end = HEAD_LEN + len;
+        if src.len() < end {
+            // make room for the rest of the frame
+            src.reserve(end - src.len());
+            return Ok(None);
+        }
+        let frame = src[HEAD_LEN..end].to_vec();
+        src.drain(..end);
+        Ok(Some(frame))
+    }
+}
+
+impl<T: AsRef<[u8]>> Encoder<T> for LengthDelimitedCodec {
+    fn encode(&mut self, item: T, dst: &mut Vec<u8>) -> io::Result<()> {
+        let frame = item.as_ref();
+        let len = match u32::try_from(frame.len()) {
+            Ok(len) if frame.len() <= self.max_frame_length => len,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "frame length limit exceeded",
+                ))
+            }
+        };
+        dst.reserve(HEAD_LEN + frame.len());
+        dst.extend_from_slice(&len.to_be_bytes());
+        dst.extend_from_slice(frame);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn length_delimited_codec() {
+        let mut codec = LengthDelimitedCodec::new();
+        let mut buf = vec![];
+        codec.encode("hello", &mut buf).unwrap();
+        codec.encode(b"", &mut buf).unwrap();
+        assert_eq!(buf, b"\0\0\0\x05hello\0\0\0\0");
+
+        let mut partial = buf.split_off(6);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.append(&mut partial);
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), b"hello");
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), b"");
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert!(buf.is_empty());
+    }
+
+    #[test]
+    fn length_delimited_codec_max_length() {
+        let mut codec = LengthDelimitedCodec::new_with_max_length(4);
+        let mut buf = vec![];
+        let err = codec.encode("hello", &mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        let mut buf = b"\0\0\0\x05hello".to_vec();
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
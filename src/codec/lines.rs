@@ -0,0 +1,134 @@
+use std::io;
+
+use super::{Decoder, Encoder};
+
+/// A codec for the lines ended by `\n`.
+///
+/// The decoded lines are `String`s without the `\n` and the trailing `\r`.
+/// The last line before the end of the stream doesn't need the `\n`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinesCodec {
+    // where to continue searching the `\n`
+    next_index: usize,
+    max_length: usize,
+}
+
+impl LinesCodec {
+    /// Creates a new `LinesCodec` without a line length limit
+    pub fn new() -> Self {
+        Self::new_with_max_length(usize::MAX)
+    }
+
+    /// Creates a new `LinesCodec` that rejects the lines longer than
+    /// `max_length` bytes with an `InvalidData` error.
+    pub fn new_with_max_length(max_length: usize) -> Self {
+        LinesCodec {
+            next_index: 0,
+            max_length,
+        }
+    }
+
+    /// Returns the line length limit
+    pub fn max_length(&self) -> usize {
+        self.max_length
+    }
+}
+
+impl Default for LinesCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn to_line(mut buf: Vec<u8>) -> io::Result<String> {
+    if buf.last() == Some(&b'\r') {
+        buf.pop();
+    }
+    String::from_utf8(buf).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+impl Decoder for LinesCodec {
+    type Item = String;
+
+    fn decode(&mut self, src: &mut Vec<u8>) -> io::Result<Option<String>> {
+        // no need to search beyond the limit
+        let read_to = src.len().min(self.max_length.saturating_add(1));
+        match src[self.next_index..read_to]
+            .iter()
+            .position(|&b| b == b'\n')
+        {
+            Some(pos) => {
+                let end = self.next_index + pos;
+                self.next_index = 0;
+                let mut line: Vec<u8> = src.drain(..=end).collect();
+                line.pop();
+                to_line(line).map(Some)
+            }
+            None if src.len() > self.max_length => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "line length limit exceeded",
+            )),
+            None => {
+                self.next_index = read_to;
+                Ok(None)
+            }
+        }
+    }
+
+    fn decode_eof(&mut self, src: &mut Vec<u8>) -> io::Result<Option<String>> {
+        match self.decode(src)? {
+            Some(line) => Ok(Some(line)),
+            None if src.is_empty() => Ok(None),
+            None => {
+                self.next_index = 0;
+                to_line(std::mem::take(src)).map(Some)
+            }
+        }
+    }
+}
+
+impl<T: AsRef<str>> Encoder<T> for LinesCodec {
+    fn encode(&mut self, item: T, dst: &mut Vec<u8>) -> io::Result<()> {
+        let line = item.as_ref();
+        dst.reserve(line.len() + 1);
+        dst.extend_from_slice(line.as_bytes());
+        dst.push(b'\n');
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines_codec() {
+        let mut codec = LinesCodec::new();
+        let mut buf = b"hello\r\nwor".to_vec();
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "hello");
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(b"ld\n\nend");
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "world");
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "");
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        assert_eq!(codec.decode_eof(&mut buf).unwrap().unwrap(), "end");
+        assert_eq!(codec.decode_eof(&mut buf).unwrap(), None);
+
+        codec.encode("line", &mut buf).unwrap();
+        codec.encode(String::from("two"), &mut buf).unwrap();
+        assert_eq!(buf, b"line\ntwo\n");
+    }
+
+    #[test]
+    fn lines_codec_max_length() {
+        let mut codec = LinesCodec::new_with_max_length(4);
+        let mut buf = b"four\nfive!".to_vec();
+        assert_eq!(codec.decode(&mut buf).unwrap().unwrap(), "four");
+        let err = codec.decode(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+        let mut buf = vec![0xff, b'\n'];
+        let err = LinesCodec::new().decode(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,58 @@
+//! Framed io over the coroutine streams
+//!
+//! A [`Decoder`] turns the bytes read from a stream into frames and an
+//! [`Encoder`] turns the frames into bytes to write. [`Framed`] does the
+//! buffering and the partial reads, `next_frame` blocks the coroutine
+//! until a whole frame is received.
+//!
+//! `Framed` works with any `Read`/`Write` object, including the parts from
+//! [`SplitIo::split`], so one coroutine could read the frames while another
+//! writes them.
+//!
+//! [`SplitIo::split`]: crate::io::SplitIo::split
+
+mod bytes_codec;
+mod framed;
+mod length_delimited;
+mod lines;
+
+use std::io;
+
+pub use self::bytes_codec::BytesCodec;
+pub use self::framed::Framed;
+pub use self::length_delimited::LengthDelimitedCodec;
+pub use self::lines::LinesCodec;
+
+/// Decodes the frames from the bytes read from a stream
+pub trait Decoder {
+    /// The type of the decoded frames
+    type Item;
+
+    /// Decodes a frame from the front of the buffer.
+    ///
+    /// The bytes of the decoded frame are removed from `src`. Returns
+    /// `None` if there is not a whole frame yet, then more bytes are read
+    /// into the buffer before it's called again.
+    fn decode(&mut self, src: &mut Vec<u8>) -> io::Result<Option<Self::Item>>;
+
+    /// Decodes a frame when the stream reaches the end.
+    ///
+    /// By default it's the same as `decode`, but the bytes left in the
+    /// buffer are reported as an `UnexpectedEof` error.
+    fn decode_eof(&mut self, src: &mut Vec<u8>) -> io::Result<Option<Self::Item>> {
+        match self.decode(src)? {
+            Some(frame) => Ok(Some(frame)),
+            None if src.is_empty() => Ok(None),
+            None => Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                "bytes remaining on stream",
+            )),
+        }
+    }
+}
+
+/// Encodes the frames into the bytes written to a stream
+pub trait Encoder<Item> {
+    /// Appends the encoded frame to the end of `dst`
+    fn encode(&mut self, item: Item, dst: &mut Vec<u8>) -> io::Result<()>;
+}
@@ -4,6 +4,8 @@
 #[cfg(feature = "io_timeout")]
 use std::sync::atomic::AtomicU64;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+#[cfg(feature = "io_timeout")]
+use std::time::Duration;
 
 // default stack size, in usize
 // windows has a minimal size as 0x4a8!!!!
@@ -22,6 +24,14 @@ static POLL_TIMEOUT_NS: AtomicU64 = AtomicU64::new(10_000_000);
 // Should cores be pinned?
 static PIN_WORKERS: AtomicBool = AtomicBool::new(true);
 
+// Should sub-millisecond timers keep their precision?
+static HIGH_RES_TIMER: AtomicBool = AtomicBool::new(false);
+
+// How late can an io timeout fire, in ns
+// By default, no slack
+#[cfg(feature = "io_timeout")]
+static TIMER_SLACK_NS: AtomicU64 = AtomicU64::new(0);
+
 /// `May` Configuration type
 pub struct Config;
 
@@ -116,4 +126,47 @@ impl Config {
     pub fn get_worker_pin(&self) -> bool {
         PIN_WORKERS.load(Ordering::Acquire)
     }
+
+    /// Enable/Disable the high resolution timer
+    ///
+    /// when enabled, sleeps and timeouts that are not whole milliseconds
+    /// fire with microsecond precision, on linux the event loop waits on
+    /// a `timerfd` for that. whole millisecond timers are still aligned
+    /// to milliseconds. it's ignored by the kqueue and iocp event loops,
+    /// all the timers are aligned to milliseconds there.
+    ///
+    /// it must be set before the first coroutine is spawned, the timer
+    /// wheels and the `timerfd` are created when the scheduler starts,
+    /// changing it after that has no effect.
+    pub fn set_high_res_timer(&self, enable: bool) {
+        HIGH_RES_TIMER.store(enable, Ordering::Release);
+    }
+
+    /// Check if the high resolution timer is on
+    pub fn get_high_res_timer(&self) -> bool {
+        HIGH_RES_TIMER.load(Ordering::Acquire)
+    }
+
+    /// Set the timer slack for io timeouts
+    ///
+    /// an io timeout may fire up to `slack` late, so that the timeouts
+    /// registered around the same time are coalesced into one expiry.
+    /// this cuts the wakeups of a server with lots of idle connections.
+    /// sleeps and the timers in [`time`](crate::time) are not affected.
+    /// on unix a socket can opt out with [`TcpStream::set_precise_timer`]
+    /// or [`UdpSocket::set_precise_timer`].
+    ///
+    /// [`TcpStream::set_precise_timer`]: crate::net::TcpStream::set_precise_timer
+    /// [`UdpSocket::set_precise_timer`]: crate::net::UdpSocket::set_precise_timer
+    #[cfg(feature = "io_timeout")]
+    pub fn set_timer_slack(&self, slack: Duration) {
+        let ns = u64::try_from(slack.as_nanos()).unwrap_or(u64::MAX);
+        TIMER_SLACK_NS.store(ns, Ordering::Release);
+    }
+
+    /// Get the timer slack for io timeouts
+    #[cfg(feature = "io_timeout")]
+    pub fn get_timer_slack(&self) -> Duration {
+        Duration::from_nanos(TIMER_SLACK_NS.load(Ordering::Acquire))
+    }
 }
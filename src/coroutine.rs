@@ -1,10 +1,12 @@
 // re-export coroutine interface
+pub use crate::block_on::block_on;
 pub use crate::cancel::trigger_cancel_panic;
 pub use crate::coroutine_impl::{
     current, is_coroutine, park, park_timeout, spawn, Builder, Coroutine,
 };
 pub use crate::join::JoinHandle;
 pub use crate::park::ParkError;
-pub use crate::scoped::scope;
+pub use crate::scoped::{scope, scope_fail_fast};
 pub use crate::sleep::sleep;
+pub use crate::stream::{stream, Stream, Yielder};
 pub use crate::yield_now::yield_now;
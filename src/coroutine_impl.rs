@@ -150,6 +150,11 @@ impl Coroutine {
         self.inner.name.as_deref()
     }
 
+    /// return true if the two handles refer to the same coroutine
+    pub(crate) fn ptr_eq(&self, other: &Coroutine) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
     /// Get the internal cancel
     #[cfg(unix)]
     #[cfg(feature = "io_cancel")]
@@ -0,0 +1,540 @@
+//! Coroutine-friendly filesystem operations
+//!
+//! This module mirrors `std::fs`. The blocking calls run in a thread pool
+//! while the calling coroutine is parked, so they never stall the worker
+//! threads. On Linux with the `io_uring` feature, `File` reads and writes
+//! are submitted to io_uring instead. In thread context the calls are the
+//! same as the `std::fs` ones.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+pub use std::fs::{DirEntry, FileType, Metadata, Permissions};
+
+use crate::blocking;
+#[cfg(uring)]
+use crate::coroutine_impl::is_coroutine;
+#[cfg(uring)]
+use crate::io::net as net_impl;
+#[cfg(uring)]
+use crate::io::sys::uring;
+#[cfg(uring)]
+use crate::io::{AsIoData, IoData};
+#[cfg(uring)]
+use crate::yield_now::yield_with_io;
+
+// the number of entries read from the directory in one blocking call
+const READ_DIR_BATCH: usize = 32;
+
+/// A reference to an open file on the filesystem.
+///
+/// This is the coroutine version of `std::fs::File`.
+pub struct File {
+    // the io data must be dropped before the file is closed
+    #[cfg(uring)]
+    io: IoData,
+    inner: std::fs::File,
+}
+
+impl File {
+    /// Attempts to open a file in read-only mode.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<File> {
+        OpenOptions::new().read(true).open(path)
+    }
+
+    /// Opens a file in write-only mode, create or truncate it.
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<File> {
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)
+    }
+
+    /// Creates a new file in read-write mode, error if the file exists.
+    pub fn create_new<P: AsRef<Path>>(path: P) -> io::Result<File> {
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create_new(true)
+            .open(path)
+    }
+
+    /// Returns a new `OpenOptions` object.
+    pub fn options() -> OpenOptions {
+        OpenOptions::new()
+    }
+
+    /// Attempts to sync all OS-internal metadata to disk.
+    pub fn sync_all(&self) -> io::Result<()> {
+        blocking::run(|| self.inner.sync_all())
+    }
+
+    /// Attempts to sync the file content to disk, may not sync the metadata.
+    pub fn sync_data(&self) -> io::Result<()> {
+        blocking::run(|| self.inner.sync_data())
+    }
+
+    /// Truncates or extends the underlying file.
+    pub fn set_len(&self, size: u64) -> io::Result<()> {
+        blocking::run(|| self.inner.set_len(size))
+    }
+
+    /// Queries metadata about the underlying file.
+    pub fn metadata(&self) -> io::Result<Metadata> {
+        blocking::run(|| self.inner.metadata())
+    }
+
+    /// Creates a new `File` that shares the same underlying file handle.
+    pub fn try_clone(&self) -> io::Result<File> {
+        self.inner.try_clone().map(File::from_std)
+    }
+
+    /// Changes the permissions of the underlying file.
+    pub fn set_permissions(&self, perm: Permissions) -> io::Result<()> {
+        blocking::run(|| self.inner.set_permissions(perm))
+    }
+
+    /// Wraps a `std::fs::File`.
+    pub fn from_std(file: std::fs::File) -> File {
+        File {
+            #[cfg(uring)]
+            io: IoData::new(&file),
+            inner: file,
+        }
+    }
+
+    /// Returns the inner `std::fs::File`.
+    pub fn into_std(self) -> std::fs::File {
+        self.inner
+    }
+
+    fn read_impl(&self, buf: &mut [u8]) -> io::Result<usize> {
+        #[cfg(uring)]
+        if is_coroutine() && uring::is_enabled() {
+            // read from the current file position
+            let mut reader = net_impl::SocketRead::new(
+                self,
+                buf,
+                #[cfg(feature = "io_timeout")]
+                None,
+            );
+            yield_with_io(&reader, reader.is_coroutine);
+            return reader.done();
+        }
+
+        blocking::run(|| (&self.inner).read(buf))
+    }
+
+    fn write_impl(&self, buf: &[u8]) -> io::Result<usize> {
+        #[cfg(uring)]
+        if is_coroutine() && uring::is_enabled() {
+            // write to the current file position
+            let mut writer = net_impl::SocketWrite::new(
+                self,
+                buf,
+                #[cfg(feature = "io_timeout")]
+                None,
+            );
+            yield_with_io(&writer, writer.is_coroutine);
+            return writer.done();
+        }
+
+        blocking::run(|| (&self.inner).write(buf))
+    }
+}
+
+impl fmt::Debug for File {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl From<std::fs::File> for File {
+    fn from(file: std::fs::File) -> Self {
+        File::from_std(file)
+    }
+}
+
+#[cfg(uring)]
+impl AsIoData for File {
+    fn as_io_data(&self) -> &IoData {
+        &self.io
+    }
+}
+
+impl Read for File {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_impl(buf)
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        (&*self).read_to_end(buf)
+    }
+}
+
+impl Read for &File {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.read_impl(buf)
+    }
+
+    fn read_to_end(&mut self, buf: &mut Vec<u8>) -> io::Result<usize> {
+        #[cfg(uring)]
+        if is_coroutine() && uring::is_enabled() {
+            let start = buf.len();
+            loop {
+                let len = buf.len();
+                buf.resize(len + 8192, 0);
+                let ret = self.read_impl(&mut buf[len..]);
+                buf.truncate(len + *ret.as_ref().unwrap_or(&0));
+                match ret {
+                    Ok(0) => return Ok(buf.len() - start),
+                    Ok(_) => {}
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+        // read all the remaining content in one blocking call
+        blocking::run(|| (&self.inner).read_to_end(buf))
+    }
+}
+
+impl Write for File {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_impl(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Write for &File {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.write_impl(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Seek for File {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        // seek never blocks on the disk
+        self.inner.seek(pos)
+    }
+}
+
+impl Seek for &File {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        (&self.inner).seek(pos)
+    }
+}
+
+#[cfg(unix)]
+mod unix_impl {
+    use super::File;
+    use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+
+    impl AsRawFd for File {
+        fn as_raw_fd(&self) -> RawFd {
+            self.inner.as_raw_fd()
+        }
+    }
+
+    impl AsFd for File {
+        fn as_fd(&self) -> BorrowedFd<'_> {
+            self.inner.as_fd()
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows_impl {
+    use super::File;
+    use std::os::windows::io::{AsHandle, AsRawHandle, BorrowedHandle, RawHandle};
+
+    impl AsRawHandle for File {
+        fn as_raw_handle(&self) -> RawHandle {
+            self.inner.as_raw_handle()
+        }
+    }
+
+    impl AsHandle for File {
+        fn as_handle(&self) -> BorrowedHandle<'_> {
+            self.inner.as_handle()
+        }
+    }
+}
+
+/// Options and flags which can be used to configure how a file is opened.
+///
+/// The platform specific options can be set on a `std::fs::OpenOptions`
+/// and then converted into this type.
+#[derive(Clone, Debug)]
+pub struct OpenOptions(std::fs::OpenOptions);
+
+impl OpenOptions {
+    /// Creates a blank new set of options ready for configuration.
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        OpenOptions(std::fs::OpenOptions::new())
+    }
+
+    /// Sets the option for read access.
+    pub fn read(&mut self, read: bool) -> &mut Self {
+        self.0.read(read);
+        self
+    }
+
+    /// Sets the option for write access.
+    pub fn write(&mut self, write: bool) -> &mut Self {
+        self.0.write(write);
+        self
+    }
+
+    /// Sets the option for the append mode.
+    pub fn append(&mut self, append: bool) -> &mut Self {
+        self.0.append(append);
+        self
+    }
+
+    /// Sets the option for truncating a previous file.
+    pub fn truncate(&mut self, truncate: bool) -> &mut Self {
+        self.0.truncate(truncate);
+        self
+    }
+
+    /// Sets the option to create a new file if it does not exist.
+    pub fn create(&mut self, create: bool) -> &mut Self {
+        self.0.create(create);
+        self
+    }
+
+    /// Sets the option to create a new file, failing if it already exists.
+    pub fn create_new(&mut self, create_new: bool) -> &mut Self {
+        self.0.create_new(create_new);
+        self
+    }
+
+    /// Opens a file at `path` with the options specified by `self`.
+    pub fn open<P: AsRef<Path>>(&self, path: P) -> io::Result<File> {
+        let path = path.as_ref();
+        blocking::run(|| self.0.open(path)).map(File::from_std)
+    }
+}
+
+impl From<std::fs::OpenOptions> for OpenOptions {
+    fn from(opts: std::fs::OpenOptions) -> Self {
+        OpenOptions(opts)
+    }
+}
+
+/// Iterator over the entries in a directory, returned by [`read_dir`].
+///
+/// The entries are read in batches from the thread pool.
+pub struct ReadDir {
+    inner: Option<std::fs::ReadDir>,
+    buf: VecDeque<io::Result<DirEntry>>,
+}
+
+impl Iterator for ReadDir {
+    type Item = io::Result<DirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.buf.is_empty() {
+            let inner = self.inner.as_mut()?;
+            let buf = &mut self.buf;
+            let end = blocking::run(|| {
+                buf.extend(inner.by_ref().take(READ_DIR_BATCH));
+                buf.len() < READ_DIR_BATCH
+            });
+            if end {
+                self.inner = None;
+            }
+        }
+        self.buf.pop_front()
+    }
+}
+
+impl fmt::Debug for ReadDir {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ReadDir").finish_non_exhaustive()
+    }
+}
+
+/// Returns an iterator over the entries within a directory.
+pub fn read_dir<P: AsRef<Path>>(path: P) -> io::Result<ReadDir> {
+    let path = path.as_ref();
+    let inner = blocking::run(|| std::fs::read_dir(path))?;
+    Ok(ReadDir {
+        inner: Some(inner),
+        buf: VecDeque::new(),
+    })
+}
+
+/// Reads the entire contents of a file into a bytes vector.
+pub fn read<P: AsRef<Path>>(path: P) -> io::Result<Vec<u8>> {
+    let path = path.as_ref();
+    blocking::run(|| std::fs::read(path))
+}
+
+/// Reads the entire contents of a file into a string.
+pub fn read_to_string<P: AsRef<Path>>(path: P) -> io::Result<String> {
+    let path = path.as_ref();
+    blocking::run(|| std::fs::read_to_string(path))
+}
+
+/// Writes a slice as the entire contents of a file.
+pub fn write<P: AsRef<Path>, C: AsRef<[u8]>>(path: P, contents: C) -> io::Result<()> {
+    let (path, contents) = (path.as_ref(), contents.as_ref());
+    blocking::run(|| std::fs::write(path, contents))
+}
+
+/// Copies the contents of one file to another, returns the bytes copied.
+pub fn copy<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> io::Result<u64> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    blocking::run(|| std::fs::copy(from, to))
+}
+
+/// Renames a file or directory to a new name.
+pub fn rename<P: AsRef<Path>, Q: AsRef<Path>>(from: P, to: Q) -> io::Result<()> {
+    let (from, to) = (from.as_ref(), to.as_ref());
+    blocking::run(|| std::fs::rename(from, to))
+}
+
+/// Creates a new hard link on the filesystem.
+pub fn hard_link<P: AsRef<Path>, Q: AsRef<Path>>(original: P, link: Q) -> io::Result<()> {
+    let (original, link) = (original.as_ref(), link.as_ref());
+    blocking::run(|| std::fs::hard_link(original, link))
+}
+
+/// Removes a file from the filesystem.
+pub fn remove_file<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let path = path.as_ref();
+    blocking::run(|| std::fs::remove_file(path))
+}
+
+/// Creates a new, empty directory.
+pub fn create_dir<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let path = path.as_ref();
+    blocking::run(|| std::fs::create_dir(path))
+}
+
+/// Recursively creates a directory and all of its missing parents.
+pub fn create_dir_all<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let path = path.as_ref();
+    blocking::run(|| std::fs::create_dir_all(path))
+}
+
+/// Removes an empty directory.
+pub fn remove_dir<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let path = path.as_ref();
+    blocking::run(|| std::fs::remove_dir(path))
+}
+
+/// Removes a directory after removing all its contents.
+pub fn remove_dir_all<P: AsRef<Path>>(path: P) -> io::Result<()> {
+    let path = path.as_ref();
+    blocking::run(|| std::fs::remove_dir_all(path))
+}
+
+/// Given a path, queries the file system to get its metadata.
+pub fn metadata<P: AsRef<Path>>(path: P) -> io::Result<Metadata> {
+    let path = path.as_ref();
+    blocking::run(|| std::fs::metadata(path))
+}
+
+/// Queries the metadata about a file without following symlinks.
+pub fn symlink_metadata<P: AsRef<Path>>(path: P) -> io::Result<Metadata> {
+    let path = path.as_ref();
+    blocking::run(|| std::fs::symlink_metadata(path))
+}
+
+/// Returns the canonical, absolute form of a path.
+pub fn canonicalize<P: AsRef<Path>>(path: P) -> io::Result<PathBuf> {
+    let path = path.as_ref();
+    blocking::run(|| std::fs::canonicalize(path))
+}
+
+/// Reads a symbolic link, returning the file that the link points to.
+pub fn read_link<P: AsRef<Path>>(path: P) -> io::Result<PathBuf> {
+    let path = path.as_ref();
+    blocking::run(|| std::fs::read_link(path))
+}
+
+/// Changes the permissions found on a file or a directory.
+pub fn set_permissions<P: AsRef<Path>>(path: P, perm: Permissions) -> io::Result<()> {
+    let path = path.as_ref();
+    blocking::run(|| std::fs::set_permissions(path, perm))
+}
+
+/// Returns `Ok(true)` if the path points at an existing entity.
+pub fn exists<P: AsRef<Path>>(path: P) -> io::Result<bool> {
+    let path = path.as_ref();
+    blocking::run(|| path.try_exists())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_read_write() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        let h = go!(move || {
+            let mut f = File::create_new(&path).unwrap();
+            f.write_all(b"hello may fs").unwrap();
+            f.sync_all().unwrap();
+            assert_eq!(f.metadata().unwrap().len(), 12);
+
+            f.seek(SeekFrom::Start(6)).unwrap();
+            let mut s = String::new();
+            f.read_to_string(&mut s).unwrap();
+            assert_eq!(s, "may fs");
+
+            f.set_len(5).unwrap();
+            drop(f);
+            read_to_string(&path).unwrap()
+        });
+        assert_eq!(h.join().unwrap(), "hello");
+    }
+
+    #[test]
+    fn dir_ops() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().to_owned();
+        let h = go!(move || {
+            let sub = root.join("a/b");
+            create_dir_all(&sub).unwrap();
+            for i in 0..40 {
+                write(sub.join(format!("{i}")), [i as u8]).unwrap();
+            }
+            rename(sub.join("0"), root.join("a/zero")).unwrap();
+            assert_eq!(read(root.join("a/zero")).unwrap(), [0]);
+            assert!(!exists(sub.join("0")).unwrap());
+
+            // more than one batch
+            let n = read_dir(&sub).unwrap().filter(|e| e.is_ok()).count();
+            remove_dir_all(root.join("a")).unwrap();
+            n
+        });
+        assert_eq!(h.join().unwrap(), 39);
+        assert!(!dir.path().join("a").exists());
+    }
+
+    #[test]
+    fn file_in_thread() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        write(&path, "thread").unwrap();
+        let mut f = OpenOptions::new().append(true).open(&path).unwrap();
+        f.write_all(b" context").unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "thread context");
+    }
+}
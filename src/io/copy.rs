@@ -0,0 +1,295 @@
+//! Copy data between the coroutine io objects
+
+use std::io::{self, Read, Write};
+use std::os::fd::AsRawFd;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::os::fd::{FromRawFd, OwnedFd};
+#[cfg(feature = "io_timeout")]
+use std::time::Duration;
+
+use super::{AsIoData, SplitIo, SplitReader, SplitWriter};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use crate::io::sys::net::{SocketSplice, SpliceEnd};
+
+// the buffer size used by the userspace copy
+const BUF_SIZE: usize = 64 * 1024;
+
+/// Moves up to `len` bytes from `r` to `w` with `splice(2)` without copying
+/// the data to the userspace.
+///
+/// One of them must be a pipe, like [`PipeReader`] and [`PipeWriter`]. The
+/// coroutine is blocked until some data is moved, returns 0 at the end of
+/// the input. The timeouts of `r` and `w` are not applied, use
+/// [`splice_timeout`] to limit the wait.
+///
+/// [`PipeReader`]: crate::os::unix::PipeReader
+/// [`PipeWriter`]: crate::os::unix::PipeWriter
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub fn splice<R: AsIoData, W: AsIoData>(r: &R, w: &W, len: usize) -> io::Result<usize> {
+    let input = SpliceEnd::Io(r.as_io_data());
+    let output = SpliceEnd::Io(w.as_io_data());
+    SocketSplice::new(
+        input,
+        output,
+        len,
+        #[cfg(feature = "io_timeout")]
+        None,
+    )
+    .done()
+}
+
+/// Same as [`splice`] but returns a `TimedOut` error if no data is moved
+/// within `timeout`.
+#[cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    feature = "io_timeout"
+))]
+pub fn splice_timeout<R: AsIoData, W: AsIoData>(
+    r: &R,
+    w: &W,
+    len: usize,
+    timeout: Duration,
+) -> io::Result<usize> {
+    let input = SpliceEnd::Io(r.as_io_data());
+    let output = SpliceEnd::Io(w.as_io_data());
+    SocketSplice::new(input, output, len, Some(timeout)).done()
+}
+
+/// Copies data in both directions between `a` and `b` until both of them
+/// reach the end.
+///
+/// The data from `a` to `b` is copied in a new scoped coroutine while the
+/// other direction is copied in the current context. When one side reaches
+/// the end, the write direction of the other side is shutdown to pass the
+/// end along. On Linux the data is moved by `splice(2)` through a pipe.
+/// The read and write timeouts of `a` and `b` are applied to the copy, a
+/// timeout stops both directions with the `TimedOut` error.
+///
+/// Returns the number of bytes copied from `a` to `b` and from `b` to `a`.
+///
+/// # Examples
+///
+/// ```no_run
+/// use may::net::{TcpListener, TcpStream};
+///
+/// let listener = TcpListener::bind("127.0.0.1:8080").unwrap();
+/// for client in listener.incoming() {
+///     let client = client.unwrap();
+///     may::go!(move || {
+///         let server = TcpStream::connect("127.0.0.1:80").unwrap();
+///         may::io::copy_bidirectional(client, server).ok();
+///     });
+/// }
+/// ```
+pub fn copy_bidirectional<A, B>(a: A, b: B) -> io::Result<(u64, u64)>
+where
+    A: SplitIo + Read + Write + AsIoData + AsRawFd + Send + Sync,
+    B: SplitIo + Read + Write + AsIoData + AsRawFd + Send + Sync,
+{
+    let (mut ar, mut aw) = a.split()?;
+    let (mut br, mut bw) = b.split()?;
+    let (a_fd, b_fd) = (ar.as_raw_fd(), br.as_raw_fd());
+
+    let (a_to_b, b_to_a) = crate::coroutine::scope(|s| {
+        let h = go!(s, || {
+            let ret = copy_half(&mut ar, &mut bw);
+            if ret.is_err() {
+                // unblock the other direction
+                shutdown(a_fd, libc::SHUT_RDWR);
+                shutdown(b_fd, libc::SHUT_RDWR);
+            }
+            ret
+        });
+        let ret = copy_half(&mut br, &mut aw);
+        if ret.is_err() {
+            shutdown(a_fd, libc::SHUT_RDWR);
+            shutdown(b_fd, libc::SHUT_RDWR);
+        }
+        (h.join(), ret)
+    });
+    Ok((a_to_b?, b_to_a?))
+}
+
+// the error is ignored, the fd may not be a socket
+fn shutdown(fd: std::os::fd::RawFd, how: libc::c_int) {
+    unsafe { libc::shutdown(fd, how) };
+}
+
+fn copy_half<R, W>(r: &mut SplitReader<R>, w: &mut SplitWriter<W>) -> io::Result<u64>
+where
+    R: SplitIo + AsIoData,
+    W: SplitIo + AsRawFd,
+{
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    let n = splice_copy(r, w)?;
+    #[cfg(not(any(target_os = "linux", target_os = "android")))]
+    let n = buf_copy(r, w)?;
+    // pass the end to the peer
+    w.flush()?;
+    shutdown(w.as_raw_fd(), libc::SHUT_WR);
+    Ok(n)
+}
+
+fn buf_copy<R: Read, W: Write>(r: &mut R, w: &mut W) -> io::Result<u64> {
+    let mut buf = vec![0; BUF_SIZE];
+    let mut total = 0;
+    loop {
+        let n = match r.read(&mut buf) {
+            Ok(0) => return Ok(total),
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        w.write_all(&buf[..n])?;
+        total += n as u64;
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn splice_copy<R, W>(r: &mut SplitReader<R>, w: &mut SplitWriter<W>) -> io::Result<u64>
+where
+    R: SplitIo + AsIoData,
+    W: SplitIo,
+{
+    let mut fds = [0; 2];
+    if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_NONBLOCK | libc::O_CLOEXEC) } != 0 {
+        return buf_copy(r, w);
+    }
+    let (pr, pw) = unsafe { (OwnedFd::from_raw_fd(fds[0]), OwnedFd::from_raw_fd(fds[1])) };
+
+    let mut total = 0;
+    loop {
+        // the pipe is always drained, so it's never the blocking end
+        let input = SpliceEnd::Io(r.as_io_data());
+        let n = match SocketSplice::new(
+            input,
+            SpliceEnd::Pipe(pw.as_raw_fd()),
+            BUF_SIZE,
+            #[cfg(feature = "io_timeout")]
+            r.inner().shared_read_timeout(),
+        )
+        .done()
+        {
+            Ok(0) => return Ok(total),
+            Ok(n) => n,
+            // the fd doesn't support splice
+            Err(e) if total == 0 && e.raw_os_error() == Some(libc::EINVAL) => {
+                return buf_copy(r, w)
+            }
+            Err(e) => return Err(e),
+        };
+
+        let mut left = n;
+        while left > 0 {
+            let output = SpliceEnd::Io(w.as_io_data());
+            left -= SocketSplice::new(
+                SpliceEnd::Pipe(pr.as_raw_fd()),
+                output,
+                left,
+                #[cfg(feature = "io_timeout")]
+                w.inner().shared_write_timeout(),
+            )
+            .done()?;
+        }
+        total += n as u64;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::{TcpListener, TcpStream};
+
+    #[test]
+    fn copy_bidirectional_proxy() {
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server_addr = server.local_addr().unwrap();
+        let proxy = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy_addr = proxy.local_addr().unwrap();
+
+        // echo server
+        let echo = go!(move || {
+            let (mut s, _) = server.accept().unwrap();
+            let mut buf = vec![];
+            s.read_to_end(&mut buf).unwrap();
+            s.write_all(&buf).unwrap();
+            buf.len()
+        });
+
+        let h = go!(move || {
+            let (client, _) = proxy.accept().unwrap();
+            let server = TcpStream::connect(server_addr).unwrap();
+            copy_bidirectional(client, server).unwrap()
+        });
+
+        let data = "proxy".repeat(100_000);
+        let mut w = TcpStream::connect(proxy_addr).unwrap();
+        let mut r = w.try_clone().unwrap();
+        let writer = go!(move || {
+            w.write_all(data.as_bytes()).unwrap();
+            w.shutdown(std::net::Shutdown::Write).unwrap();
+        });
+        let mut buf = String::new();
+        r.read_to_string(&mut buf).unwrap();
+        writer.join().unwrap();
+
+        assert_eq!(buf, "proxy".repeat(100_000));
+        assert_eq!(echo.join().unwrap(), buf.len());
+        assert_eq!(h.join().unwrap(), (buf.len() as u64, buf.len() as u64));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn splice_pipe_to_socket() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (reader, mut writer) = crate::os::unix::pipe().unwrap();
+
+        let h = go!(move || {
+            let (s, _) = listener.accept().unwrap();
+            let mut total = 0;
+            while total < 5 {
+                total += splice(&reader, &s, 5 - total).unwrap();
+            }
+            total
+        });
+
+        let mut s = TcpStream::connect(addr).unwrap();
+        writer.write_all(b"pipes").unwrap();
+        let mut buf = [0; 5];
+        s.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"pipes");
+        assert_eq!(h.join().unwrap(), 5);
+    }
+
+    #[cfg(all(
+        any(target_os = "linux", target_os = "android"),
+        feature = "io_timeout"
+    ))]
+    #[test]
+    fn splice_timeout_empty_pipe() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let s = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (reader, _writer) = crate::os::unix::pipe().unwrap();
+
+        let err = splice_timeout(&reader, &s, 5, Duration::from_millis(50)).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+
+    #[cfg(feature = "io_timeout")]
+    #[test]
+    fn copy_bidirectional_timeout() {
+        let server = TcpListener::bind("127.0.0.1:0").unwrap();
+        let proxy = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = TcpStream::connect(server.local_addr().unwrap()).unwrap();
+        // the client is kept alive but sends nothing
+        let _client = TcpStream::connect(proxy.local_addr().unwrap()).unwrap();
+        let (client, _) = proxy.accept().unwrap();
+        client
+            .set_read_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+
+        let err = copy_bidirectional(client, server).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+    }
+}
@@ -11,8 +11,12 @@ pub(crate) mod sys;
 // export the generic IO wrapper
 pub mod co_io_err;
 
+#[cfg(unix)]
+mod copy;
 mod event_loop;
 pub(crate) mod split_io;
+#[cfg(unix)]
+mod stdio;
 pub(crate) mod thread;
 
 use std::ops::Deref;
@@ -24,13 +28,34 @@ pub use self::sys::co_io::CoIo;
 #[cfg(unix)]
 pub use self::sys::wait_io::{WaitIo, WaitIoWaker};
 pub use self::sys::IoData;
+#[cfg(unix)]
+pub use copy::copy_bidirectional;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use copy::splice;
+#[cfg(all(
+    any(target_os = "linux", target_os = "android"),
+    feature = "io_timeout"
+))]
+pub use copy::splice_timeout;
+#[cfg(unix)]
+pub(crate) use self::sys::add_socket_to;
 pub(crate) use self::sys::{add_socket, net, Selector};
-pub use split_io::{SplitIo, SplitReader, SplitWriter};
+pub use split_io::{ReuniteError, SplitIo, SplitReader, SplitWriter};
+#[cfg(unix)]
+pub use stdio::{
+    stderr, stdin, stdout, Stderr, StderrLock, Stdin, StdinLock, Stdout, StdoutLock,
+};
 
 pub trait AsIoData {
     fn as_io_data(&self) -> &IoData;
 }
 
+impl AsIoData for IoData {
+    fn as_io_data(&self) -> &IoData {
+        self
+    }
+}
+
 // an option type that implement deref
 struct OptionCell<T>(Option<T>);
 
@@ -1,54 +1,117 @@
 //! Split io object into read/write part
 //!
+//! The two parts share the same io object, so there is only one fd and one
+//! registration in the selector. The write part waits on its own io data
+//! that is woken up by the write readiness of the fd, so the two parts
+//! could be used in different coroutines at the same time.
 
-use std::io::{self, Read, Write};
+use std::fmt;
+use std::io::{self, IoSlice, IoSliceMut, Read, Write};
 #[cfg(unix)]
 use std::os::fd::{AsRawFd, RawFd};
+use std::sync::Arc;
+#[cfg(feature = "io_timeout")]
+use std::time::Duration;
 
-use super::AsIoData;
+use super::{AsIoData, IoData};
 
+// the io object that could be read and written through a shared reference,
+// the write part passes in the io data that it waits on. it's public but
+// not exported, so that `SplitIo` is sealed and enough for the split parts
+pub trait SharedIo {
+    fn shared_read(&self, buf: &mut [u8]) -> io::Result<usize>;
+
+    fn shared_read_vectored(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize>;
+
+    fn shared_write(&self, io: &IoData, buf: &[u8]) -> io::Result<usize>;
+
+    fn shared_write_vectored(&self, io: &IoData, bufs: &[IoSlice<'_>]) -> io::Result<usize>;
+
+    #[cfg(feature = "io_timeout")]
+    fn shared_read_timeout(&self) -> Option<Duration>;
+
+    #[cfg(feature = "io_timeout")]
+    fn shared_write_timeout(&self) -> Option<Duration>;
+}
+
+/// The read part of a split io object
 pub struct SplitReader<T> {
-    inner: T,
+    inner: Arc<T>,
 }
 
 impl<T> SplitReader<T> {
-    pub(crate) fn new(io: T) -> Self {
-        SplitReader { inner: io }
-    }
-
     pub fn inner(&self) -> &T {
         &self.inner
     }
 
-    pub fn inner_mut(&mut self) -> &mut T {
-        &mut self.inner
+    /// Puts the two parts back together to get the original io object.
+    ///
+    /// Returns an error with the two parts if they are not split from
+    /// the same io object.
+    pub fn reunite(self, writer: SplitWriter<T>) -> Result<T, ReuniteError<T>> {
+        if !Arc::ptr_eq(&self.inner, &writer.inner) {
+            return Err(ReuniteError(self, writer));
+        }
+        drop(writer);
+        Ok(Arc::into_inner(self.inner).expect("the split parts are not unique"))
     }
 }
 
+/// The write part of a split io object
 pub struct SplitWriter<T> {
-    inner: T,
+    inner: Arc<T>,
+    // the io data that the writer waits on
+    io: IoData,
 }
 
 impl<T> SplitWriter<T> {
-    pub(crate) fn new(io: T) -> Self {
-        SplitWriter { inner: io }
-    }
-
     pub fn inner(&self) -> &T {
         &self.inner
     }
 
-    pub fn inner_mut(&mut self) -> &mut T {
-        &mut self.inner
+    /// Puts the two parts back together to get the original io object.
+    ///
+    /// Returns an error with the two parts if they are not split from
+    /// the same io object.
+    pub fn reunite(self, reader: SplitReader<T>) -> Result<T, ReuniteError<T>> {
+        reader.reunite(self)
     }
 }
 
-impl<T> AsIoData for SplitWriter<T>
-where
-    T: AsIoData,
-{
+/// split the io into the read part and the write part that share the io,
+/// the write part waits on the given io data
+pub(crate) fn split<T>(io: T, writer_io: IoData) -> (SplitReader<T>, SplitWriter<T>) {
+    let inner = Arc::new(io);
+    let reader = SplitReader {
+        inner: inner.clone(),
+    };
+    let writer = SplitWriter {
+        inner,
+        io: writer_io,
+    };
+    (reader, writer)
+}
+
+/// Error returned by `reunite` when the two parts are not from the same io
+pub struct ReuniteError<T>(pub SplitReader<T>, pub SplitWriter<T>);
+
+impl<T> fmt::Debug for ReuniteError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ReuniteError").finish_non_exhaustive()
+    }
+}
+
+impl<T> fmt::Display for ReuniteError<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "tried to reunite parts that are not from the same io")
+    }
+}
+
+impl<T> std::error::Error for ReuniteError<T> {}
+
+impl<T> AsIoData for SplitWriter<T> {
     fn as_io_data(&self) -> &super::IoData {
-        self.inner.as_io_data()
+        &self.io
     }
 }
 
@@ -81,25 +144,35 @@ where
     }
 }
 
-impl<T: Read> Read for SplitReader<T> {
+impl<T: SplitIo> Read for SplitReader<T> {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
-        self.inner.read(buf)
+        self.inner.shared_read(buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        self.inner.shared_read_vectored(bufs)
     }
 }
 
-impl<T: Write> Write for SplitWriter<T> {
+impl<T: SplitIo> Write for SplitWriter<T> {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
-        self.inner.write(buf)
+        self.inner.shared_write(&self.io, buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.inner.shared_write_vectored(&self.io, bufs)
     }
 
     fn flush(&mut self) -> io::Result<()> {
-        self.inner.flush()
+        Ok(())
     }
 }
 
 /// This is trait that split an io obj into two parts
 /// one is for read operation, another is for write operation
-pub trait SplitIo {
+///
+/// This trait is sealed and implemented by the `may` streams.
+pub trait SplitIo: SharedIo {
     /// split the io into read and write part
     fn split(self) -> io::Result<(SplitReader<Self>, SplitWriter<Self>)>
     where
@@ -0,0 +1,354 @@
+//! Coroutine-aware standard streams
+//!
+//! The O_NONBLOCK flag is shared by all the fds that refer to the same
+//! file description, setting it on the inherited stdio would break the
+//! blocking users such as `println!`. So on Linux a pipe is reopened from
+//! `/proc/self/fd` to get a private file description that can be safely
+//! registered to the selector. The other kinds of stdio, like tty and
+//! regular file, are read and written in the blocking thread pool.
+
+use std::fmt;
+use std::io::{self, BufRead, BufReader, LineWriter, Read, Write};
+use std::os::fd::{AsRawFd, RawFd};
+use std::sync::OnceLock;
+
+use crate::blocking;
+use crate::io::CoIo;
+use crate::sync::{Mutex, MutexGuard};
+
+// the raw standard stream fd that is never closed
+#[derive(Debug)]
+struct StdFd(RawFd);
+
+impl Read for StdFd {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = unsafe { libc::read(self.0, buf.as_mut_ptr().cast(), buf.len()) };
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(n as usize)
+    }
+}
+
+impl Write for StdFd {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = unsafe { libc::write(self.0, buf.as_ptr().cast(), buf.len()) };
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(n as usize)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+enum Raw {
+    // the private nonblocking file description
+    Co(CoIo<std::fs::File>),
+    // done in the blocking thread pool
+    Blocking(StdFd),
+    // done in place, used when the process is exiting
+    Exit(StdFd),
+}
+
+impl Raw {
+    fn new(fd: RawFd, read: bool) -> Raw {
+        #[cfg(target_os = "linux")]
+        if let Some(io) = reopen(fd, read) {
+            return Raw::Co(io);
+        }
+        Raw::Blocking(StdFd(fd))
+    }
+}
+
+// reopen the pipe with a new file description
+#[cfg(target_os = "linux")]
+fn reopen(fd: RawFd, read: bool) -> Option<CoIo<std::fs::File>> {
+    use std::fs::OpenOptions;
+    use std::os::unix::fs::FileTypeExt;
+
+    let path = format!("/proc/self/fd/{fd}");
+    let file_type = std::fs::metadata(&path).ok()?.file_type();
+    if !file_type.is_fifo() {
+        return None;
+    }
+    let file = OpenOptions::new()
+        .read(read)
+        .write(!read)
+        .open(&path)
+        .ok()?;
+    CoIo::new(file).ok()
+}
+
+impl Read for Raw {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            Raw::Co(io) => io.read(buf),
+            Raw::Blocking(fd) => blocking::run(|| fd.read(buf)),
+            Raw::Exit(fd) => fd.read(buf),
+        }
+    }
+}
+
+impl Write for Raw {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Raw::Co(io) => io.write(buf),
+            Raw::Blocking(fd) => blocking::run(|| fd.write(buf)),
+            Raw::Exit(fd) => fd.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn lock<T>(m: &Mutex<T>) -> MutexGuard<'_, T> {
+    // the poison is ignored like std
+    m.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// A handle to the standard input stream of the process.
+///
+/// The handle is shared by all the coroutines and buffered, the `lock`
+/// method gives exclusive access with the `BufRead` methods.
+pub struct Stdin {
+    inner: &'static Mutex<BufReader<Raw>>,
+}
+
+/// A locked reference to the `Stdin` handle.
+pub struct StdinLock<'a> {
+    inner: MutexGuard<'a, BufReader<Raw>>,
+}
+
+/// Constructs a new handle to the standard input of the current process.
+pub fn stdin() -> Stdin {
+    static INSTANCE: OnceLock<Mutex<BufReader<Raw>>> = OnceLock::new();
+    Stdin {
+        inner: INSTANCE
+            .get_or_init(|| Mutex::new(BufReader::new(Raw::new(libc::STDIN_FILENO, true)))),
+    }
+}
+
+impl Stdin {
+    /// Locks this handle to the standard input stream.
+    pub fn lock(&self) -> StdinLock<'static> {
+        StdinLock {
+            inner: lock(self.inner),
+        }
+    }
+
+    /// Locks this handle and reads a line of input into the buffer.
+    pub fn read_line(&self, buf: &mut String) -> io::Result<usize> {
+        self.lock().read_line(buf)
+    }
+}
+
+impl fmt::Debug for Stdin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Stdin").finish_non_exhaustive()
+    }
+}
+
+impl Read for Stdin {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.lock().read(buf)
+    }
+}
+
+impl Read for StdinLock<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.inner.read(buf)
+    }
+}
+
+impl BufRead for StdinLock<'_> {
+    fn fill_buf(&mut self) -> io::Result<&[u8]> {
+        self.inner.fill_buf()
+    }
+
+    fn consume(&mut self, amt: usize) {
+        self.inner.consume(amt)
+    }
+}
+
+impl AsRawFd for Stdin {
+    fn as_raw_fd(&self) -> RawFd {
+        libc::STDIN_FILENO
+    }
+}
+
+macro_rules! output {
+    ($(#[$attr:meta])* $name:ident, $lock:ident, $ctor:ident, $instance:ident, $fd:path, $buf:ty, $new:expr) => {
+        $(#[$attr])*
+        pub struct $name {
+            inner: &'static Mutex<$buf>,
+        }
+
+        /// A locked reference to the output handle.
+        pub struct $lock<'a> {
+            inner: MutexGuard<'a, $buf>,
+        }
+
+        static $instance: OnceLock<Mutex<$buf>> = OnceLock::new();
+
+        #[doc = concat!("Constructs a new handle to the ", stringify!($ctor), " of the current process.")]
+        pub fn $ctor() -> $name {
+            $name {
+                inner: $instance.get_or_init(|| Mutex::new($new(Raw::new($fd, false)))),
+            }
+        }
+
+        impl $name {
+            /// Locks this handle to the output stream.
+            pub fn lock(&self) -> $lock<'static> {
+                $lock {
+                    inner: lock(self.inner),
+                }
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct(stringify!($name)).finish_non_exhaustive()
+            }
+        }
+
+        impl Write for $name {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                (&*self).write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                (&*self).flush()
+            }
+
+            fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+                (&*self).write_all(buf)
+            }
+        }
+
+        impl Write for &$name {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.lock().write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                self.lock().flush()
+            }
+
+            fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
+                // hold the lock to not interleave with other writers
+                self.lock().write_all(buf)
+            }
+        }
+
+        impl Write for $lock<'_> {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.inner.write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                self.inner.flush()
+            }
+        }
+
+        impl AsRawFd for $name {
+            fn as_raw_fd(&self) -> RawFd {
+                $fd
+            }
+        }
+    };
+}
+
+output!(
+    /// A handle to the standard output stream of the process.
+    ///
+    /// The output is line-buffered like `std::io::Stdout`. The data left
+    /// in the buffer is flushed when the process exits normally.
+    Stdout,
+    StdoutLock,
+    stdout,
+    STDOUT,
+    libc::STDOUT_FILENO,
+    LineWriter<Raw>,
+    new_stdout
+);
+
+fn new_stdout(raw: Raw) -> LineWriter<Raw> {
+    // only called once, the failure just leaves the buffer unflushed
+    unsafe { libc::atexit(flush_stdout) };
+    LineWriter::new(raw)
+}
+
+// flush the buffered stdout when the process exits
+extern "C" fn flush_stdout() {
+    let Some(m) = STDOUT.get() else { return };
+    // the holder of the lock would never release it
+    let Ok(mut w) = m.try_lock() else { return };
+    // the exiting context may not run the selector or the blocking pool
+    *w.get_mut() = Raw::Exit(StdFd(libc::STDOUT_FILENO));
+    let _ = w.flush();
+}
+
+output!(
+    /// A handle to the standard error stream of the process.
+    ///
+    /// The output is not buffered.
+    Stderr,
+    StderrLock,
+    stderr,
+    STDERR,
+    libc::STDERR_FILENO,
+    Raw,
+    std::convert::identity
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::os::unix::pipe;
+
+    #[test]
+    fn stdio_raw_kind() {
+        let file = tempfile::tempfile().unwrap();
+        assert!(matches!(
+            Raw::new(file.as_raw_fd(), false),
+            Raw::Blocking(_)
+        ));
+
+        let (reader, mut writer) = pipe().unwrap();
+        let mut raw = Raw::new(reader.as_raw_fd(), true);
+        #[cfg(target_os = "linux")]
+        assert!(matches!(raw, Raw::Co(_)));
+        let h = go!(move || {
+            let mut buf = [0; 5];
+            raw.read_exact(&mut buf).unwrap();
+            buf
+        });
+        writer.write_all(b"stdio").unwrap();
+        assert_eq!(&h.join().unwrap(), b"stdio");
+    }
+
+    #[test]
+    fn stdout_flush_at_exit() {
+        if std::env::var_os("MAY_STDOUT_EXIT").is_some() {
+            let h = go!(|| stdout().write_all(b"no newline").unwrap());
+            h.join().unwrap();
+            std::process::exit(0);
+        }
+
+        let output = std::process::Command::new(std::env::current_exe().unwrap())
+            .args(["--exact", "io::stdio::tests::stdout_flush_at_exit"])
+            .env("MAY_STDOUT_EXIT", "1")
+            .output()
+            .unwrap();
+        assert!(output.status.success());
+        let out = String::from_utf8_lossy(&output.stdout);
+        assert!(out.contains("no newline"), "{out}");
+    }
+}
@@ -24,6 +24,12 @@ impl CancelIo for CancelIoImpl {
 
     unsafe fn cancel(&self) -> Option<std::io::Result<()>> {
         if let Some(e) = self.0.take() {
+            // the kernel may still use the buffers of the in-flight
+            // io_uring request, resume the coroutine on the completion
+            #[cfg(uring)]
+            if get_scheduler().get_selector().cancel_uring(&e) {
+                return Some(Ok(()));
+            }
             if let Some(co) = e.co.take() {
                 get_scheduler().schedule(co);
                 return Some(Ok(()));
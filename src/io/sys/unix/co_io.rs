@@ -3,7 +3,7 @@
 //! context with non blocking operations
 //!
 
-use std::io::{self, Read, Write};
+use std::io::{self, IoSlice, IoSliceMut, Read, Write};
 use std::os::unix::io::{AsRawFd, IntoRawFd, RawFd};
 #[cfg(feature = "io_timeout")]
 use std::time::Duration;
@@ -12,11 +12,13 @@ use self::io_impl::co_io_err::Error;
 use self::io_impl::net as net_impl;
 use super::from_nix_error;
 use crate::io as io_impl;
+use crate::io::split_io::SharedIo;
 #[cfg(feature = "io_timeout")]
 use crate::sync::atomic_dur::AtomicDuration;
 use crate::yield_now::yield_with_io;
 
 use nix::sys::socket::{recv, MsgFlags};
+use nix::unistd::{read, write};
 
 fn set_nonblocking<T: AsRawFd>(fd: &T, nb: bool) -> io::Result<()> {
     unsafe {
@@ -138,6 +140,18 @@ impl<T: AsRawFd> CoIo<T> {
         Ok(self.write_timeout.get())
     }
 
+    // the read timeout for the io ops, it never fails
+    #[cfg(feature = "io_timeout")]
+    pub(crate) fn get_read_timeout(&self) -> Option<Duration> {
+        self.read_timeout.get()
+    }
+
+    // the write timeout for the io ops, it never fails
+    #[cfg(feature = "io_timeout")]
+    pub(crate) fn get_write_timeout(&self) -> Option<Duration> {
+        self.write_timeout.get()
+    }
+
     /// set read timeout
     #[cfg(feature = "io_timeout")]
     pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
@@ -208,6 +222,32 @@ impl<T: AsRawFd + Read> Read for CoIo<T> {
         yield_with_io(&reader, reader.is_coroutine);
         reader.done()
     }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        self.io.reset();
+        // this is an earlier return try for nonblocking read
+        match net_impl::readv(self.as_raw_fd(), bufs) {
+            Ok(n) => return Ok(n),
+            Err(e) => {
+                // raw_os_error is faster than kind
+                let raw_err = e.raw_os_error();
+                if raw_err == Some(libc::EAGAIN) || raw_err == Some(libc::EWOULDBLOCK) {
+                    // do nothing here
+                } else {
+                    return Err(e);
+                }
+            }
+        }
+
+        let mut reader = net_impl::SocketReadVectored::new(
+            self,
+            bufs,
+            #[cfg(feature = "io_timeout")]
+            self.read_timeout.get(),
+        );
+        yield_with_io(&reader, reader.is_coroutine);
+        reader.done()
+    }
 }
 
 impl<T: AsRawFd + Write> Write for CoIo<T> {
@@ -237,11 +277,154 @@ impl<T: AsRawFd + Write> Write for CoIo<T> {
         writer.done()
     }
 
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.io.reset();
+        // this is an earlier return try for nonblocking write
+        match net_impl::writev(self.as_raw_fd(), bufs) {
+            Ok(n) => return Ok(n),
+            Err(e) => {
+                // raw_os_error is faster than kind
+                let raw_err = e.raw_os_error();
+                if raw_err == Some(libc::EAGAIN) || raw_err == Some(libc::EWOULDBLOCK) {
+                    // do nothing here
+                } else {
+                    return Err(e);
+                }
+            }
+        }
+
+        let mut writer = net_impl::SocketWriteVectored::new(
+            self,
+            bufs,
+            #[cfg(feature = "io_timeout")]
+            self.write_timeout.get(),
+        );
+        yield_with_io(&writer, writer.is_coroutine);
+        writer.done()
+    }
+
     fn flush(&mut self) -> io::Result<()> {
         self.inner.flush()
     }
 }
 
+// the split parts do the io on the fd directly
+impl<T: AsRawFd> SharedIo for CoIo<T> {
+    fn shared_read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.io.reset();
+        // this is an earlier return try for nonblocking read
+        match read(&self.io, buf) {
+            Ok(n) => return Ok(n),
+            Err(e) => {
+                if e == nix::errno::Errno::EAGAIN {
+                    // do nothing
+                } else {
+                    return Err(from_nix_error(e));
+                }
+            }
+        }
+
+        let mut reader = net_impl::SocketRead::new(
+            self,
+            buf,
+            #[cfg(feature = "io_timeout")]
+            self.read_timeout.get(),
+        );
+        yield_with_io(&reader, reader.is_coroutine);
+        reader.done()
+    }
+
+    fn shared_read_vectored(&self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        self.io.reset();
+        // this is an earlier return try for nonblocking read
+        match net_impl::readv(self.as_raw_fd(), bufs) {
+            Ok(n) => return Ok(n),
+            Err(e) => {
+                // raw_os_error is faster than kind
+                let raw_err = e.raw_os_error();
+                if raw_err == Some(libc::EAGAIN) || raw_err == Some(libc::EWOULDBLOCK) {
+                    // do nothing here
+                } else {
+                    return Err(e);
+                }
+            }
+        }
+
+        let mut reader = net_impl::SocketReadVectored::new(
+            self,
+            bufs,
+            #[cfg(feature = "io_timeout")]
+            self.read_timeout.get(),
+        );
+        yield_with_io(&reader, reader.is_coroutine);
+        reader.done()
+    }
+
+    fn shared_write(&self, io: &io_impl::IoData, buf: &[u8]) -> io::Result<usize> {
+        io.reset();
+        // this is an earlier return try for nonblocking write
+        match write(io, buf) {
+            Ok(n) => return Ok(n),
+            Err(e) => {
+                if e == nix::errno::Errno::EAGAIN {
+                    // do nothing
+                } else {
+                    return Err(from_nix_error(e));
+                }
+            }
+        }
+
+        let mut writer = net_impl::SocketWrite::new(
+            io,
+            buf,
+            #[cfg(feature = "io_timeout")]
+            self.write_timeout.get(),
+        );
+        yield_with_io(&writer, writer.is_coroutine);
+        writer.done()
+    }
+
+    fn shared_write_vectored(
+        &self,
+        io: &io_impl::IoData,
+        bufs: &[IoSlice<'_>],
+    ) -> io::Result<usize> {
+        io.reset();
+        // this is an earlier return try for nonblocking write
+        match net_impl::writev(io.fd, bufs) {
+            Ok(n) => return Ok(n),
+            Err(e) => {
+                // raw_os_error is faster than kind
+                let raw_err = e.raw_os_error();
+                if raw_err == Some(libc::EAGAIN) || raw_err == Some(libc::EWOULDBLOCK) {
+                    // do nothing here
+                } else {
+                    return Err(e);
+                }
+            }
+        }
+
+        let mut writer = net_impl::SocketWriteVectored::new(
+            io,
+            bufs,
+            #[cfg(feature = "io_timeout")]
+            self.write_timeout.get(),
+        );
+        yield_with_io(&writer, writer.is_coroutine);
+        writer.done()
+    }
+
+    #[cfg(feature = "io_timeout")]
+    fn shared_read_timeout(&self) -> Option<Duration> {
+        self.read_timeout.get()
+    }
+
+    #[cfg(feature = "io_timeout")]
+    fn shared_write_timeout(&self) -> Option<Duration> {
+        self.write_timeout.get()
+    }
+}
+
 // impl<'a, T: AsRawFd + Read> Read for &'a CoIo<T> {
 //     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
 //         let s = unsafe { &mut *(*self as *const _ as *mut _) };
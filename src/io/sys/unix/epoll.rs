@@ -1,35 +1,111 @@
 use std::io;
 use std::os::fd::AsFd;
-use std::os::fd::BorrowedFd;
-use std::sync::atomic::Ordering;
+use std::os::fd::{BorrowedFd, RawFd};
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-#[cfg(feature = "io_timeout")]
 use std::time::Duration;
 
+#[cfg(uring)]
+use super::uring::{uring_error, Ring, IGNORE_DATA, NO_RING};
 use super::{from_nix_error, EventData, IoData};
 #[cfg(feature = "io_timeout")]
 use super::{timeout_handler, TimerList};
+use crate::config::config;
+#[cfg(uring)]
+use crate::coroutine_impl::CoroutineImpl;
 use crate::scheduler::Scheduler;
+#[cfg(uring)]
+use crate::scheduler::WORKER_ID;
 #[cfg(feature = "io_timeout")]
 use crate::timeout_list::now;
+#[cfg(uring)]
+use crate::yield_now::set_co_para;
 
 use may_queue::mpsc::Queue;
 use nix::sys::epoll::*;
 use nix::sys::eventfd::*;
+use nix::sys::time::TimeSpec;
+use nix::sys::timerfd::{ClockId, Expiration, TimerFd, TimerFlags, TimerSetTimeFlags};
 use nix::unistd::{read, write};
 use smallvec::SmallVec;
 
 pub type SysEvent = EpollEvent;
 
+// the epoll data of the high resolution timer, never a valid EventData
+const TIMER_DATA: u64 = 1;
+// the epoll data of the io_uring completions, never a valid EventData
+#[cfg(uring)]
+const RING_DATA: u64 = 2;
+
+// the events that the write half of a split io is interested in
+const WRITE_EVENTS: usize = (libc::EPOLLOUT | libc::EPOLLERR | libc::EPOLLHUP) as usize;
+
+// set the io flag and resume the coroutine that waits on the event data
+#[inline]
+fn wakeup(data: &EventData, events: usize, scheduler: &Scheduler, id: usize) {
+    data.io_flag.fetch_or(events, Ordering::Release);
+
+    // the coroutine is waiting for the io_uring completion
+    #[cfg(uring)]
+    if data.uring_id.load(Ordering::Acquire) != NO_RING {
+        return;
+    }
+
+    // first check the atomic co, this may be grab by the worker first
+    let co = match data.co.take() {
+        Some(co) => co,
+        None => return,
+    };
+
+    // it's safe to remove the timer since we are running the timer_list in the same thread
+    #[cfg(feature = "io_timeout")]
+    data.timer.borrow_mut().take().map(|h| {
+        // tell the timer handler not to cancel the io
+        // it's not always true that you can really remove the timer entry
+        h.with_mut_data(|value| value.event_data = std::ptr::null_mut());
+        h.remove()
+    });
+
+    #[cfg(feature = "work_steal")]
+    scheduler.schedule_with_id(co, id);
+    #[cfg(not(feature = "work_steal"))]
+    {
+        let _ = (scheduler, id);
+        crate::coroutine_impl::run_coroutine(co);
+    }
+}
+
 struct SingleSelector {
     epoll: Epoll,
     evfd: EventFd,
+    // used for the sub-millisecond wait timeout
+    timerfd: Option<TimerFd>,
+    // the timerfd is armed and not expired yet
+    timer_armed: AtomicBool,
+    // the completion based io requests
+    #[cfg(uring)]
+    ring: Option<Ring>,
     #[cfg(feature = "io_timeout")]
     timer_list: TimerList,
     free_ev: Queue<Arc<EventData>>,
 }
 
 impl SingleSelector {
+    // the timeout can't be waited by epoll_wait in milliseconds, that is
+    // it's less than 1ms or there are timers not aligned to 1ms
+    fn need_timerfd(&self, scheduler: &Scheduler, id: usize, timeout: u64) -> bool {
+        if timeout == 0 {
+            return false;
+        }
+        if timeout < 1_000_000 || scheduler.has_fine_timers(id) {
+            return true;
+        }
+        #[cfg(feature = "io_timeout")]
+        return self.timer_list.has_fine_timers();
+        #[cfg(not(feature = "io_timeout"))]
+        false
+    }
+
     pub fn new() -> io::Result<Self> {
         // wakeup data is 0
         let info = EpollEvent::new(EpollFlags::EPOLLIN, 0);
@@ -40,9 +116,37 @@ impl SingleSelector {
         // add the eventfd to the epfd
         epoll.add(evfd.as_fd(), info)?;
 
+        let timerfd = if config().get_high_res_timer() {
+            let flags = TimerFlags::TFD_NONBLOCK | TimerFlags::TFD_CLOEXEC;
+            let timerfd = TimerFd::new(ClockId::CLOCK_MONOTONIC, flags)?;
+            let info = EpollEvent::new(EpollFlags::EPOLLIN, TIMER_DATA);
+            epoll.add(timerfd.as_fd(), info)?;
+            Some(timerfd)
+        } else {
+            None
+        };
+
+        #[cfg(uring)]
+        let ring = match Ring::new() {
+            Ok(ring) => {
+                // the ring fd is readable when there are completions
+                let info = EpollEvent::new(EpollFlags::EPOLLIN, RING_DATA);
+                epoll.add(ring.as_fd(), info)?;
+                Some(ring)
+            }
+            Err(e) => {
+                warn!("io_uring is not available, fallback to epoll, err={e}");
+                None
+            }
+        };
+
         Ok(SingleSelector {
             epoll,
             evfd,
+            timerfd,
+            timer_armed: AtomicBool::new(false),
+            #[cfg(uring)]
+            ring,
             free_ev: Queue::new(),
             #[cfg(feature = "io_timeout")]
             timer_list: TimerList::new(),
@@ -53,12 +157,17 @@ impl SingleSelector {
 pub(crate) struct Selector {
     // 128 should be fine for max io threads
     vec: SmallVec<[SingleSelector; 128]>,
+    // all the selectors have a ring
+    #[cfg(uring)]
+    has_uring: bool,
 }
 
 impl Selector {
     pub fn new(io_workers: usize) -> io::Result<Self> {
         let mut s = Selector {
             vec: SmallVec::new(),
+            #[cfg(uring)]
+            has_uring: false,
         };
 
         for _ in 0..io_workers {
@@ -66,6 +175,11 @@ impl Selector {
             s.vec.push(ss);
         }
 
+        #[cfg(uring)]
+        {
+            s.has_uring = s.vec.iter().all(|ss| ss.ring.is_some());
+        }
+
         Ok(s)
     }
 
@@ -75,22 +189,54 @@ impl Selector {
         scheduler: &Scheduler,
         id: usize,
         events: &mut [SysEvent],
-        _timeout: Option<u64>,
+        timeout: Option<u64>,
     ) -> io::Result<Option<u64>> {
         assert!(id < self.vec.len());
-        #[cfg(feature = "io_timeout")]
-        let timeout_ms = _timeout
-            .map(|to| EpollTimeout::try_from(to.div_ceil(1_000_000)).unwrap())
-            .unwrap_or(EpollTimeout::NONE);
-        #[cfg(not(feature = "io_timeout"))]
-        let timeout_ms = EpollTimeout::NONE;
-        // info!("select; timeout={:?}", timeout_ms);
-
         let single_selector = &self.vec[id];
         let epoll = &single_selector.epoll;
 
+        let timeout_ms = match (&single_selector.timerfd, timeout) {
+            // epoll_wait only support milliseconds, use the timerfd instead
+            (Some(timerfd), Some(to)) if single_selector.need_timerfd(scheduler, id, to) => {
+                let expire = TimeSpec::from_duration(Duration::from_nanos(to));
+                timerfd
+                    .set(Expiration::OneShot(expire), TimerSetTimeFlags::empty())
+                    .map_err(from_nix_error)?;
+                single_selector.timer_armed.store(true, Ordering::Relaxed);
+                EpollTimeout::NONE
+            }
+            (timerfd, _) => {
+                // the earlier deadline is gone, don't wake up for it
+                if let Some(timerfd) = timerfd {
+                    if single_selector.timer_armed.swap(false, Ordering::Relaxed) {
+                        timerfd.unset().map_err(from_nix_error)?;
+                    }
+                }
+                // the longer timeout is clamped, the timers are checked again after the wakeup
+                timeout
+                    .map(|to| {
+                        EpollTimeout::try_from(to.div_ceil(1_000_000)).unwrap_or(EpollTimeout::MAX)
+                    })
+                    .unwrap_or(EpollTimeout::NONE)
+            }
+        };
+        // info!("select; timeout={:?}", timeout_ms);
+
+        // submit all the io requests queued since the last select
+        #[cfg(uring)]
+        if let Some(ring) = &single_selector.ring {
+            if let Err(e) = ring.submit() {
+                error!("io_uring submit failed, err={e}");
+            }
+        }
+
         // Wait for epoll events for at most timeout_ms milliseconds
-        let n = epoll.wait(events, timeout_ms)?;
+        let n = match epoll.wait(events, timeout_ms) {
+            Ok(n) => n,
+            // interrupted by a signal or the io_uring task work
+            Err(nix::errno::Errno::EINTR) => 0,
+            Err(e) => return Err(e.into()),
+        };
         // println!("epoll_wait = {}", n);
 
         // collect coroutines
@@ -104,34 +250,60 @@ impl Selector {
                 scheduler.collect_global(id);
                 continue;
             }
-            let data = unsafe { &mut *(event.data() as *mut EventData) };
+            if event.data() == TIMER_DATA {
+                // clear the expired timerfd, the timers are checked later
+                if let Some(timerfd) = &single_selector.timerfd {
+                    read(timerfd.as_fd(), &mut [0u8; 8]).ok();
+                    single_selector.timer_armed.store(false, Ordering::Relaxed);
+                }
+                continue;
+            }
+            #[cfg(uring)]
+            if event.data() == RING_DATA {
+                // the completions are reaped later
+                continue;
+            }
+            let data = unsafe { &*(event.data() as *const EventData) };
             let events = event.events().bits() as usize;
             // info!("select got event, data={:p}, events={}", data, events);
-            data.io_flag.fetch_or(events, Ordering::Release);
-
-            // first check the atomic co, this may be grab by the worker first
-            let co = match data.co.take() {
-                Some(co) => co,
-                None => continue,
-            };
+            // the write half of a split io waits on its own event data
+            if let Some(writer) = data.writer.get() {
+                if events & WRITE_EVENTS != 0 {
+                    wakeup(writer, events, scheduler, id);
+                }
+            }
+            wakeup(data, events, scheduler, id);
+        }
 
-            // it's safe to remove the timer since we are running the timer_list in the same thread
-            #[cfg(feature = "io_timeout")]
-            data.timer.borrow_mut().take().map(|h| {
-                unsafe {
-                    // tell the timer handler not to cancel the io
-                    // it's not always true that you can really remove the timer entry
-                    h.with_mut_data(|value| value.data.event_data = std::ptr::null_mut());
+        // resume the coroutines that their io requests completed
+        #[cfg(uring)]
+        if let Some(ring) = &single_selector.ring {
+            ring.complete(|user_data, res| {
+                if user_data == IGNORE_DATA {
+                    return;
+                }
+                let data = unsafe { &*(user_data as *const EventData) };
+                data.uring_res.store(res, Ordering::Release);
+                let mut co = match data.co.take() {
+                    Some(co) => co,
+                    None => return,
+                };
+                // clear it after take the co, so that the readiness events are ignored
+                data.uring_id.store(NO_RING, Ordering::Release);
+                if res < 0 {
+                    set_co_para(&mut co, uring_error(data, res));
                 }
-                h.remove()
-            });
 
-            #[cfg(feature = "work_steal")]
-            scheduler.schedule_with_id(co, id);
-            #[cfg(not(feature = "work_steal"))]
-            crate::coroutine_impl::run_coroutine(co);
+                #[cfg(feature = "work_steal")]
+                scheduler.schedule_with_id(co, id);
+                #[cfg(not(feature = "work_steal"))]
+                crate::coroutine_impl::run_coroutine(co);
+            });
         }
 
+        // resume the coroutines that their timers expired
+        scheduler.run_timers(id);
+
         // run all the local tasks
         scheduler.run_queued_tasks(id);
 
@@ -145,7 +317,8 @@ impl Selector {
             .schedule_timer(now(), &timeout_handler);
         #[cfg(not(feature = "io_timeout"))]
         let next_expire = None;
-        Ok(next_expire)
+        // the coroutine timers may expire earlier
+        Ok(scheduler.next_timer_expire(id, next_expire))
     }
 
     // this will post an os event so that we can wake up the event loop
@@ -156,9 +329,23 @@ impl Selector {
         trace!("wakeup id={id:?}, ret={ret:?}");
     }
 
+    // the default worker of the fd, it's picked by the fd value
+    #[inline]
+    fn worker_id(&self, fd: RawFd) -> usize {
+        fd as usize % self.vec.len()
+    }
+
     // register io event to the selector
     #[inline]
     pub fn add_fd(&self, io_data: IoData) -> io::Result<IoData> {
+        let id = self.worker_id(io_data.fd);
+        self.add_fd_to(io_data, id)
+    }
+
+    // register io event to the selector of the worker
+    pub fn add_fd_to(&self, io_data: IoData, id: usize) -> io::Result<IoData> {
+        assert!(id < self.vec.len());
+        io_data.worker.store(id, Ordering::Relaxed);
         let info = EpollEvent::new(
             EpollFlags::EPOLLIN
                 | EpollFlags::EPOLLOUT
@@ -168,7 +355,6 @@ impl Selector {
         );
 
         let fd = io_data.fd;
-        let id = fd as usize % self.vec.len();
         let single_selector = &self.vec[id];
         let epoll = &single_selector.epoll;
         info!("add fd to epoll select, fd={fd:?}");
@@ -178,45 +364,19 @@ impl Selector {
             .map(|_| io_data)
     }
 
-    #[inline]
-    pub fn mod_fd(&self, io_data: &IoData, is_read: bool) -> io::Result<()> {
-        let mut info = if is_read {
-            EpollEvent::new(
-                EpollFlags::EPOLLIN | EpollFlags::EPOLLRDHUP | EpollFlags::EPOLLET,
-                io_data.as_ref() as *const _ as _,
-            )
-        } else {
-            EpollEvent::new(
-                EpollFlags::EPOLLOUT | EpollFlags::EPOLLHUP | EpollFlags::EPOLLET,
-                io_data.as_ref() as *const _ as _,
-            )
-        };
-
-        let fd = io_data.fd;
-        let id = fd as usize % self.vec.len();
-        let single_selector = &self.vec[id];
-        let epoll = &single_selector.epoll;
-        info!("mod fd to epoll select, fd={fd:?}, is_read={is_read}");
-        epoll
-            .modify(unsafe { BorrowedFd::borrow_raw(fd) }, &mut info)
-            .map_err(from_nix_error)
-    }
-
     #[inline]
     pub fn del_fd(&self, io_data: &IoData) {
         #[cfg(feature = "io_timeout")]
         if let Some(h) = io_data.timer.borrow_mut().take() {
-            unsafe {
-                // mark the timer as removed if any, this only happened
-                // when cancel an IO. what if the timer expired at the same time?
-                // because we run this func in the user space, so the timer handler
-                // will not got the coroutine
-                h.with_mut_data(|value| value.data.event_data = std::ptr::null_mut());
-            }
+            // mark the timer as removed if any, this only happened
+            // when cancel an IO. what if the timer expired at the same time?
+            // because we run this func in the user space, so the timer handler
+            // will not got the coroutine
+            h.with_mut_data(|value| value.event_data = std::ptr::null_mut());
         }
 
         let fd = io_data.fd;
-        let id = fd as usize % self.vec.len();
+        let id = io_data.worker.load(Ordering::Relaxed);
         let single_selector = &self.vec[id];
         let epoll = &single_selector.epoll;
         info!("del fd from epoll select, fd={fd:?}");
@@ -238,13 +398,89 @@ impl Selector {
     #[inline]
     #[cfg(feature = "io_timeout")]
     pub fn add_io_timer(&self, io: &IoData, timeout: Duration) {
-        let id = io.fd as usize % self.vec.len();
+        let id = io.worker.load(Ordering::Relaxed);
         // info!("io timeout = {:?}", dur);
-        let (h, b_new) = self.vec[id].timer_list.add_timer(timeout, io.timer_data());
+        let slack = if io.precise_timer.load(Ordering::Relaxed) {
+            Duration::ZERO
+        } else {
+            config().get_timer_slack()
+        };
+        let (h, b_new) =
+            self.vec[id]
+                .timer_list
+                .add_timer_with_slack(timeout, slack, io.timer_data());
         if b_new {
             // wake up the event loop thread to recall the next wait timeout
             self.wakeup(id);
         }
         io.timer.borrow_mut().replace(h);
     }
+
+    // return true if the io requests are submitted to io_uring
+    #[cfg(uring)]
+    #[inline]
+    pub fn has_uring(&self) -> bool {
+        self.has_uring
+    }
+
+    // queue the io_uring request in the ring of the current worker
+    // the coroutine is resumed when the request is completed
+    #[cfg(uring)]
+    pub fn submit_uring(
+        &self,
+        io: &IoData,
+        mut co: CoroutineImpl,
+        entries: &[io_uring::squeue::Entry],
+    ) {
+        let worker = WORKER_ID.get();
+        let id = if worker < self.vec.len() {
+            worker
+        } else {
+            io.worker.load(Ordering::Relaxed)
+        };
+        let ring = self.vec[id]
+            .ring
+            .as_ref()
+            .expect("no io_uring for the selector");
+
+        // mark it before store the co, so that the readiness events are ignored
+        io.uring_id.store(id, Ordering::Release);
+        io.co.store(co);
+        match ring.push(entries) {
+            Ok(()) => {
+                if id != worker {
+                    // the worker would submit it in the next select
+                    self.wakeup(id);
+                }
+            }
+            Err(e) => {
+                // resume the coroutine with the error
+                co = io.co.take().expect("can't get co after submit failed");
+                io.uring_id.store(NO_RING, Ordering::Release);
+                set_co_para(&mut co, e);
+                crate::scheduler::get_scheduler().schedule(co);
+            }
+        }
+    }
+
+    // cancel the in-flight io_uring request, the coroutine is resumed
+    // when the canceled request is completed
+    // return false if there is no in-flight request
+    #[cfg(all(uring, feature = "io_cancel"))]
+    pub fn cancel_uring(&self, io: &EventData) -> bool {
+        let id = io.uring_id.load(Ordering::Acquire);
+        if id == NO_RING {
+            return false;
+        }
+        let entry = io_uring::opcode::AsyncCancel::new(io as *const _ as u64)
+            .build()
+            .user_data(IGNORE_DATA);
+        if let Some(ring) = &self.vec[id].ring {
+            ring.push(&[entry]).ok();
+        }
+        if id != WORKER_ID.get() {
+            self.wakeup(id);
+        }
+        true
+    }
 }
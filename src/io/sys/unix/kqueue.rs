@@ -2,13 +2,14 @@ use std::os::fd::{AsRawFd, FromRawFd, RawFd};
 use std::os::unix::io::OwnedFd;
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
-#[cfg(feature = "io_timeout")]
 use std::time::Duration;
 use std::{io, ptr};
 
 #[cfg(feature = "io_timeout")]
 use super::{timeout_handler, TimerList};
 use super::{EventData, IoData};
+#[cfg(feature = "io_timeout")]
+use crate::config::config;
 use crate::scheduler::Scheduler;
 #[cfg(feature = "io_timeout")]
 use crate::timeout_list::now;
@@ -20,6 +21,37 @@ pub type SysEvent = libc::kevent;
 
 // used for notify wakeup
 const NOTIFY_IDENT: usize = 42;
+// the longest wait of a select, the same as epoll
+const MAX_TIMEOUT_NS: u64 = i32::MAX as u64 * 1_000_000;
+
+// set the io flag and resume the coroutine that waits on the event data
+#[inline]
+fn wakeup(data: &EventData, flags: usize, scheduler: &Scheduler, id: usize) {
+    data.io_flag.fetch_or(flags, Ordering::Release);
+
+    // first check the atomic co, this may be grab by the worker first
+    let co = match data.co.take() {
+        None => return,
+        Some(co) => co,
+    };
+
+    // it's safe to remove the timer since we are running the timer_list in the same thread
+    #[cfg(feature = "io_timeout")]
+    data.timer.borrow_mut().take().map(|h| {
+        // tell the timer handler not to cancel the io
+        // it's not always true that you can really remove the timer entry
+        h.with_mut_data(|value| value.event_data = ptr::null_mut());
+        h.remove()
+    });
+
+    #[cfg(feature = "work_steal")]
+    scheduler.schedule_with_id(co, id);
+    #[cfg(not(feature = "work_steal"))]
+    {
+        let _ = (scheduler, id);
+        crate::coroutine_impl::run_coroutine(co);
+    }
+}
 
 macro_rules! kevent {
     ($id:expr, $filter:expr, $flags:expr, $data:expr) => {
@@ -112,25 +144,22 @@ impl Selector {
         scheduler: &Scheduler,
         id: usize,
         events: &mut [SysEvent],
-        _timeout: Option<u64>,
+        timeout: Option<u64>,
     ) -> io::Result<Option<u64>> {
         assert!(id < self.vec.len());
-        #[cfg(feature = "io_timeout")]
-        let timeout_spec = _timeout.map(|to| {
-            let dur = Duration::from_nanos(to);
+        let timeout_spec = timeout.map(|to| {
+            // the longer timeout is clamped, the timers are checked again after the wakeup
+            let dur = Duration::from_nanos(to.min(MAX_TIMEOUT_NS));
             libc::timespec {
                 tv_sec: dur.as_secs() as libc::time_t,
                 tv_nsec: dur.subsec_nanos() as libc::c_long,
             }
         });
 
-        #[cfg(feature = "io_timeout")]
         let timeout = timeout_spec
             .as_ref()
             .map(|s| s as *const _)
             .unwrap_or(ptr::null());
-        #[cfg(not(feature = "io_timeout"))]
-        let timeout = ptr::null();
         // debug!("select; timeout={:?}", timeout_spec);
 
         let single_selector = &self.vec[id];
@@ -153,34 +182,22 @@ impl Selector {
                 scheduler.collect_global(id);
                 continue;
             }
-            let data = unsafe { &mut *(event.udata as *mut EventData) };
+            let data = unsafe { &*(event.udata as *const EventData) };
             // info!("select got event, data={:p}", data);
-            data.io_flag
-                .fetch_or(event.flags as usize, Ordering::Release);
-
-            // first check the atomic co, this may be grab by the worker first
-            let co = match data.co.take() {
-                None => continue,
-                Some(co) => co,
-            };
-
-            // it's safe to remove the timer since we are running the timer_list in the same thread
-            #[cfg(feature = "io_timeout")]
-            data.timer.borrow_mut().take().map(|h| {
-                unsafe {
-                    // tell the timer handler not to cancel the io
-                    // it's not always true that you can really remove the timer entry
-                    h.with_mut_data(|value| value.data.event_data = ptr::null_mut());
+            let flags = event.flags as usize;
+            // the write half of a split io waits on its own event data
+            if let Some(writer) = data.writer.get() {
+                if event.filter == libc::EVFILT_WRITE {
+                    wakeup(writer, flags, scheduler, id);
+                    continue;
                 }
-                h.remove()
-            });
-
-            #[cfg(feature = "work_steal")]
-            scheduler.schedule_with_id(co, id);
-            #[cfg(not(feature = "work_steal"))]
-            crate::coroutine_impl::run_coroutine(co);
+            }
+            wakeup(data, flags, scheduler, id);
         }
 
+        // resume the coroutines that their timers expired
+        scheduler.run_timers(id);
+
         // run all the local tasks
         scheduler.run_queued_tasks(id);
 
@@ -194,7 +211,8 @@ impl Selector {
             .schedule_timer(now(), &timeout_handler);
         #[cfg(not(feature = "io_timeout"))]
         let next_expire = None;
-        Ok(next_expire)
+        // the coroutine timers may expire earlier
+        Ok(scheduler.next_timer_expire(id, next_expire))
     }
 
     // this will post an os event so that we can wakeup the event loop
@@ -217,11 +235,24 @@ impl Selector {
         trace!("wakeup id={:?}", id);
     }
 
+    // the default worker of the fd, it's picked by the fd value
+    #[inline]
+    fn worker_id(&self, fd: RawFd) -> usize {
+        fd as usize % self.vec.len()
+    }
+
     // register io event to the selector
     #[inline]
     pub fn add_fd(&self, io_data: IoData) -> io::Result<IoData> {
+        let id = self.worker_id(io_data.fd);
+        self.add_fd_to(io_data, id)
+    }
+
+    // register io event to the selector of the worker
+    pub fn add_fd_to(&self, io_data: IoData, id: usize) -> io::Result<IoData> {
+        assert!(id < self.vec.len());
+        io_data.worker.store(id, Ordering::Relaxed);
         let fd = io_data.fd;
-        let id = fd as usize % self.vec.len();
         let kqfd = self.vec[id].as_raw_fd();
 
         let flags = libc::EV_ADD | libc::EV_CLEAR | libc::EV_RECEIPT;
@@ -244,54 +275,19 @@ impl Selector {
         Ok(io_data)
     }
 
-    #[inline]
-    pub fn mod_fd(&self, io_data: &IoData, is_read: bool) -> io::Result<()> {
-        let fd = io_data.fd;
-        let id = fd as usize % self.vec.len();
-        let kqfd = self.vec[id].as_raw_fd();
-
-        let flags = libc::EV_CLEAR | libc::EV_RECEIPT;
-        let udata = io_data.as_ref() as *const _;
-        let mut changes = if is_read {
-            [
-                kevent!(fd, libc::EVFILT_WRITE, flags | libc::EV_DELETE, udata),
-                kevent!(fd, libc::EVFILT_READ, flags | libc::EV_ADD, udata),
-            ]
-        } else {
-            [
-                kevent!(fd, libc::EVFILT_WRITE, flags | libc::EV_ADD, udata),
-                kevent!(fd, libc::EVFILT_READ, flags | libc::EV_DELETE, udata),
-            ]
-        };
-
-        syscall!(kevent(
-            kqfd,
-            changes.as_ptr(),
-            changes.len() as libc::c_int,
-            changes.as_mut_ptr(),
-            changes.len() as libc::c_int,
-            ptr::null(),
-        ))?;
-
-        debug!("modify fd to kqueue select, fd={:?}", fd);
-        Ok(())
-    }
-
     #[inline]
     pub fn del_fd(&self, io_data: &IoData) {
         #[cfg(feature = "io_timeout")]
         if let Some(h) = io_data.timer.borrow_mut().take() {
-            unsafe {
-                // mark the timer as removed if any, this only happened
-                // when cancel an IO. what if the timer expired at the same time?
-                // because we run this func in the user space, so the timer handler
-                // will not got the coroutine
-                h.with_mut_data(|value| value.data.event_data = ptr::null_mut());
-            }
+            // mark the timer as removed if any, this only happened
+            // when cancel an IO. what if the timer expired at the same time?
+            // because we run this func in the user space, so the timer handler
+            // will not got the coroutine
+            h.with_mut_data(|value| value.event_data = ptr::null_mut());
         }
 
         let fd = io_data.fd;
-        let id = fd as usize % self.vec.len();
+        let id = io_data.worker.load(Ordering::Relaxed);
         let single_selector = &self.vec[id];
         let kqfd = single_selector.as_raw_fd();
 
@@ -329,9 +325,17 @@ impl Selector {
     #[inline]
     #[cfg(feature = "io_timeout")]
     pub fn add_io_timer(&self, io: &IoData, timeout: Duration) {
-        let id = io.fd as usize % self.vec.len();
+        let id = io.worker.load(Ordering::Relaxed);
         // info!("io timeout = {:?}", dur);
-        let (h, b_new) = self.vec[id].timer_list.add_timer(timeout, io.timer_data());
+        let slack = if io.precise_timer.load(Ordering::Relaxed) {
+            Duration::ZERO
+        } else {
+            config().get_timer_slack()
+        };
+        let (h, b_new) =
+            self.vec[id]
+                .timer_list
+                .add_timer_with_slack(timeout, slack, io.timer_data());
         if b_new {
             // wakeup the event loop thread to recall the next wait timeout
             self.wakeup(id);
@@ -17,15 +17,21 @@ mod select;
 pub mod cancel;
 pub mod co_io;
 pub mod net;
+#[cfg(uring)]
+pub mod uring;
 pub mod wait_io;
 
 #[cfg(feature = "io_timeout")]
 use std::cell::RefCell;
+#[cfg(all(uring, feature = "io_timeout"))]
+use std::cell::UnsafeCell;
 use std::ops::Deref;
 use std::os::fd::{AsFd, BorrowedFd};
 use std::os::unix::io::{AsRawFd, RawFd};
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
+#[cfg(uring)]
+use std::sync::atomic::AtomicI32;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, OnceLock};
 use std::{fmt, io};
 
 use crate::coroutine_impl::{run_coroutine, CoroutineImpl};
@@ -34,7 +40,7 @@ use crate::likely::likely;
 use crate::scheduler::get_scheduler;
 use crate::sync::AtomicOption;
 #[cfg(feature = "io_timeout")]
-use crate::timeout_list::{TimeOutList, TimeoutHandle};
+use crate::timeout_list::{TimeoutHandle, TimerWheel};
 use crate::yield_now::get_co_para;
 #[cfg(feature = "io_timeout")]
 use crate::yield_now::set_co_para;
@@ -46,9 +52,10 @@ pub fn add_socket<T: AsRawFd + ?Sized>(t: &T) -> io::Result<IoData> {
     get_scheduler().get_selector().add_fd(IoData::new(t))
 }
 
+// register the socket to the selector of the worker
 #[inline]
-pub fn mod_socket(io: &IoData, is_read: bool) -> io::Result<()> {
-    get_scheduler().get_selector().mod_fd(io, is_read)
+pub fn add_socket_to<T: AsRawFd + ?Sized>(t: &T, id: usize) -> io::Result<IoData> {
+    get_scheduler().get_selector().add_fd_to(IoData::new(t), id)
 }
 
 #[inline]
@@ -108,7 +115,7 @@ pub struct TimerData {
 }
 
 #[cfg(feature = "io_timeout")]
-pub type TimerList = TimeOutList<TimerData>;
+pub type TimerList = TimerWheel<TimerData>;
 #[cfg(feature = "io_timeout")]
 pub type TimerHandle = TimeoutHandle<TimerData>;
 
@@ -117,9 +124,30 @@ pub type TimerHandle = TimeoutHandle<TimerData>;
 pub struct EventData {
     pub fd: RawFd,
     pub io_flag: AtomicUsize,
+    // the worker whose selector polls the fd, set when registered
+    pub worker: AtomicUsize,
+    // the io timeouts ignore the timer slack
+    pub precise_timer: AtomicBool,
     #[cfg(feature = "io_timeout")]
     pub timer: RefCell<Option<TimerHandle>>,
     pub co: AtomicOption<CoroutineImpl>,
+    // the ring of the in-flight io_uring request
+    #[cfg(uring)]
+    pub uring_id: AtomicUsize,
+    // the result of the completed io_uring request
+    #[cfg(uring)]
+    pub uring_res: AtomicI32,
+    // the linked timeout of the io_uring request
+    #[cfg(all(uring, feature = "io_timeout"))]
+    pub uring_ts: UnsafeCell<io_uring::types::Timespec>,
+    // the deadline of the linked timeout, none if there is no timeout
+    #[cfg(all(uring, feature = "io_timeout"))]
+    pub uring_deadline: UnsafeCell<Option<std::time::Instant>>,
+    // the event data of the write half of a split io, it's woken up
+    // by the write readiness of this fd
+    pub writer: OnceLock<Arc<EventData>>,
+    // the write half event data is not registered to the selector
+    pub is_writer: bool,
 }
 
 unsafe impl Send for EventData {}
@@ -130,9 +158,21 @@ impl EventData {
         EventData {
             fd,
             io_flag: AtomicUsize::new(0),
+            worker: AtomicUsize::new(0),
+            precise_timer: AtomicBool::new(false),
             #[cfg(feature = "io_timeout")]
             timer: RefCell::new(None),
             co: AtomicOption::none(),
+            #[cfg(uring)]
+            uring_id: AtomicUsize::new(uring::NO_RING),
+            #[cfg(uring)]
+            uring_res: AtomicI32::new(0),
+            #[cfg(all(uring, feature = "io_timeout"))]
+            uring_ts: UnsafeCell::new(io_uring::types::Timespec::new()),
+            #[cfg(all(uring, feature = "io_timeout"))]
+            uring_deadline: UnsafeCell::new(None),
+            writer: OnceLock::new(),
+            is_writer: false,
         }
     }
 
@@ -153,11 +193,9 @@ impl EventData {
         // it's safe to remove the timer since we are running the timer_list in the same thread
         #[cfg(feature = "io_timeout")]
         self.timer.borrow_mut().take().map(|h| {
-            unsafe {
-                // tell the timer function not to cancel the io
-                // it's not always true that you can really remove the timer entry
-                h.with_mut_data(|value| value.data.event_data = std::ptr::null_mut());
-            }
+            // tell the timer function not to cancel the io
+            // it's not always true that you can really remove the timer entry
+            h.with_mut_data(|value| value.event_data = std::ptr::null_mut());
             h.remove()
         });
 
@@ -176,11 +214,9 @@ impl EventData {
         // it's safe to remove the timer since we are running the timer_list in the same thread
         #[cfg(feature = "io_timeout")]
         self.timer.borrow_mut().take().map(|h| {
-            unsafe {
-                // tell the timer function not to cancel the io
-                // it's not always true that you can really remove the timer entry
-                h.with_mut_data(|value| value.data.event_data = std::ptr::null_mut());
-            }
+            // tell the timer function not to cancel the io
+            // it's not always true that you can really remove the timer entry
+            h.with_mut_data(|value| value.event_data = std::ptr::null_mut());
             h.remove()
         });
 
@@ -204,6 +240,38 @@ impl IoData {
     pub fn reset(&self) -> usize {
         self.io_flag.swap(0, Ordering::AcqRel)
     }
+
+    // the io data of the write half, it shares the fd registration
+    // but has its own io flag and waiting coroutine
+    pub fn split_writer(&self) -> IoData {
+        let writer = self.writer.get_or_init(|| {
+            let mut event_data = EventData::new(self.fd);
+            event_data.is_writer = true;
+            event_data.worker = AtomicUsize::new(self.worker.load(Ordering::Relaxed));
+            event_data.precise_timer = AtomicBool::new(self.precise_timer());
+            Arc::new(event_data)
+        });
+        IoData(writer.clone())
+    }
+
+    /// Makes the io timeouts of the socket fire on time.
+    ///
+    /// The timeouts ignore the [`timer slack`] when it's set, which is
+    /// useful for the latency sensitive sockets in a server that coalesces
+    /// the timeouts of the idle connections.
+    ///
+    /// [`timer slack`]: crate::Config::set_timer_slack
+    pub fn set_precise_timer(&self, precise: bool) {
+        self.precise_timer.store(precise, Ordering::Relaxed);
+        if let Some(writer) = self.writer.get() {
+            writer.precise_timer.store(precise, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns true if the io timeouts ignore the timer slack
+    pub fn precise_timer(&self) -> bool {
+        self.precise_timer.load(Ordering::Relaxed)
+    }
 }
 
 impl Deref for IoData {
@@ -222,7 +290,10 @@ impl fmt::Debug for IoData {
 
 impl Drop for IoData {
     fn drop(&mut self) {
-        del_socket(self);
+        // the fd is deregistered by the owner of the registration
+        if !self.is_writer {
+            del_socket(self);
+        }
     }
 }
 
@@ -1,23 +1,43 @@
+mod socket_io;
 mod socket_peek;
 mod socket_read;
+mod socket_read_vectored;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod socket_sendfile;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod socket_splice;
 mod socket_write;
 mod socket_write_vectored;
 mod tcp_listener_accept;
 mod tcp_stream_connect;
 mod udp_recv_from;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod udp_recv_many;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod udp_send_many;
 mod udp_send_to;
 mod unix_listener_accept;
 mod unix_recv_from;
 mod unix_send_to;
 mod unix_stream_connect;
 
+pub use self::socket_io::SocketIo;
 pub use self::socket_peek::SocketPeek;
 pub use self::socket_read::SocketRead;
+pub use self::socket_read_vectored::{readv, SocketReadVectored};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::socket_sendfile::sendfile;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::socket_splice::{SocketSplice, SpliceEnd};
 pub use self::socket_write::SocketWrite;
-pub use self::socket_write_vectored::SocketWriteVectored;
+pub use self::socket_write_vectored::{writev, SocketWriteVectored};
 pub use self::tcp_listener_accept::TcpListenerAccept;
 pub use self::tcp_stream_connect::TcpStreamConnect;
 pub use self::udp_recv_from::UdpRecvFrom;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::udp_recv_many::recv_many;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::udp_send_many::send_many;
 pub use self::udp_send_to::UdpSendTo;
 pub use self::unix_listener_accept::UnixListenerAccept;
 pub use self::unix_recv_from::UnixRecvFrom;
@@ -4,12 +4,16 @@ use std::sync::atomic::Ordering;
 #[cfg(feature = "io_timeout")]
 use std::time::Duration;
 
+#[cfg(uring)]
+use super::super::uring;
 use super::super::{co_io_result, from_nix_error, IoData};
 #[cfg(feature = "io_cancel")]
 use crate::coroutine_impl::co_cancel_data;
 use crate::coroutine_impl::{is_coroutine, CoroutineImpl, EventSource};
 use crate::io::AsIoData;
 use crate::yield_now::yield_with_io;
+#[cfg(uring)]
+use io_uring::{opcode, types};
 use nix::unistd::read;
 
 pub struct SocketRead<'a> {
@@ -36,6 +40,11 @@ impl<'a> SocketRead<'a> {
     }
 
     pub fn done(&mut self) -> io::Result<usize> {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            return uring::done(self.io_data, self.is_coroutine);
+        }
+
         let fd = unsafe { BorrowedFd::borrow_raw(self.io_data.fd) };
         loop {
             co_io_result(self.is_coroutine)?;
@@ -67,6 +76,23 @@ impl<'a> SocketRead<'a> {
 
 impl EventSource for SocketRead<'_> {
     fn subscribe(&mut self, co: CoroutineImpl) {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            let fd = types::Fd(self.io_data.fd);
+            let len = self.buf.len().min(u32::MAX as usize) as u32;
+            // read from the current file position
+            let entry = opcode::Read::new(fd, self.buf.as_mut_ptr(), len)
+                .offset(u64::MAX)
+                .build();
+            return uring::subscribe(
+                self.io_data,
+                co,
+                entry,
+                #[cfg(feature = "io_timeout")]
+                self.timeout,
+            );
+        }
+
         #[cfg(feature = "io_cancel")]
         let cancel = co_cancel_data(&co);
         let io_data = self.io_data;
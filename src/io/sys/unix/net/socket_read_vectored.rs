@@ -0,0 +1,143 @@
+use std::io::{self, IoSliceMut};
+use std::os::fd::RawFd;
+use std::sync::atomic::Ordering;
+#[cfg(feature = "io_timeout")]
+use std::time::Duration;
+
+#[cfg(uring)]
+use super::super::uring;
+use super::super::{co_io_result, IoData};
+#[cfg(feature = "io_cancel")]
+use crate::coroutine_impl::co_cancel_data;
+use crate::coroutine_impl::{is_coroutine, CoroutineImpl, EventSource};
+use crate::io::AsIoData;
+use crate::yield_now::yield_with_io;
+#[cfg(uring)]
+use io_uring::{opcode, types};
+
+// the IOV_MAX of linux and the bsd family
+pub(crate) const MAX_IOV: usize = 1024;
+
+/// read into the buffers with one `readv` call
+pub fn readv(fd: RawFd, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+    let len = bufs.len().min(MAX_IOV) as libc::c_int;
+    // IoSliceMut is ABI compatible with iovec
+    let n = unsafe { libc::readv(fd, bufs.as_ptr().cast(), len) };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(n as usize)
+}
+
+pub struct SocketReadVectored<'a, 'b> {
+    io_data: &'a IoData,
+    bufs: &'a mut [IoSliceMut<'b>],
+    #[cfg(feature = "io_timeout")]
+    timeout: Option<Duration>,
+    pub(crate) is_coroutine: bool,
+}
+
+impl<'a, 'b> SocketReadVectored<'a, 'b> {
+    pub fn new<T: AsIoData>(
+        s: &'a T,
+        bufs: &'a mut [IoSliceMut<'b>],
+        #[cfg(feature = "io_timeout")] timeout: Option<Duration>,
+    ) -> Self {
+        SocketReadVectored {
+            io_data: s.as_io_data(),
+            bufs,
+            #[cfg(feature = "io_timeout")]
+            timeout,
+            is_coroutine: is_coroutine(),
+        }
+    }
+
+    pub fn done(&mut self) -> io::Result<usize> {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            return uring::done(self.io_data, self.is_coroutine);
+        }
+
+        loop {
+            co_io_result(self.is_coroutine)?;
+
+            // clear the io_flag
+            self.io_data.io_flag.store(0, Ordering::Relaxed);
+
+            // finish the read operation
+            match readv(self.io_data.fd, self.bufs) {
+                Ok(n) => return Ok(n),
+                Err(e) => {
+                    // raw_os_error is faster than kind
+                    let raw_err = e.raw_os_error();
+                    if raw_err == Some(libc::EAGAIN) || raw_err == Some(libc::EWOULDBLOCK) {
+                        // do nothing here
+                    } else {
+                        return Err(e);
+                    }
+                }
+            }
+
+            if self.io_data.io_flag.load(Ordering::Relaxed) != 0 {
+                continue;
+            }
+
+            // the result is still WouldBlock, need to try again
+            yield_with_io(self, self.is_coroutine);
+        }
+    }
+}
+
+impl EventSource for SocketReadVectored<'_, '_> {
+    fn subscribe(&mut self, co: CoroutineImpl) {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            let fd = types::Fd(self.io_data.fd);
+            // IoSliceMut is ABI compatible with iovec
+            let iovec = self.bufs.as_ptr().cast::<libc::iovec>();
+            let len = self.bufs.len().min(MAX_IOV) as u32;
+            // read from the current file position
+            let entry = opcode::Readv::new(fd, iovec, len).offset(u64::MAX).build();
+            return uring::subscribe(
+                self.io_data,
+                co,
+                entry,
+                #[cfg(feature = "io_timeout")]
+                self.timeout,
+            );
+        }
+
+        #[cfg(feature = "io_cancel")]
+        let cancel = co_cancel_data(&co);
+        let io_data = self.io_data;
+
+        #[cfg(feature = "io_timeout")]
+        if let Some(dur) = self.timeout {
+            crate::scheduler::get_scheduler()
+                .get_selector()
+                .add_io_timer(self.io_data, dur);
+        }
+
+        // after register the coroutine, it's possible that other thread run it immediately
+        // and cause the process after it invalid, this is kind of user and kernel competition
+        // so we need to delay the drop of the EventSource, that's why _g is here
+        io_data.co.store(co);
+        // till here the io may be done in other thread
+
+        // there is event, re-run the coroutine
+        if io_data.io_flag.load(Ordering::Acquire) != 0 {
+            #[allow(clippy::needless_return)]
+            return io_data.fast_schedule();
+        }
+
+        #[cfg(feature = "io_cancel")]
+        {
+            // register the cancel io data
+            cancel.set_io((*io_data).clone());
+            // re-check the cancel status
+            if cancel.is_canceled() {
+                unsafe { cancel.cancel() };
+            }
+        }
+    }
+}
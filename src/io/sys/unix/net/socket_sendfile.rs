@@ -0,0 +1,15 @@
+use std::io;
+use std::os::fd::RawFd;
+
+/// send the file content at the offset to the socket with one `sendfile` call
+pub fn sendfile(socket: RawFd, file: RawFd, offset: u64, len: usize) -> io::Result<usize> {
+    let mut off = libc::off_t::try_from(offset)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "offset overflow"))?;
+    // the kernel transfers at most 0x7ffff000 bytes
+    let len = len.min(0x7fff_f000);
+    let n = unsafe { libc::sendfile(socket, file, &mut off, len) };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(n as usize)
+}
@@ -0,0 +1,179 @@
+use std::io;
+use std::os::fd::RawFd;
+use std::sync::atomic::Ordering;
+#[cfg(feature = "io_timeout")]
+use std::time::Duration;
+
+use super::super::{co_io_result, IoData};
+#[cfg(feature = "io_cancel")]
+use crate::coroutine_impl::co_cancel_data;
+use crate::coroutine_impl::{is_coroutine, CoroutineImpl, EventSource};
+use crate::yield_now::yield_with_io;
+
+/// one end of the splice
+#[derive(Clone, Copy)]
+pub enum SpliceEnd<'a> {
+    /// the fd that is registered to the selector
+    Io(&'a IoData),
+    /// the nonblocking pipe that is never waited, it must be drained or
+    /// filled by the caller so that it's not the blocking end
+    Pipe(RawFd),
+}
+
+impl SpliceEnd<'_> {
+    fn fd(&self) -> RawFd {
+        match self {
+            SpliceEnd::Io(io) => io.fd,
+            SpliceEnd::Pipe(fd) => *fd,
+        }
+    }
+
+    fn clear(&self) {
+        if let SpliceEnd::Io(io) = self {
+            io.io_flag.store(0, Ordering::Relaxed);
+        }
+    }
+}
+
+// move data between two fds by splice, one of them must be a pipe
+pub struct SocketSplice<'a> {
+    input: SpliceEnd<'a>,
+    output: SpliceEnd<'a>,
+    len: usize,
+    // the end that blocks the splice
+    wait: Option<&'a IoData>,
+    #[cfg(feature = "io_timeout")]
+    timeout: Option<Duration>,
+    pub(crate) is_coroutine: bool,
+}
+
+impl<'a> SocketSplice<'a> {
+    pub fn new(
+        input: SpliceEnd<'a>,
+        output: SpliceEnd<'a>,
+        len: usize,
+        #[cfg(feature = "io_timeout")] timeout: Option<Duration>,
+    ) -> Self {
+        SocketSplice {
+            input,
+            output,
+            len,
+            wait: None,
+            #[cfg(feature = "io_timeout")]
+            timeout,
+            is_coroutine: is_coroutine(),
+        }
+    }
+
+    pub fn done(&mut self) -> io::Result<usize> {
+        // the io is tried first, no need to yield before calling this
+        loop {
+            // clear the io_flag
+            self.input.clear();
+            self.output.clear();
+
+            match splice(self.input.fd(), self.output.fd(), self.len) {
+                Ok(n) => return Ok(n),
+                Err(e) => {
+                    // raw_os_error is faster than kind
+                    let raw_err = e.raw_os_error();
+                    if raw_err == Some(libc::EAGAIN) || raw_err == Some(libc::EWOULDBLOCK) {
+                        // do nothing here
+                    } else {
+                        return Err(e);
+                    }
+                }
+            }
+
+            let wait = match self.blocking_end()? {
+                Some(io) => io,
+                // the pipe end is not ready, the caller didn't keep the contract
+                None => return Err(io::Error::from(io::ErrorKind::WouldBlock)),
+            };
+            if wait.io_flag.load(Ordering::Relaxed) != 0 {
+                continue;
+            }
+
+            // the result is still WouldBlock, need to try again
+            self.wait = Some(wait);
+            yield_with_io(self, self.is_coroutine);
+            co_io_result(self.is_coroutine)?;
+        }
+    }
+
+    // find out the end that is not ready
+    fn blocking_end(&self) -> io::Result<Option<&'a IoData>> {
+        match (self.input, self.output) {
+            (SpliceEnd::Io(input), SpliceEnd::Io(output)) => {
+                let mut fd = libc::pollfd {
+                    fd: input.fd,
+                    events: libc::POLLIN,
+                    revents: 0,
+                };
+                if unsafe { libc::poll(&mut fd, 1, 0) } < 0 {
+                    return Err(io::Error::last_os_error());
+                }
+                if fd.revents == 0 {
+                    Ok(Some(input))
+                } else {
+                    Ok(Some(output))
+                }
+            }
+            (SpliceEnd::Io(io), SpliceEnd::Pipe(_)) | (SpliceEnd::Pipe(_), SpliceEnd::Io(io)) => {
+                Ok(Some(io))
+            }
+            (SpliceEnd::Pipe(_), SpliceEnd::Pipe(_)) => Ok(None),
+        }
+    }
+}
+
+/// move data from the input fd to the output fd with one `splice` call
+pub fn splice(input: RawFd, output: RawFd, len: usize) -> io::Result<usize> {
+    let flags = libc::SPLICE_F_MOVE | libc::SPLICE_F_NONBLOCK;
+    let n = unsafe {
+        libc::splice(
+            input,
+            std::ptr::null_mut(),
+            output,
+            std::ptr::null_mut(),
+            len,
+            flags,
+        )
+    };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(n as usize)
+}
+
+impl EventSource for SocketSplice<'_> {
+    fn subscribe(&mut self, co: CoroutineImpl) {
+        #[cfg(feature = "io_cancel")]
+        let cancel = co_cancel_data(&co);
+        let io_data = self.wait.expect("no io to wait");
+
+        #[cfg(feature = "io_timeout")]
+        if let Some(dur) = self.timeout {
+            crate::scheduler::get_scheduler()
+                .get_selector()
+                .add_io_timer(io_data, dur);
+        }
+        io_data.co.store(co);
+
+        // there is event, re-run the coroutine
+        if io_data.io_flag.load(Ordering::Acquire) != 0 {
+            #[allow(clippy::needless_return)]
+            return io_data.fast_schedule();
+        }
+
+        #[cfg(feature = "io_cancel")]
+        {
+            // register the cancel io data
+            cancel.set_io((*io_data).clone());
+            // re-check the cancel status
+            if cancel.is_canceled() {
+                unsafe { cancel.cancel() };
+            }
+        }
+    }
+}
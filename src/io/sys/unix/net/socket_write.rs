@@ -3,11 +3,15 @@ use std::sync::atomic::Ordering;
 #[cfg(feature = "io_timeout")]
 use std::time::Duration;
 
+#[cfg(uring)]
+use super::super::uring;
 use super::super::{co_io_result, from_nix_error, IoData};
 use crate::coroutine_impl::{is_coroutine, CoroutineImpl, EventSource};
 use crate::io::AsIoData;
 use crate::yield_now::yield_with_io;
 
+#[cfg(uring)]
+use io_uring::{opcode, types};
 use nix::unistd::write;
 
 pub struct SocketWrite<'a> {
@@ -34,6 +38,11 @@ impl<'a> SocketWrite<'a> {
     }
 
     pub fn done(&mut self) -> io::Result<usize> {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            return uring::done(self.io_data, self.is_coroutine);
+        }
+
         loop {
             co_io_result(self.is_coroutine)?;
 
@@ -63,6 +72,23 @@ impl<'a> SocketWrite<'a> {
 
 impl EventSource for SocketWrite<'_> {
     fn subscribe(&mut self, co: CoroutineImpl) {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            let fd = types::Fd(self.io_data.fd);
+            let len = self.buf.len().min(u32::MAX as usize) as u32;
+            // write to the current file position
+            let entry = opcode::Write::new(fd, self.buf.as_ptr(), len)
+                .offset(u64::MAX)
+                .build();
+            return uring::subscribe(
+                self.io_data,
+                co,
+                entry,
+                #[cfg(feature = "io_timeout")]
+                self.timeout,
+            );
+        }
+
         let io_data = self.io_data;
 
         #[cfg(feature = "io_timeout")]
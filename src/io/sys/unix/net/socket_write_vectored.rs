@@ -1,33 +1,47 @@
 use std::io::{self, IoSlice};
+use std::os::fd::RawFd;
 use std::sync::atomic::Ordering;
 #[cfg(feature = "io_timeout")]
 use std::time::Duration;
 
+#[cfg(uring)]
+use super::super::uring;
 use super::super::{co_io_result, IoData};
+use super::socket_read_vectored::MAX_IOV;
 use crate::coroutine_impl::{is_coroutine, CoroutineImpl, EventSource};
 use crate::io::AsIoData;
 use crate::yield_now::yield_with_io;
+#[cfg(uring)]
+use io_uring::{opcode, types};
 
-pub struct SocketWriteVectored<'a> {
+/// write the buffers with one `writev` call
+pub fn writev(fd: RawFd, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+    let len = bufs.len().min(MAX_IOV) as libc::c_int;
+    // IoSlice is ABI compatible with iovec
+    let n = unsafe { libc::writev(fd, bufs.as_ptr().cast(), len) };
+    if n < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(n as usize)
+}
+
+pub struct SocketWriteVectored<'a, 'b> {
     io_data: &'a IoData,
-    bufs: &'a [IoSlice<'a>],
-    socket: &'a std::net::TcpStream,
+    bufs: &'a [IoSlice<'b>],
     #[cfg(feature = "io_timeout")]
     timeout: Option<Duration>,
     pub(crate) is_coroutine: bool,
 }
 
-impl<'a> SocketWriteVectored<'a> {
+impl<'a, 'b> SocketWriteVectored<'a, 'b> {
     pub fn new<T: AsIoData>(
         s: &'a T,
-        socket: &'a std::net::TcpStream,
-        bufs: &'a [IoSlice<'a>],
+        bufs: &'a [IoSlice<'b>],
         #[cfg(feature = "io_timeout")] timeout: Option<Duration>,
     ) -> Self {
         SocketWriteVectored {
             io_data: s.as_io_data(),
             bufs,
-            socket,
             #[cfg(feature = "io_timeout")]
             timeout,
             is_coroutine: is_coroutine(),
@@ -35,7 +49,10 @@ impl<'a> SocketWriteVectored<'a> {
     }
 
     pub fn done(&mut self) -> io::Result<usize> {
-        use std::io::Write;
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            return uring::done(self.io_data, self.is_coroutine);
+        }
 
         loop {
             co_io_result(self.is_coroutine)?;
@@ -43,7 +60,7 @@ impl<'a> SocketWriteVectored<'a> {
             // clear the io_flag
             self.io_data.io_flag.store(0, Ordering::Relaxed);
 
-            match self.socket.write_vectored(self.bufs) {
+            match writev(self.io_data.fd, self.bufs) {
                 Ok(n) => return Ok(n),
                 Err(e) => {
                     let raw_err = e.raw_os_error();
@@ -65,8 +82,24 @@ impl<'a> SocketWriteVectored<'a> {
     }
 }
 
-impl EventSource for SocketWriteVectored<'_> {
+impl EventSource for SocketWriteVectored<'_, '_> {
     fn subscribe(&mut self, co: CoroutineImpl) {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            let fd = types::Fd(self.io_data.fd);
+            // IoSlice is ABI compatible with iovec
+            let iovec = self.bufs.as_ptr().cast::<libc::iovec>();
+            let len = self.bufs.len().min(MAX_IOV) as u32;
+            let entry = opcode::Writev::new(fd, iovec, len).offset(u64::MAX).build();
+            return uring::subscribe(
+                self.io_data,
+                co,
+                entry,
+                #[cfg(feature = "io_timeout")]
+                self.timeout,
+            );
+        }
+
         let io_data = self.io_data;
 
         #[cfg(feature = "io_timeout")]
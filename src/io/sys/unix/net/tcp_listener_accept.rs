@@ -1,18 +1,27 @@
 use std::io;
 use std::net::SocketAddr;
+#[cfg(uring)]
+use std::os::fd::{FromRawFd, RawFd};
 use std::sync::atomic::Ordering;
 
-use super::super::{add_socket, co_io_result, IoData};
+#[cfg(uring)]
+use super::super::uring::{self, AddrBuf};
+use super::super::{co_io_result, IoData};
 #[cfg(feature = "io_cancel")]
 use crate::coroutine_impl::co_cancel_data;
 use crate::coroutine_impl::{is_coroutine, CoroutineImpl, EventSource};
 use crate::io::AsIoData;
 use crate::net::{TcpListener, TcpStream};
 use crate::yield_now::yield_with_io;
+#[cfg(uring)]
+use io_uring::{opcode, types};
 
 pub struct TcpListenerAccept<'a> {
     io_data: &'a IoData,
-    socket: &'a std::net::TcpListener,
+    listener: &'a TcpListener,
+    // the peer address of the io_uring accept
+    #[cfg(uring)]
+    addr: AddrBuf,
     pub(crate) is_coroutine: bool,
 }
 
@@ -20,22 +29,32 @@ impl<'a> TcpListenerAccept<'a> {
     pub fn new(socket: &'a TcpListener) -> io::Result<Self> {
         Ok(TcpListenerAccept {
             io_data: socket.as_io_data(),
-            socket: socket.inner(),
+            listener: socket,
+            #[cfg(uring)]
+            addr: AddrBuf::new(),
             is_coroutine: is_coroutine(),
         })
     }
 
     pub fn done(&mut self) -> io::Result<(TcpStream, SocketAddr)> {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            let fd = uring::done(self.io_data, self.is_coroutine)? as RawFd;
+            let s = unsafe { std::net::TcpStream::from_raw_fd(fd) };
+            let a = self.addr.take_socket_addr()?;
+            return self.listener.register_stream(s).map(|s| (s, a));
+        }
+
         loop {
             co_io_result(self.is_coroutine)?;
 
             // clear the io_flag
             self.io_data.io_flag.store(0, Ordering::Relaxed);
 
-            match self.socket.accept() {
+            match self.listener.inner().accept() {
                 Ok((s, a)) => {
                     s.set_nonblocking(true)?;
-                    return add_socket(&s).map(|io| (TcpStream::from_stream(s, io), a));
+                    return self.listener.register_stream(s).map(|s| (s, a));
                 }
                 Err(e) => {
                     // raw_os_error is faster than kind
@@ -60,6 +79,22 @@ impl<'a> TcpListenerAccept<'a> {
 
 impl EventSource for TcpListenerAccept<'_> {
     fn subscribe(&mut self, co: CoroutineImpl) {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            let fd = types::Fd(self.io_data.fd);
+            let (addr, len) = self.addr.as_mut_ptr();
+            let entry = opcode::Accept::new(fd, addr, len)
+                .flags(libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC)
+                .build();
+            return uring::subscribe(
+                self.io_data,
+                co,
+                entry,
+                #[cfg(feature = "io_timeout")]
+                None,
+            );
+        }
+
         #[cfg(feature = "io_cancel")]
         let cancel = co_cancel_data(&co);
         let io_data = self.io_data;
@@ -4,6 +4,8 @@ use std::sync::atomic::Ordering;
 #[cfg(feature = "io_timeout")]
 use std::time::Duration;
 
+#[cfg(uring)]
+use super::super::uring;
 use super::super::{add_socket, co_io_result, IoData};
 #[cfg(feature = "io_cancel")]
 use crate::coroutine_impl::co_cancel_data;
@@ -11,6 +13,10 @@ use crate::coroutine_impl::{is_coroutine, CoroutineImpl, EventSource};
 use crate::io::OptionCell;
 use crate::net::TcpStream;
 use crate::yield_now::yield_with_io;
+#[cfg(uring)]
+use io_uring::{opcode, types};
+#[cfg(uring)]
+use socket2::SockAddr;
 use socket2::Socket;
 
 pub struct TcpStreamConnect {
@@ -19,6 +25,9 @@ pub struct TcpStreamConnect {
     #[cfg(feature = "io_timeout")]
     timeout: Option<Duration>,
     addr: SocketAddr,
+    // the address of the io_uring connect
+    #[cfg(uring)]
+    sock_addr: SockAddr,
     is_connected: bool,
     pub(crate) is_coroutine: bool,
 }
@@ -30,36 +39,50 @@ impl TcpStreamConnect {
     ) -> io::Result<Self> {
         use socket2::{Domain, Type};
 
-        // let err = io::Error::new(io::ErrorKind::Other, "no socket addresses resolved");
-        addr.to_socket_addrs()?
+        let addr = addr
+            .to_socket_addrs()?
             .next()
-            .ok_or_else(|| io::Error::other("no socket addresses resolved"))
-            .and_then(|addr| {
-                let stream = match addr {
-                    SocketAddr::V4(..) => Socket::new(Domain::IPV4, Type::STREAM, None)?,
-                    SocketAddr::V6(..) => Socket::new(Domain::IPV6, Type::STREAM, None)?,
-                };
-                Ok((stream, addr))
-            })
-            .and_then(|(stream, addr)| {
-                // before yield we must set the socket to nonblocking mode and register to selector
-                stream.set_nonblocking(true)?;
-
-                add_socket(&stream).map(|io| TcpStreamConnect {
-                    io_data: OptionCell::new(io),
-                    stream: OptionCell::new(stream),
-                    #[cfg(feature = "io_timeout")]
-                    timeout,
-                    addr,
-                    is_connected: false,
-                    is_coroutine: is_coroutine(),
-                })
-            })
+            .ok_or_else(|| io::Error::other("no socket addresses resolved"))?;
+        let stream = Socket::new(Domain::for_address(addr), Type::STREAM, None)?;
+        Self::from_socket(
+            stream,
+            addr,
+            #[cfg(feature = "io_timeout")]
+            timeout,
+        )
+    }
+
+    // connect with the socket that is configured by the user
+    pub fn from_socket(
+        stream: Socket,
+        addr: SocketAddr,
+        #[cfg(feature = "io_timeout")] timeout: Option<Duration>,
+    ) -> io::Result<Self> {
+        // before yield we must set the socket to nonblocking mode and register to selector
+        stream.set_nonblocking(true)?;
+
+        add_socket(&stream).map(|io| TcpStreamConnect {
+            io_data: OptionCell::new(io),
+            stream: OptionCell::new(stream),
+            #[cfg(feature = "io_timeout")]
+            timeout,
+            addr,
+            #[cfg(uring)]
+            sock_addr: addr.into(),
+            is_connected: false,
+            is_coroutine: is_coroutine(),
+        })
     }
 
     #[inline]
     // return true if it's connected
     pub fn check_connected(&mut self) -> io::Result<bool> {
+        // the connect request is submitted when yield
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            return Ok(false);
+        }
+
         // unix connect is some like completion mode
         // we must give the connect request first to the system
         match self.stream.connect(&self.addr.into()) {
@@ -83,6 +106,12 @@ impl TcpStreamConnect {
             return Ok(convert_to_stream(self));
         }
 
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            uring::done(&self.io_data, self.is_coroutine)?;
+            return Ok(convert_to_stream(self));
+        }
+
         loop {
             co_io_result(self.is_coroutine)?;
 
@@ -111,6 +140,20 @@ impl TcpStreamConnect {
 
 impl EventSource for TcpStreamConnect {
     fn subscribe(&mut self, co: CoroutineImpl) {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            let fd = types::Fd(self.io_data.fd);
+            let addr = self.sock_addr.as_ptr().cast();
+            let entry = opcode::Connect::new(fd, addr, self.sock_addr.len()).build();
+            return uring::subscribe(
+                &self.io_data,
+                co,
+                entry,
+                #[cfg(feature = "io_timeout")]
+                self.timeout,
+            );
+        }
+
         #[cfg(feature = "io_cancel")]
         let cancel = co_cancel_data(&co);
         let io_data = &self.io_data;
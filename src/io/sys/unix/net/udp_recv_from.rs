@@ -4,6 +4,8 @@ use std::sync::atomic::Ordering;
 #[cfg(feature = "io_timeout")]
 use std::time::Duration;
 
+#[cfg(uring)]
+use super::super::uring::{self, MsgHdr};
 use super::super::{co_io_result, IoData};
 #[cfg(feature = "io_cancel")]
 use crate::coroutine_impl::co_cancel_data;
@@ -11,6 +13,8 @@ use crate::coroutine_impl::{is_coroutine, CoroutineImpl, EventSource};
 use crate::io::AsIoData;
 use crate::net::UdpSocket;
 use crate::yield_now::yield_with_io;
+#[cfg(uring)]
+use io_uring::{opcode, types};
 
 pub struct UdpRecvFrom<'a> {
     io_data: &'a IoData,
@@ -18,6 +22,9 @@ pub struct UdpRecvFrom<'a> {
     socket: &'a std::net::UdpSocket,
     #[cfg(feature = "io_timeout")]
     timeout: Option<Duration>,
+    // the message header of the io_uring recvmsg
+    #[cfg(uring)]
+    msg: MsgHdr,
     pub(crate) is_coroutine: bool,
 }
 
@@ -29,11 +36,19 @@ impl<'a> UdpRecvFrom<'a> {
             socket: socket.inner(),
             #[cfg(feature = "io_timeout")]
             timeout: socket.read_timeout().unwrap(),
+            #[cfg(uring)]
+            msg: MsgHdr::new(),
             is_coroutine: is_coroutine(),
         }
     }
 
     pub fn done(&mut self) -> io::Result<(usize, SocketAddr)> {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            let n = uring::done(self.io_data, self.is_coroutine)?;
+            return Ok((n, self.msg.addr()?));
+        }
+
         loop {
             co_io_result(self.is_coroutine)?;
 
@@ -65,6 +80,19 @@ impl<'a> UdpRecvFrom<'a> {
 
 impl EventSource for UdpRecvFrom<'_> {
     fn subscribe(&mut self, co: CoroutineImpl) {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            let fd = types::Fd(self.io_data.fd);
+            let entry = opcode::RecvMsg::new(fd, self.msg.recv(self.buf)).build();
+            return uring::subscribe(
+                self.io_data,
+                co,
+                entry,
+                #[cfg(feature = "io_timeout")]
+                self.timeout,
+            );
+        }
+
         #[cfg(feature = "io_cancel")]
         let cancel = co_cancel_data(&co);
         let io_data = self.io_data;
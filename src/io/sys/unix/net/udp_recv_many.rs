@@ -0,0 +1,93 @@
+use std::io;
+use std::mem;
+#[cfg(feature = "io_timeout")]
+use std::time::Duration;
+
+use super::SocketIo;
+use crate::io::AsIoData;
+use crate::net::RecvMsg;
+use smallvec::SmallVec;
+use socket2::{SockAddr, SockAddrStorage};
+
+// the kernel handles at most UIO_MAXIOV messages in one call
+pub(crate) const MAX_MMSG: usize = 1024;
+
+// the messages that are kept on the stack, the coroutine stack is small
+pub(crate) const INLINE_MMSG: usize = 8;
+
+// the control message buffer that holds one int, like UDP_GRO
+pub(crate) type CmsgBuf = [u64; 4];
+
+// receive the datagrams into the messages with one `recvmmsg` call, the
+// headers are built once and reused when the call is retried
+pub fn recv_many<T: AsIoData>(
+    s: &T,
+    msgs: &mut [RecvMsg<'_>],
+    #[cfg(feature = "io_timeout")] timeout: Option<Duration>,
+) -> io::Result<usize> {
+    let count = msgs.len().min(MAX_MMSG);
+    let msgs = &mut msgs[..count];
+    let mut iovs = SmallVec::<[libc::iovec; INLINE_MMSG]>::with_capacity(count);
+    let mut addrs = SmallVec::<[SockAddrStorage; INLINE_MMSG]>::with_capacity(count);
+    let mut cmsgs = SmallVec::<[CmsgBuf; INLINE_MMSG]>::from_elem([0; 4], count);
+    let mut hdrs = SmallVec::<[libc::mmsghdr; INLINE_MMSG]>::with_capacity(count);
+
+    for msg in msgs.iter_mut() {
+        iovs.push(libc::iovec {
+            iov_base: msg.buf.as_mut_ptr().cast(),
+            iov_len: msg.buf.len(),
+        });
+        addrs.push(SockAddrStorage::zeroed());
+    }
+    for ((addr, iov), cmsg) in addrs.iter_mut().zip(&mut iovs).zip(&mut cmsgs) {
+        let mut hdr: libc::mmsghdr = unsafe { mem::zeroed() };
+        hdr.msg_hdr.msg_namelen = addr.size_of();
+        hdr.msg_hdr.msg_name = (addr as *mut SockAddrStorage).cast();
+        hdr.msg_hdr.msg_iov = iov;
+        hdr.msg_hdr.msg_iovlen = 1;
+        hdr.msg_hdr.msg_control = cmsg.as_mut_ptr().cast();
+        hdr.msg_hdr.msg_controllen = mem::size_of::<CmsgBuf>() as _;
+        hdrs.push(hdr);
+    }
+
+    let fd = s.as_io_data().fd;
+    let n = SocketIo::new(
+        s,
+        || {
+            let n = unsafe {
+                libc::recvmmsg(fd, hdrs.as_mut_ptr(), count as _, 0, std::ptr::null_mut())
+            };
+            if n < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(n as usize)
+        },
+        #[cfg(feature = "io_timeout")]
+        timeout,
+    )
+    .done()?;
+
+    for ((msg, hdr), storage) in msgs.iter_mut().zip(&hdrs).zip(addrs).take(n) {
+        msg.len = hdr.msg_len as usize;
+        msg.truncated = hdr.msg_hdr.msg_flags & libc::MSG_TRUNC != 0;
+        let addr = unsafe { SockAddr::new(storage, hdr.msg_hdr.msg_namelen) };
+        msg.addr = addr.as_socket();
+        msg.segment_size = gro_segment(&hdr.msg_hdr);
+    }
+    Ok(n)
+}
+
+// the GRO segment size of the coalesced datagram
+fn gro_segment(hdr: &libc::msghdr) -> Option<usize> {
+    let mut cmsg = unsafe { libc::CMSG_FIRSTHDR(hdr) };
+    while !cmsg.is_null() {
+        let c = unsafe { &*cmsg };
+        if c.cmsg_level == libc::SOL_UDP && c.cmsg_type == libc::UDP_GRO {
+            let size =
+                unsafe { std::ptr::read_unaligned(libc::CMSG_DATA(cmsg).cast::<libc::c_int>()) };
+            return Some(size as usize);
+        }
+        cmsg = unsafe { libc::CMSG_NXTHDR(hdr, cmsg) };
+    }
+    None
+}
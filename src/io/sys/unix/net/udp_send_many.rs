@@ -0,0 +1,72 @@
+use std::io;
+use std::mem;
+#[cfg(feature = "io_timeout")]
+use std::time::Duration;
+
+use super::udp_recv_many::{CmsgBuf, INLINE_MMSG, MAX_MMSG};
+use super::SocketIo;
+use crate::io::AsIoData;
+use crate::net::SendMsg;
+use smallvec::SmallVec;
+use socket2::SockAddr;
+
+// send the messages with one `sendmmsg` call, the headers are built once
+// and reused when the call is retried
+pub fn send_many<T: AsIoData>(
+    s: &T,
+    msgs: &[SendMsg<'_>],
+    #[cfg(feature = "io_timeout")] timeout: Option<Duration>,
+) -> io::Result<usize> {
+    let count = msgs.len().min(MAX_MMSG);
+    let msgs = &msgs[..count];
+    let mut iovs = SmallVec::<[libc::iovec; INLINE_MMSG]>::with_capacity(count);
+    let mut addrs = SmallVec::<[Option<SockAddr>; INLINE_MMSG]>::with_capacity(count);
+    let mut cmsgs = SmallVec::<[CmsgBuf; INLINE_MMSG]>::from_elem([0; 4], count);
+    let mut hdrs = SmallVec::<[libc::mmsghdr; INLINE_MMSG]>::with_capacity(count);
+
+    for msg in msgs {
+        iovs.push(libc::iovec {
+            iov_base: msg.buf.as_ptr() as *mut _,
+            iov_len: msg.buf.len(),
+        });
+        addrs.push(msg.addr.map(SockAddr::from));
+    }
+    for (i, msg) in msgs.iter().enumerate() {
+        let mut hdr: libc::mmsghdr = unsafe { mem::zeroed() };
+        if let Some(addr) = &addrs[i] {
+            hdr.msg_hdr.msg_name = addr.as_ptr() as *mut _;
+            hdr.msg_hdr.msg_namelen = addr.len();
+        }
+        hdr.msg_hdr.msg_iov = &mut iovs[i];
+        hdr.msg_hdr.msg_iovlen = 1;
+        if let Some(size) = msg.segment_size {
+            // the GSO segment size, the kernel splits the buffer into datagrams
+            let space = unsafe { libc::CMSG_SPACE(mem::size_of::<u16>() as _) };
+            hdr.msg_hdr.msg_control = cmsgs[i].as_mut_ptr().cast();
+            hdr.msg_hdr.msg_controllen = space as _;
+            unsafe {
+                let cmsg = &mut *libc::CMSG_FIRSTHDR(&hdr.msg_hdr);
+                cmsg.cmsg_level = libc::SOL_UDP;
+                cmsg.cmsg_type = libc::UDP_SEGMENT;
+                cmsg.cmsg_len = libc::CMSG_LEN(mem::size_of::<u16>() as _) as _;
+                std::ptr::write_unaligned(libc::CMSG_DATA(cmsg).cast::<u16>(), size);
+            }
+        }
+        hdrs.push(hdr);
+    }
+
+    let fd = s.as_io_data().fd;
+    SocketIo::new(
+        s,
+        || {
+            let n = unsafe { libc::sendmmsg(fd, hdrs.as_mut_ptr(), count as _, 0) };
+            if n < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(n as usize)
+        },
+        #[cfg(feature = "io_timeout")]
+        timeout,
+    )
+    .done()
+}
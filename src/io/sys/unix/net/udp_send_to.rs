@@ -4,11 +4,15 @@ use std::sync::atomic::Ordering;
 #[cfg(feature = "io_timeout")]
 use std::time::Duration;
 
+#[cfg(uring)]
+use super::super::uring::{self, MsgHdr};
 use super::super::{co_io_result, IoData};
 use crate::coroutine_impl::{is_coroutine, CoroutineImpl, EventSource};
 use crate::io::AsIoData;
 use crate::net::UdpSocket;
 use crate::yield_now::yield_with_io;
+#[cfg(uring)]
+use io_uring::{opcode, types};
 
 pub struct UdpSendTo<'a, A: ToSocketAddrs> {
     io_data: &'a IoData,
@@ -17,11 +21,25 @@ pub struct UdpSendTo<'a, A: ToSocketAddrs> {
     addr: A,
     #[cfg(feature = "io_timeout")]
     timeout: Option<Duration>,
+    // the message header of the io_uring sendmsg
+    #[cfg(uring)]
+    msg: MsgHdr,
     pub(crate) is_coroutine: bool,
 }
 
 impl<'a, A: ToSocketAddrs> UdpSendTo<'a, A> {
     pub fn new(socket: &'a UdpSocket, buf: &'a [u8], addr: A) -> io::Result<Self> {
+        #[cfg(uring)]
+        let msg = if uring::is_enabled() {
+            // only send to the first address like the std
+            let addr = addr.to_socket_addrs()?.next().ok_or_else(|| {
+                io::Error::new(io::ErrorKind::InvalidInput, "no addresses to send data to")
+            })?;
+            MsgHdr::with_addr(addr.into())
+        } else {
+            MsgHdr::new()
+        };
+
         Ok(UdpSendTo {
             io_data: socket.as_io_data(),
             buf,
@@ -29,11 +47,18 @@ impl<'a, A: ToSocketAddrs> UdpSendTo<'a, A> {
             addr,
             #[cfg(feature = "io_timeout")]
             timeout: socket.write_timeout().unwrap(),
+            #[cfg(uring)]
+            msg,
             is_coroutine: is_coroutine(),
         })
     }
 
     pub fn done(&mut self) -> io::Result<usize> {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            return uring::done(self.io_data, self.is_coroutine);
+        }
+
         loop {
             co_io_result(self.is_coroutine)?;
 
@@ -65,6 +90,19 @@ impl<'a, A: ToSocketAddrs> UdpSendTo<'a, A> {
 
 impl<A: ToSocketAddrs> EventSource for UdpSendTo<'_, A> {
     fn subscribe(&mut self, co: CoroutineImpl) {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            let fd = types::Fd(self.io_data.fd);
+            let entry = opcode::SendMsg::new(fd, self.msg.send(self.buf)).build();
+            return uring::subscribe(
+                self.io_data,
+                co,
+                entry,
+                #[cfg(feature = "io_timeout")]
+                self.timeout,
+            );
+        }
+
         let io_data = self.io_data;
 
         #[cfg(feature = "io_timeout")]
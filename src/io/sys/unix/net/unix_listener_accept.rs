@@ -1,14 +1,20 @@
 use std::io;
+#[cfg(uring)]
+use std::os::fd::{FromRawFd, RawFd};
 use std::os::unix::net::{self, SocketAddr};
 use std::sync::atomic::Ordering;
 
 #[cfg(feature = "io_cancel")]
 use crate::coroutine_impl::co_cancel_data;
 use crate::coroutine_impl::{is_coroutine, CoroutineImpl, EventSource};
+#[cfg(uring)]
+use crate::io::sys::uring;
 use crate::io::sys::{co_io_result, IoData};
 use crate::io::{AsIoData, CoIo};
 use crate::os::unix::net::{UnixListener, UnixStream};
 use crate::yield_now::yield_with_io;
+#[cfg(uring)]
+use io_uring::{opcode, types};
 
 pub struct UnixListenerAccept<'a> {
     io_data: &'a IoData,
@@ -26,6 +32,15 @@ impl<'a> UnixListenerAccept<'a> {
     }
 
     pub fn done(&mut self) -> io::Result<(UnixStream, SocketAddr)> {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            let fd = uring::done(self.io_data, self.is_coroutine)? as RawFd;
+            let s = unsafe { net::UnixStream::from_raw_fd(fd) };
+            // the unix socket address can only be got from the socket
+            let a = s.peer_addr()?;
+            return Ok((UnixStream::from_coio(CoIo::new(s)?), a));
+        }
+
         loop {
             co_io_result(self.is_coroutine)?;
 
@@ -60,6 +75,21 @@ impl<'a> UnixListenerAccept<'a> {
 
 impl EventSource for UnixListenerAccept<'_> {
     fn subscribe(&mut self, co: CoroutineImpl) {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            let fd = types::Fd(self.io_data.fd);
+            let entry = opcode::Accept::new(fd, std::ptr::null_mut(), std::ptr::null_mut())
+                .flags(libc::SOCK_CLOEXEC)
+                .build();
+            return uring::subscribe(
+                self.io_data,
+                co,
+                entry,
+                #[cfg(feature = "io_timeout")]
+                None,
+            );
+        }
+
         #[cfg(feature = "io_cancel")]
         let cancel = co_cancel_data(&co);
         let io_data = self.io_data;
@@ -1,46 +1,67 @@
 use std::io;
-use std::path::Path;
 use std::sync::atomic::Ordering;
 #[cfg(feature = "io_timeout")]
 use std::time::Duration;
 
+#[cfg(uring)]
+use super::super::uring::{self, MsgHdr};
 use super::super::{co_io_result, IoData};
 use crate::coroutine_impl::{is_coroutine, CoroutineImpl, EventSource};
 use crate::io::AsIoData;
 use crate::os::unix::net::UnixDatagram;
 use crate::yield_now::yield_with_io;
+#[cfg(uring)]
+use io_uring::{opcode, types};
+use socket2::{SockAddr, SockRef};
 
 pub struct UnixSendTo<'a> {
     io_data: &'a IoData,
     buf: &'a [u8],
     socket: &'a std::os::unix::net::UnixDatagram,
-    path: &'a Path,
+    addr: SockAddr,
     #[cfg(feature = "io_timeout")]
     timeout: Option<Duration>,
+    // the message header of the io_uring sendmsg
+    #[cfg(uring)]
+    msg: MsgHdr,
     pub(crate) is_coroutine: bool,
 }
 
 impl<'a> UnixSendTo<'a> {
-    pub fn new(socket: &'a UnixDatagram, buf: &'a [u8], path: &'a Path) -> io::Result<Self> {
-        Ok(UnixSendTo {
+    pub fn new(socket: &'a UnixDatagram, buf: &'a [u8], addr: SockAddr) -> Self {
+        #[cfg(uring)]
+        let msg = if uring::is_enabled() {
+            MsgHdr::with_addr(addr.clone())
+        } else {
+            MsgHdr::new()
+        };
+
+        UnixSendTo {
             io_data: socket.0.as_io_data(),
             buf,
             socket: socket.0.inner(),
-            path,
+            addr,
             #[cfg(feature = "io_timeout")]
             timeout: socket.write_timeout().unwrap(),
+            #[cfg(uring)]
+            msg,
             is_coroutine: is_coroutine(),
-        })
+        }
     }
 
     pub fn done(&mut self) -> io::Result<usize> {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            return uring::done(self.io_data, self.is_coroutine);
+        }
+
         loop {
             co_io_result(self.is_coroutine)?;
 
             // clear the io_flag
             self.io_data.io_flag.store(0, Ordering::Relaxed);
 
-            match self.socket.send_to(self.buf, self.path) {
+            match SockRef::from(self.socket).send_to(self.buf, &self.addr) {
                 Ok(n) => return Ok(n),
                 Err(e) => {
                     // raw_os_error is faster than kind
@@ -65,6 +86,19 @@ impl<'a> UnixSendTo<'a> {
 
 impl EventSource for UnixSendTo<'_> {
     fn subscribe(&mut self, co: CoroutineImpl) {
+        #[cfg(uring)]
+        if uring::is_enabled() {
+            let fd = types::Fd(self.io_data.fd);
+            let entry = opcode::SendMsg::new(fd, self.msg.send(self.buf)).build();
+            return uring::subscribe(
+                self.io_data,
+                co,
+                entry,
+                #[cfg(feature = "io_timeout")]
+                self.timeout,
+            );
+        }
+
         let io_data = self.io_data;
 
         #[cfg(feature = "io_timeout")]
@@ -1,4 +1,5 @@
 use std::io;
+use std::os::fd::AsRawFd;
 use std::path::Path;
 use std::sync::atomic::Ordering;
 #[cfg(feature = "io_timeout")]
@@ -23,8 +24,12 @@ pub struct UnixStreamConnect {
 
 impl UnixStreamConnect {
     pub fn new<P: AsRef<Path>>(path: P) -> io::Result<Self> {
-        let path = SockAddr::unix(path)?;
-        let socket = Socket::new(Domain::UNIX, Type::STREAM, None)?;
+        Self::with_addr(SockAddr::unix(path)?, Type::STREAM)
+    }
+
+    // connect to the address with the socket type, like `Type::SEQPACKET`
+    pub fn with_addr(path: SockAddr, ty: Type) -> io::Result<Self> {
+        let socket = Socket::new(Domain::UNIX, ty, None)?;
         // before yield we must set the socket to nonblocking mode and register to selector
         socket.set_nonblocking(true)?;
         add_socket(&socket).map(|io| UnixStreamConnect {
@@ -52,9 +57,14 @@ impl UnixStreamConnect {
     }
 
     pub fn done(&mut self) -> io::Result<UnixStream> {
-        fn convert_to_stream(s: &mut UnixStreamConnect) -> UnixStream {
+        self.done_io().map(UnixStream::from_coio)
+    }
+
+    // return the connected socket as the CoIo of any socket type
+    pub fn done_io<T: AsRawFd + From<Socket>>(&mut self) -> io::Result<CoIo<T>> {
+        fn convert_to_stream<T: AsRawFd + From<Socket>>(s: &mut UnixStreamConnect) -> CoIo<T> {
             let stream = s.stream.take().into();
-            UnixStream::from_coio(CoIo::from_raw(stream, s.io_data.take()))
+            CoIo::from_raw(stream, s.io_data.take())
         }
 
         // first check if it's already connected
@@ -0,0 +1,279 @@
+//! io_uring based completion io
+//!
+//! each worker owns a ring, the requests queued by the coroutines running
+//! in the worker are submitted together in the next `select`, and the
+//! coroutines are resumed when the completions are reaped. the ring fd is
+//! registered to the epoll so that a completion wakes up the worker.
+
+use std::net::SocketAddr;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, RawFd};
+use std::sync::atomic::Ordering;
+#[cfg(feature = "io_timeout")]
+use std::time::{Duration, Instant};
+use std::{io, mem};
+
+use super::{co_io_result, EventData, IoData};
+#[cfg(feature = "io_cancel")]
+use crate::coroutine_impl::co_cancel_data;
+use crate::coroutine_impl::CoroutineImpl;
+use crate::scheduler::get_scheduler;
+
+#[cfg(feature = "io_timeout")]
+use io_uring::{opcode, types};
+use io_uring::{squeue, IoUring};
+use parking_lot::Mutex;
+use smallvec::SmallVec;
+use socket2::{SockAddr, SockAddrStorage};
+
+// the submission queue size of each ring
+const RING_ENTRIES: u32 = 1024;
+
+// the user data of the requests that we don't care the completion
+pub const IGNORE_DATA: u64 = 3;
+
+// the event data has no in-flight request
+pub const NO_RING: usize = usize::MAX;
+
+pub struct Ring {
+    fd: RawFd,
+    ring: Mutex<IoUring>,
+}
+
+impl Ring {
+    pub fn new() -> io::Result<Self> {
+        let ring = IoUring::new(RING_ENTRIES)?;
+        Ok(Ring {
+            fd: ring.as_raw_fd(),
+            ring: Mutex::new(ring),
+        })
+    }
+
+    // queue the entries, they would be submitted in the next select
+    // the linked entries must be pushed together
+    pub fn push(&self, entries: &[squeue::Entry]) -> io::Result<()> {
+        let mut ring = self.ring.lock();
+        // SAFETY: the buffers of the request are kept alive by the
+        // suspended coroutine until the completion is reaped
+        if unsafe { ring.submission().push_multiple(entries) }.is_err() {
+            // the submission queue is full, flush it first
+            ring.submit()?;
+            unsafe { ring.submission().push_multiple(entries) }
+                .map_err(|_| io::Error::other("io_uring submission queue is full"))?;
+        }
+        Ok(())
+    }
+
+    // submit all the queued entries
+    pub fn submit(&self) -> io::Result<usize> {
+        self.ring.lock().submit()
+    }
+
+    // reap all the completions, the callback is called without the lock
+    pub fn complete(&self, mut f: impl FnMut(u64, i32)) {
+        loop {
+            let cqes: SmallVec<[(u64, i32); 64]> = {
+                let mut ring = self.ring.lock();
+                let cq = ring.completion();
+                cq.take(64).map(|c| (c.user_data(), c.result())).collect()
+            };
+            if cqes.is_empty() {
+                return;
+            }
+            for (user_data, res) in cqes {
+                f(user_data, res);
+            }
+        }
+    }
+}
+
+impl AsFd for Ring {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        unsafe { BorrowedFd::borrow_raw(self.fd) }
+    }
+}
+
+// return true if the io requests are submitted to io_uring
+#[inline]
+pub fn is_enabled() -> bool {
+    get_scheduler().get_selector().has_uring()
+}
+
+// convert the failed completion result to the io error
+#[cfg_attr(not(feature = "io_timeout"), allow(unused_variables))]
+pub fn uring_error(io_data: &EventData, res: i32) -> io::Error {
+    if res != -libc::ECANCELED {
+        return io::Error::from_raw_os_error(-res);
+    }
+    // the linked timeout fires no earlier than the deadline
+    #[cfg(feature = "io_timeout")]
+    if let Some(deadline) = unsafe { *io_data.uring_deadline.get() } {
+        if Instant::now() >= deadline {
+            return io::Error::new(io::ErrorKind::TimedOut, "timeout");
+        }
+    }
+    // canceled by others, e.g. the coroutine is canceled
+    io::Error::new(io::ErrorKind::Interrupted, "canceled")
+}
+
+/// submit the io request, the coroutine is resumed on completion
+///
+/// the entry and the buffers it refers must be valid until the coroutine
+/// is resumed, it's true for the `EventSource` that is yielded.
+pub fn subscribe(
+    io_data: &IoData,
+    co: CoroutineImpl,
+    entry: squeue::Entry,
+    #[cfg(feature = "io_timeout")] timeout: Option<Duration>,
+) {
+    #[cfg(feature = "io_cancel")]
+    let cancel = co_cancel_data(&co);
+    let selector = get_scheduler().get_selector();
+    let entry = entry.user_data(io_data.as_ref() as *const EventData as u64);
+
+    #[cfg(feature = "io_timeout")]
+    unsafe {
+        *io_data.uring_deadline.get() = timeout.map(|dur| Instant::now() + dur)
+    };
+    #[cfg(feature = "io_timeout")]
+    if let Some(dur) = timeout {
+        // the timespec is read when the entry is submitted
+        let ts = io_data.uring_ts.get();
+        unsafe { *ts = types::Timespec::from(dur) };
+        let link = opcode::LinkTimeout::new(ts).build().user_data(IGNORE_DATA);
+        selector.submit_uring(io_data, co, &[entry.flags(squeue::Flags::IO_LINK), link]);
+    } else {
+        selector.submit_uring(io_data, co, &[entry]);
+    }
+    #[cfg(not(feature = "io_timeout"))]
+    selector.submit_uring(io_data, co, &[entry]);
+
+    #[cfg(feature = "io_cancel")]
+    {
+        // register the cancel io data
+        cancel.set_io((*io_data).clone());
+        // re-check the cancel status
+        if cancel.is_canceled() {
+            unsafe { cancel.cancel() };
+        }
+    }
+}
+
+/// get the result of the completed io request
+#[inline]
+pub fn done(io_data: &IoData, is_coroutine: bool) -> io::Result<usize> {
+    co_io_result(is_coroutine)?;
+    Ok(io_data.uring_res.load(Ordering::Acquire) as usize)
+}
+
+// the socket address that filled by the kernel
+pub struct AddrBuf {
+    storage: SockAddrStorage,
+    len: libc::socklen_t,
+}
+
+impl AddrBuf {
+    pub fn new() -> Self {
+        let storage = SockAddrStorage::zeroed();
+        let len = storage.size_of();
+        AddrBuf { storage, len }
+    }
+
+    pub fn as_mut_ptr(&mut self) -> (*mut libc::sockaddr, *mut libc::socklen_t) {
+        let addr = &mut self.storage as *mut SockAddrStorage;
+        (addr.cast(), &mut self.len)
+    }
+
+    pub fn take_socket_addr(&mut self) -> io::Result<SocketAddr> {
+        let storage = mem::replace(&mut self.storage, SockAddrStorage::zeroed());
+        let addr = unsafe { SockAddr::new(storage, self.len) };
+        addr.as_socket()
+            .ok_or_else(|| io::Error::other("invalid socket address"))
+    }
+}
+
+// the message header of sendmsg/recvmsg
+pub struct MsgHdr {
+    msg: libc::msghdr,
+    iov: libc::iovec,
+    addr: AddrBuf,
+}
+
+impl MsgHdr {
+    pub fn new() -> Self {
+        MsgHdr {
+            msg: unsafe { mem::zeroed() },
+            iov: unsafe { mem::zeroed() },
+            addr: AddrBuf::new(),
+        }
+    }
+
+    // the message is sent to the address
+    pub fn with_addr(addr: SockAddr) -> Self {
+        let len = addr.len();
+        MsgHdr {
+            addr: AddrBuf {
+                storage: addr.as_storage(),
+                len,
+            },
+            ..MsgHdr::new()
+        }
+    }
+
+    // prepare the message header for recvmsg, must not move after that
+    pub fn recv(&mut self, buf: &mut [u8]) -> *mut libc::msghdr {
+        self.addr.len = self.addr.storage.size_of();
+        self.init(buf.as_mut_ptr(), buf.len());
+        &mut self.msg
+    }
+
+    // prepare the message header for sendmsg, must not move after that
+    pub fn send(&mut self, buf: &[u8]) -> *const libc::msghdr {
+        self.init(buf.as_ptr() as *mut u8, buf.len());
+        &self.msg
+    }
+
+    fn init(&mut self, buf: *mut u8, len: usize) {
+        self.iov = libc::iovec {
+            iov_base: buf.cast(),
+            iov_len: len,
+        };
+        let (name, _) = self.addr.as_mut_ptr();
+        self.msg.msg_name = name.cast();
+        self.msg.msg_namelen = self.addr.len;
+        self.msg.msg_iov = &mut self.iov;
+        self.msg.msg_iovlen = 1;
+    }
+
+    // the source address of the received message
+    pub fn addr(&mut self) -> io::Result<SocketAddr> {
+        self.addr.len = self.msg.msg_namelen;
+        self.addr.take_socket_addr()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn canceled_error() {
+        let io_data = EventData::new(-1);
+        let err = uring_error(&io_data, -libc::EBADF);
+        assert_eq!(err.raw_os_error(), Some(libc::EBADF));
+        let err = uring_error(&io_data, -libc::ECANCELED);
+        assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+
+        #[cfg(feature = "io_timeout")]
+        {
+            let deadline = io_data.uring_deadline.get();
+            // canceled before the linked timeout fires
+            unsafe { *deadline = Some(Instant::now() + Duration::from_secs(10)) };
+            let err = uring_error(&io_data, -libc::ECANCELED);
+            assert_eq!(err.kind(), io::ErrorKind::Interrupted);
+            // canceled by the linked timeout
+            unsafe { *deadline = Some(Instant::now()) };
+            let err = uring_error(&io_data, -libc::ECANCELED);
+            assert_eq!(err.kind(), io::ErrorKind::TimedOut);
+        }
+    }
+}
@@ -4,9 +4,11 @@ use std::time::Duration;
 use std::{io, ptr};
 
 use super::miow::{CompletionPort, CompletionStatus};
+#[cfg(feature = "io_timeout")]
+use crate::config::config;
 use crate::coroutine_impl::CoroutineImpl;
 use crate::scheduler::Scheduler;
-use crate::timeout_list::{now, TimeOutList, TimeoutHandle};
+use crate::timeout_list::{now, TimeoutHandle, TimerWheel};
 use crate::yield_now::set_co_para;
 use smallvec::SmallVec;
 use windows_sys::Win32::Foundation::*;
@@ -17,7 +19,7 @@ pub struct TimerData {
     event_data: *mut EventData,
 }
 
-type TimerList = TimeOutList<TimerData>;
+type TimerList = TimerWheel<TimerData>;
 pub type TimerHandle = TimeoutHandle<TimerData>;
 
 // event associated io data, must be construct in the coroutine
@@ -137,12 +139,10 @@ impl Selector {
             // running the timer_list in the same thread
             // this is not true when running in multi-thread environment
             data.timer.take().map(|h| {
-                unsafe {
-                    // tell the timer function not to cancel the io
-                    // it's not always true that you can really remove the timer entry
-                    // it's safe in multi-thread env because it only access its own data
-                    h.with_mut_data(|value| value.data.event_data = ptr::null_mut());
-                }
+                // tell the timer function not to cancel the io
+                // it's not always true that you can really remove the timer entry
+                // it's safe in multi-thread env because it only access its own data
+                h.with_mut_data(|value| value.event_data = ptr::null_mut());
                 // NOT SAFE for multi-thread!!
                 h.remove()
             });
@@ -182,6 +182,9 @@ impl Selector {
             crate::coroutine_impl::run_coroutine(co);
         }
 
+        // resume the coroutines that their timers expired
+        scheduler.run_timers(id);
+
         // run all the local tasks
         scheduler.run_queued_tasks(id);
 
@@ -189,7 +192,8 @@ impl Selector {
         let next_expire = single_selector
             .timer_list
             .schedule_timer(now(), &timeout_handler);
-        Ok(next_expire)
+        // the coroutine timers may expire earlier
+        Ok(scheduler.next_timer_expire(id, next_expire))
     }
 
     // this will post an os event so that we can wakeup the event loop
@@ -217,7 +221,11 @@ impl Selector {
     pub fn add_io_timer(&self, io: &mut EventData, timeout: Duration) {
         let id = (io.handle as usize % self.vec.len()) >> 2;
         // info!("io timeout = {:?}", dur);
-        let (h, b_new) = self.vec[id].timer_list.add_timer(timeout, io.timer_data());
+        let slack = config().get_timer_slack();
+        let (h, b_new) =
+            self.vec[id]
+                .timer_list
+                .add_timer_with_slack(timeout, slack, io.timer_data());
         if b_new {
             // wakeup the event loop thread to recall the next wait timeout
             self.wakeup(0);
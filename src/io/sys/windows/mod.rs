@@ -43,6 +43,11 @@ impl IoData {
 
     // clear the io flag
     pub fn reset(&self) {}
+
+    // the io data of the write half, each overlapped io has its own event
+    pub fn split_writer(&self) -> IoData {
+        IoData
+    }
 }
 
 impl fmt::Debug for IoData {
@@ -15,6 +15,7 @@ use crate::io::OptionCell;
 use crate::net::TcpStream;
 use crate::scheduler::get_scheduler;
 use crate::sync::delay_drop::DelayDrop;
+use socket2::Socket;
 use windows_sys::Win32::Foundation::*;
 
 pub struct TcpStreamConnect {
@@ -32,52 +33,56 @@ impl TcpStreamConnect {
         addr: A,
         #[cfg(feature = "io_timeout")] timeout: Option<Duration>,
     ) -> io::Result<Self> {
-        use socket2::{Domain, Socket, Type};
+        use socket2::{Domain, Type};
 
-        // TODO:
-        // resolve addr is a blocking operation!
-        // here we should use a thread to finish the resolve?
-        addr.to_socket_addrs()?
+        let addr = addr
+            .to_socket_addrs()?
             .next()
-            .ok_or_else(|| io::Error::other("no socket addresses resolved"))
-            .and_then(|addr| {
-                let socket = match addr {
-                    SocketAddr::V4(..) => Socket::new(Domain::IPV4, Type::STREAM, None)?,
-                    SocketAddr::V6(..) => Socket::new(Domain::IPV6, Type::STREAM, None)?,
-                };
-                Ok((socket, addr))
-            })
-            .and_then(|(socket, addr)| {
-                // windows need to bind first when call ConnectEx API
-                let any = match addr {
-                    SocketAddr::V4(..) => {
-                        let any = Ipv4Addr::new(0, 0, 0, 0);
-                        let addr = SocketAddrV4::new(any, 0);
-                        SocketAddr::V4(addr)
-                    }
-                    SocketAddr::V6(..) => {
-                        let any = Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0);
-                        let addr = SocketAddrV6::new(any, 0, 0, 0);
-                        SocketAddr::V6(addr)
-                    }
-                };
+            .ok_or_else(|| io::Error::other("no socket addresses resolved"))?;
+        let socket = Socket::new(Domain::for_address(addr), Type::STREAM, None)?;
+        Self::from_socket(
+            socket,
+            addr,
+            #[cfg(feature = "io_timeout")]
+            timeout,
+        )
+    }
+
+    // connect with the socket that is configured by the user
+    pub fn from_socket(
+        socket: Socket,
+        addr: SocketAddr,
+        #[cfg(feature = "io_timeout")] timeout: Option<Duration>,
+    ) -> io::Result<Self> {
+        // windows need to bind first when call ConnectEx API
+        if socket.local_addr().is_err() {
+            let any = match addr {
+                SocketAddr::V4(..) => {
+                    let any = Ipv4Addr::new(0, 0, 0, 0);
+                    let addr = SocketAddrV4::new(any, 0);
+                    SocketAddr::V4(addr)
+                }
+                SocketAddr::V6(..) => {
+                    let any = Ipv6Addr::new(0, 0, 0, 0, 0, 0, 0, 0);
+                    let addr = SocketAddrV6::new(any, 0, 0, 0);
+                    SocketAddr::V6(addr)
+                }
+            };
+            socket.bind(&any.into())?;
+        }
 
-                socket.bind(&any.into()).map(|_| socket.into()).and_then(
-                    |s: std::net::TcpStream| {
-                        // must register io first
-                        s.set_nonblocking(true)?;
-                        add_socket(&s).map(|_io| TcpStreamConnect {
-                            io_data: EventData::new(s.as_raw_socket() as HANDLE),
-                            addr,
-                            stream: OptionCell::new(s),
-                            #[cfg(feature = "io_timeout")]
-                            timeout,
-                            can_drop: DelayDrop::new(),
-                            is_coroutine: is_coroutine(),
-                        })
-                    },
-                )
-            })
+        let s: std::net::TcpStream = socket.into();
+        // must register io first
+        s.set_nonblocking(true)?;
+        add_socket(&s).map(|_io| TcpStreamConnect {
+            io_data: EventData::new(s.as_raw_socket() as HANDLE),
+            addr,
+            stream: OptionCell::new(s),
+            #[cfg(feature = "io_timeout")]
+            timeout,
+            can_drop: DelayDrop::new(),
+            is_coroutine: is_coroutine(),
+        })
     }
 
     pub fn done(&mut self) -> io::Result<TcpStream> {
@@ -1,7 +1,10 @@
 use std::any::Any;
 use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::task::{Context, Poll, Waker};
 use std::thread::Result;
 
 use crate::coroutine_impl::Coroutine;
@@ -11,6 +14,8 @@ use generator::Error;
 pub struct Join {
     // the coroutine that waiting for this join handler
     to_wake: AtomicOption<Arc<Blocker>>,
+    // the async task that waiting for this join handler
+    waker: AtomicOption<Waker>,
     // the flag indicate if the host coroutine is not finished
     // when set to false, the coroutine is done
     state: AtomicBool,
@@ -27,6 +32,7 @@ impl Join {
     pub fn new(panic: Arc<AtomicOption<Box<dyn Any + Send>>>) -> Self {
         Join {
             to_wake: AtomicOption::none(),
+            waker: AtomicOption::none(),
             state: AtomicBool::new(true),
             panic,
         }
@@ -42,6 +48,19 @@ impl Join {
         if let Some(w) = self.to_wake.take() {
             w.unpark();
         }
+        if let Some(w) = self.waker.take() {
+            w.wake();
+        }
+    }
+
+    // register the waker, return true if the coroutine is still running
+    fn register_waker(&self, waker: &Waker) -> bool {
+        if !self.state.load(Ordering::Acquire) {
+            return false;
+        }
+        self.waker.store(waker.clone());
+        // re-check the state
+        self.state.load(Ordering::Acquire)
     }
 
     fn wait(&self) {
@@ -105,14 +124,29 @@ impl<T> JoinHandle<T> {
     /// Join the coroutine, returning the result it produced.
     pub fn join(self) -> Result<T> {
         self.join.wait();
+        self.take_result()
+    }
 
-        // take the result
+    // take the result
+    fn take_result(&self) -> Result<T> {
         self.packet
             .take()
             .ok_or_else(|| self.panic.take().unwrap_or_else(|| Box::new(Error::Cancel)))
     }
 }
 
+/// The join handle can be awaited in async code, the output is the same as `join`
+impl<T> Future for JoinHandle<T> {
+    type Output = Result<T>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<T>> {
+        if self.join.register_waker(cx.waker()) {
+            return Poll::Pending;
+        }
+        Poll::Ready(self.take_result())
+    }
+}
+
 impl<T> fmt::Debug for JoinHandle<T> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.pad("JoinHandle { .. }")
@@ -25,6 +25,7 @@
 #[macro_use]
 extern crate log;
 
+mod block_on;
 mod cancel;
 mod config;
 mod join;
@@ -35,21 +36,28 @@ mod pool;
 mod sleep;
 #[macro_use]
 mod macros;
+mod blocking;
 mod coroutine_impl;
 mod scheduler;
 mod scoped;
+mod stream;
 mod timeout_list;
 mod yield_now;
 
 #[cfg(feature = "crossbeam_queue_steal")]
 mod crossbeam_queue_shim;
 
+pub mod codec;
 pub mod coroutine;
 pub mod cqueue;
+pub mod fs;
 pub mod io;
 pub mod net;
 pub mod os;
+pub mod process;
+pub mod signal;
 pub mod sync;
+pub mod time;
 pub use crate::config::{config, Config};
 pub use crate::local::LocalKey;
 // re-export may_queue
@@ -0,0 +1,483 @@
+//! Socket control messages, the ancillary data of `sendmsg` and `recvmsg`
+
+use std::fmt;
+use std::io::{self, IoSlice, IoSliceMut};
+use std::mem;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::os::fd::RawFd;
+use std::ptr;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::io::sys::net as net_impl;
+use crate::io::AsIoData;
+use socket2::{SockAddr, SockAddrStorage};
+
+// don't raise SIGPIPE when the peer is closed
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const SEND_FLAGS: libc::c_int = libc::MSG_NOSIGNAL;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const SEND_FLAGS: libc::c_int = 0;
+
+// the received file descriptors are closed on exec
+#[cfg(any(target_os = "linux", target_os = "android"))]
+const RECV_FLAGS: libc::c_int = libc::MSG_CMSG_CLOEXEC;
+#[cfg(not(any(target_os = "linux", target_os = "android")))]
+const RECV_FLAGS: libc::c_int = 0;
+
+/// The credentials of a process, like the peer of a unix socket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct UCred {
+    /// The process id, it's not available on all platforms.
+    pub pid: Option<libc::pid_t>,
+    /// The user id.
+    pub uid: libc::uid_t,
+    /// The group id.
+    pub gid: libc::gid_t,
+}
+
+/// A buffer of the control messages.
+///
+/// The messages are encoded by the `push` methods before sending, and the
+/// received messages are decoded by [`CmsgBuffer::iter`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use may::net::{CmsgBuffer, ControlMessage};
+/// use may::os::unix::net::UnixDatagram;
+/// use std::io::IoSliceMut;
+///
+/// let sock = UnixDatagram::bind("/tmp/sock").unwrap();
+/// let mut buf = [0; 1024];
+/// let mut cmsg = CmsgBuffer::with_capacity(CmsgBuffer::space(64));
+/// sock.recv_msg(&mut [IoSliceMut::new(&mut buf)], &mut cmsg).unwrap();
+/// for msg in cmsg.iter() {
+///     if let ControlMessage::Rights(fds) = msg {
+///         println!("received fds: {:?}", fds);
+///     }
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct CmsgBuffer {
+    // u64 keeps the buffer aligned for the cmsghdr
+    buf: Vec<u64>,
+    len: usize,
+    truncated: bool,
+}
+
+impl CmsgBuffer {
+    /// Creates an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a buffer that receives at most `capacity` bytes of control
+    /// messages, see [`CmsgBuffer::space`].
+    pub fn with_capacity(capacity: usize) -> Self {
+        CmsgBuffer {
+            buf: vec![0; capacity.div_ceil(mem::size_of::<u64>())],
+            len: 0,
+            truncated: false,
+        }
+    }
+
+    /// Returns the buffer space of a control message with `len` bytes data.
+    pub fn space(len: usize) -> usize {
+        unsafe { libc::CMSG_SPACE(len as _) as usize }
+    }
+
+    /// Returns the number of bytes the buffer can hold without growing.
+    pub fn capacity(&self) -> usize {
+        self.buf.len() * mem::size_of::<u64>()
+    }
+
+    /// Returns the number of bytes of the control messages.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there is no control message.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Removes all the control messages, the capacity is kept.
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.truncated = false;
+    }
+
+    /// Returns true if some received control messages are discarded since
+    /// the buffer is too small, the `MSG_CTRUNC` flag.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// Appends a raw control message.
+    pub fn push(&mut self, level: libc::c_int, ty: libc::c_int, data: &[u8]) {
+        let space = Self::space(data.len());
+        let end = self.len + space;
+        if end > self.capacity() {
+            self.buf.resize(end.div_ceil(mem::size_of::<u64>()), 0);
+        }
+        unsafe {
+            let hdr = self.buf.as_mut_ptr().cast::<u8>().add(self.len);
+            // clear the padding
+            ptr::write_bytes(hdr, 0, space);
+            let hdr = hdr.cast::<libc::cmsghdr>();
+            (*hdr).cmsg_level = level;
+            (*hdr).cmsg_type = ty;
+            (*hdr).cmsg_len = libc::CMSG_LEN(data.len() as _) as _;
+            ptr::copy_nonoverlapping(data.as_ptr(), libc::CMSG_DATA(hdr), data.len());
+        }
+        self.len = end;
+    }
+
+    // appends a control message that holds the value
+    fn push_value<T: Copy>(&mut self, level: libc::c_int, ty: libc::c_int, value: &T) {
+        let data = unsafe {
+            std::slice::from_raw_parts((value as *const T).cast::<u8>(), mem::size_of::<T>())
+        };
+        self.push(level, ty, data);
+    }
+
+    /// Appends the file descriptors that are passed to the peer of a unix
+    /// socket, the `SCM_RIGHTS` message.
+    pub fn push_rights(&mut self, fds: &[RawFd]) {
+        let data =
+            unsafe { std::slice::from_raw_parts(fds.as_ptr().cast::<u8>(), mem::size_of_val(fds)) };
+        self.push(libc::SOL_SOCKET, libc::SCM_RIGHTS, data);
+    }
+
+    /// Appends the credentials that are sent to the peer of a unix socket,
+    /// the `SCM_CREDENTIALS` message.
+    ///
+    /// The pid of the current process is used if `pid` is `None`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn push_credentials(&mut self, cred: UCred) {
+        let cred = libc::ucred {
+            pid: cred.pid.unwrap_or_else(|| unsafe { libc::getpid() }),
+            uid: cred.uid,
+            gid: cred.gid,
+        };
+        self.push_value(libc::SOL_SOCKET, libc::SCM_CREDENTIALS, &cred);
+    }
+
+    /// Appends the source address and the outgoing interface of an IPv4
+    /// datagram, the `IP_PKTINFO` message.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn push_pktinfo_v4(&mut self, ifindex: u32, src: Ipv4Addr) {
+        let mut info: libc::in_pktinfo = unsafe { mem::zeroed() };
+        info.ipi_ifindex = ifindex as _;
+        info.ipi_spec_dst.s_addr = u32::from(src).to_be();
+        self.push_value(libc::IPPROTO_IP, libc::IP_PKTINFO, &info);
+    }
+
+    /// Appends the source address and the outgoing interface of an IPv6
+    /// datagram, the `IPV6_PKTINFO` message.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn push_pktinfo_v6(&mut self, ifindex: u32, src: Ipv6Addr) {
+        let mut info: libc::in6_pktinfo = unsafe { mem::zeroed() };
+        info.ipi6_ifindex = ifindex as _;
+        info.ipi6_addr.s6_addr = src.octets();
+        self.push_value(libc::IPPROTO_IPV6, libc::IPV6_PKTINFO, &info);
+    }
+
+    /// Returns an iterator over the control messages.
+    pub fn iter(&self) -> ControlMessages<'_> {
+        let buf = unsafe { std::slice::from_raw_parts(self.buf.as_ptr().cast::<u8>(), self.len) };
+        ControlMessages { buf, offset: 0 }
+    }
+}
+
+impl fmt::Debug for CmsgBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a> IntoIterator for &'a CmsgBuffer {
+    type Item = ControlMessage<'a>;
+    type IntoIter = ControlMessages<'a>;
+
+    fn into_iter(self) -> ControlMessages<'a> {
+        self.iter()
+    }
+}
+
+/// A decoded control message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum ControlMessage<'a> {
+    /// The file descriptors received from a unix socket, `SCM_RIGHTS`.
+    ///
+    /// They are owned by the receiver which must close them.
+    Rights(Vec<RawFd>),
+    /// The credentials of the sender of a unix socket, `SCM_CREDENTIALS`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    Credentials(UCred),
+    /// The destination of an IPv4 datagram, `IP_PKTINFO`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    PktInfoV4 {
+        /// The interface the datagram is received on.
+        ifindex: u32,
+        /// The local address of the route.
+        spec_dst: Ipv4Addr,
+        /// The destination address in the header.
+        addr: Ipv4Addr,
+    },
+    /// The destination of an IPv6 datagram, `IPV6_PKTINFO`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    PktInfoV6 {
+        /// The interface the datagram is received on.
+        ifindex: u32,
+        /// The destination address in the header.
+        addr: Ipv6Addr,
+    },
+    /// The receive time of the datagram, `SO_TIMESTAMP` or `SO_TIMESTAMPNS`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    Timestamp(SystemTime),
+    /// Any other control message.
+    Other {
+        /// The protocol level, the `cmsg_level`.
+        level: libc::c_int,
+        /// The message type, the `cmsg_type`.
+        ty: libc::c_int,
+        /// The raw data.
+        data: &'a [u8],
+    },
+}
+
+// read a value from the message data
+fn read<T: Copy>(data: &[u8]) -> Option<T> {
+    if data.len() < mem::size_of::<T>() {
+        return None;
+    }
+    Some(unsafe { ptr::read_unaligned(data.as_ptr().cast::<T>()) })
+}
+
+impl<'a> ControlMessage<'a> {
+    fn decode(level: libc::c_int, ty: libc::c_int, data: &'a [u8]) -> Self {
+        if level == libc::SOL_SOCKET && ty == libc::SCM_RIGHTS {
+            let fds = data
+                .chunks_exact(mem::size_of::<RawFd>())
+                .map(|c| RawFd::from_ne_bytes(c.try_into().unwrap()))
+                .collect();
+            return ControlMessage::Rights(fds);
+        }
+
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        match (level, ty) {
+            (libc::SOL_SOCKET, libc::SCM_CREDENTIALS) => {
+                if let Some(cred) = read::<libc::ucred>(data) {
+                    return ControlMessage::Credentials(UCred {
+                        pid: Some(cred.pid),
+                        uid: cred.uid,
+                        gid: cred.gid,
+                    });
+                }
+            }
+            (libc::IPPROTO_IP, libc::IP_PKTINFO) => {
+                if let Some(info) = read::<libc::in_pktinfo>(data) {
+                    return ControlMessage::PktInfoV4 {
+                        ifindex: info.ipi_ifindex as u32,
+                        spec_dst: Ipv4Addr::from(u32::from_be(info.ipi_spec_dst.s_addr)),
+                        addr: Ipv4Addr::from(u32::from_be(info.ipi_addr.s_addr)),
+                    };
+                }
+            }
+            (libc::IPPROTO_IPV6, libc::IPV6_PKTINFO) => {
+                if let Some(info) = read::<libc::in6_pktinfo>(data) {
+                    return ControlMessage::PktInfoV6 {
+                        ifindex: info.ipi6_ifindex,
+                        addr: Ipv6Addr::from(info.ipi6_addr.s6_addr),
+                    };
+                }
+            }
+            (libc::SOL_SOCKET, libc::SCM_TIMESTAMPNS) => {
+                if let Some(ts) = read::<libc::timespec>(data) {
+                    let dur = Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32);
+                    return ControlMessage::Timestamp(UNIX_EPOCH + dur);
+                }
+            }
+            (libc::SOL_SOCKET, libc::SCM_TIMESTAMP) => {
+                if let Some(tv) = read::<libc::timeval>(data) {
+                    let dur = Duration::new(tv.tv_sec as u64, tv.tv_usec as u32 * 1000);
+                    return ControlMessage::Timestamp(UNIX_EPOCH + dur);
+                }
+            }
+            _ => {}
+        }
+
+        ControlMessage::Other { level, ty, data }
+    }
+}
+
+/// An iterator over the control messages of a [`CmsgBuffer`].
+#[derive(Debug, Clone)]
+pub struct ControlMessages<'a> {
+    buf: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Iterator for ControlMessages<'a> {
+    type Item = ControlMessage<'a>;
+
+    fn next(&mut self) -> Option<ControlMessage<'a>> {
+        let hdr_len = unsafe { libc::CMSG_LEN(0) } as usize;
+        if self.offset + hdr_len > self.buf.len() {
+            return None;
+        }
+        let hdr: libc::cmsghdr = read(&self.buf[self.offset..])?;
+        let len: usize = hdr.cmsg_len as _;
+        if len < hdr_len {
+            return None;
+        }
+        // the last message may be truncated
+        let end = (self.offset + len).min(self.buf.len());
+        let data = &self.buf[self.offset + hdr_len..end];
+        self.offset += CmsgBuffer::space(len - hdr_len);
+        Some(ControlMessage::decode(hdr.cmsg_level, hdr.cmsg_type, data))
+    }
+}
+
+/// send the buffers and the control messages with `sendmsg`
+pub(crate) fn send_msg<T: AsIoData>(
+    s: &T,
+    bufs: &[IoSlice<'_>],
+    cmsg: &CmsgBuffer,
+    addr: Option<&SockAddr>,
+    #[cfg(feature = "io_timeout")] timeout: Option<std::time::Duration>,
+) -> io::Result<usize> {
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    if let Some(addr) = addr {
+        msg.msg_name = addr.as_ptr() as *mut _;
+        msg.msg_namelen = addr.len();
+    }
+    // IoSlice is ABI compatible with iovec
+    msg.msg_iov = bufs.as_ptr() as *mut libc::iovec;
+    msg.msg_iovlen = bufs.len() as _;
+    if !cmsg.is_empty() {
+        msg.msg_control = cmsg.buf.as_ptr() as *mut _;
+        msg.msg_controllen = cmsg.len as _;
+    }
+    let fd = s.as_io_data().fd;
+    let sendmsg = || {
+        let n = unsafe { libc::sendmsg(fd, &msg, SEND_FLAGS) };
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(n as usize)
+    };
+    net_impl::SocketIo::new(
+        s,
+        sendmsg,
+        #[cfg(feature = "io_timeout")]
+        timeout,
+    )
+    .done()
+}
+
+/// receive into the buffers and the control messages with `recvmsg`
+///
+/// returns the received bytes and the length of the source address
+pub(crate) fn recv_msg<T: AsIoData>(
+    s: &T,
+    bufs: &mut [IoSliceMut<'_>],
+    cmsg: &mut CmsgBuffer,
+    addr: Option<&mut SockAddrStorage>,
+    #[cfg(feature = "io_timeout")] timeout: Option<std::time::Duration>,
+) -> io::Result<(usize, libc::socklen_t)> {
+    let mut msg: libc::msghdr = unsafe { mem::zeroed() };
+    if let Some(addr) = addr {
+        msg.msg_namelen = addr.size_of();
+        msg.msg_name = (addr as *mut SockAddrStorage).cast();
+    }
+    // IoSliceMut is ABI compatible with iovec
+    msg.msg_iov = bufs.as_mut_ptr().cast();
+    msg.msg_iovlen = bufs.len() as _;
+    cmsg.clear();
+    if cmsg.capacity() > 0 {
+        msg.msg_control = cmsg.buf.as_mut_ptr().cast();
+        msg.msg_controllen = cmsg.capacity() as _;
+    }
+    let fd = s.as_io_data().fd;
+    // the buffer sizes are overwritten by the kernel
+    let (namelen, controllen) = (msg.msg_namelen, msg.msg_controllen);
+    let recvmsg = || {
+        msg.msg_namelen = namelen;
+        msg.msg_controllen = controllen;
+        let n = unsafe { libc::recvmsg(fd, &mut msg, RECV_FLAGS) };
+        if n < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(n as usize)
+    };
+    let n = net_impl::SocketIo::new(
+        s,
+        recvmsg,
+        #[cfg(feature = "io_timeout")]
+        timeout,
+    )
+    .done()?;
+    if !msg.msg_control.is_null() {
+        cmsg.len = msg.msg_controllen as usize;
+    }
+    cmsg.truncated = msg.msg_flags & libc::MSG_CTRUNC != 0;
+    Ok((n, msg.msg_namelen))
+}
+
+/// set a boolean socket option
+pub(crate) fn set_bool_opt(
+    fd: RawFd,
+    level: libc::c_int,
+    name: libc::c_int,
+    on: bool,
+) -> io::Result<()> {
+    let value = on as libc::c_int;
+    let ret = unsafe {
+        libc::setsockopt(
+            fd,
+            level,
+            name,
+            &value as *const libc::c_int as *const libc::c_void,
+            mem::size_of::<libc::c_int>() as libc::socklen_t,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cmsg_encode_decode() {
+        let mut cmsg = CmsgBuffer::new();
+        cmsg.push_rights(&[3, 4, 5]);
+        cmsg.push(libc::SOL_SOCKET, 1000, b"raw");
+        assert_eq!(cmsg.len(), CmsgBuffer::space(12) + CmsgBuffer::space(3));
+
+        let msgs: Vec<_> = cmsg.iter().collect();
+        assert_eq!(
+            msgs,
+            [
+                ControlMessage::Rights(vec![3, 4, 5]),
+                ControlMessage::Other {
+                    level: libc::SOL_SOCKET,
+                    ty: 1000,
+                    data: b"raw"
+                }
+            ]
+        );
+
+        cmsg.clear();
+        assert!(cmsg.is_empty());
+        assert_eq!(cmsg.iter().count(), 0);
+    }
+}
@@ -0,0 +1,634 @@
+//! Non-blocking host name resolution
+//!
+//! The `getaddrinfo` call blocks the worker thread, so the host names are
+//! resolved by a simple DNS client that runs on the coroutine sockets. It
+//! reads the system config from `/etc/resolv.conf` and `/etc/hosts`. When
+//! the DNS client is not available or failed, the name is resolved by the
+//! `getaddrinfo` in the blocking thread pool.
+
+use std::io;
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::{mem, vec};
+
+use crate::blocking;
+#[cfg(feature = "io_timeout")]
+use crate::coroutine_impl::is_coroutine;
+
+#[cfg(feature = "io_timeout")]
+pub use self::resolver::Resolver;
+
+/// Resolves the `host:port` string to the socket addresses.
+///
+/// This is the coroutine version of `ToSocketAddrs` for `str`, the worker
+/// thread is not blocked during the resolution.
+///
+/// # Examples
+///
+/// ```no_run
+/// let addrs = may::net::lookup_host("localhost:8080").unwrap();
+/// for addr in addrs {
+///     println!("{addr}");
+/// }
+/// ```
+pub fn lookup_host(host: &str) -> io::Result<vec::IntoIter<SocketAddr>> {
+    if let Ok(addr) = host.parse::<SocketAddr>() {
+        return Ok(vec![addr].into_iter());
+    }
+
+    #[cfg(feature = "io_timeout")]
+    if is_coroutine() {
+        if let Some(resolver) = resolver::system() {
+            match resolver.lookup_host(host) {
+                Ok(addrs) => return Ok(addrs),
+                Err(e) => debug!("dns lookup failed, fallback to getaddrinfo, err={e}"),
+            }
+        }
+    }
+
+    blocking::run(|| host.to_socket_addrs())
+}
+
+// the impls that look up the host names return the owned addresses, the
+// already resolved ones return an option or a slice iter that owns nothing
+fn never_blocks<A: ToSocketAddrs + ?Sized>() -> bool {
+    !mem::needs_drop::<A::Iter>()
+}
+
+struct AssertSend<T>(T);
+// SAFETY: the owner coroutine is parked when the value is used
+unsafe impl<T> Send for AssertSend<T> {}
+
+/// resolve the addresses without blocking the worker thread
+///
+/// the addresses are converted in the blocking thread pool if they may
+/// look up the host names, only the resolved addresses are copied back.
+pub(crate) fn resolve<A: ToSocketAddrs + ?Sized>(addr: &A) -> io::Result<Vec<SocketAddr>> {
+    if never_blocks::<A>() {
+        return addr.to_socket_addrs().map(Iterator::collect);
+    }
+    let addr = AssertSend(addr);
+    blocking::run(move || {
+        let addr = addr;
+        addr.0.to_socket_addrs().map(Iterator::collect)
+    })
+}
+
+/// resolve the first address without blocking the worker thread
+pub(crate) fn resolve_first<A: ToSocketAddrs + ?Sized>(addr: &A) -> io::Result<SocketAddr> {
+    let addr = if never_blocks::<A>() {
+        addr.to_socket_addrs()?.next()
+    } else {
+        resolve(addr)?.into_iter().next()
+    };
+    addr.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "could not resolve to any addresses",
+        )
+    })
+}
+
+#[cfg(feature = "io_timeout")]
+mod resolver {
+    use std::collections::hash_map::RandomState;
+    use std::collections::HashMap;
+    use std::hash::BuildHasher;
+    use std::io::{self, Read, Write};
+    use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+    use std::sync::OnceLock;
+    use std::time::Duration;
+    use std::vec;
+
+    use crate::net::{TcpStream, UdpSocket};
+
+    const TYPE_A: u16 = 1;
+    const TYPE_AAAA: u16 = 28;
+    const CLASS_IN: u16 = 1;
+    const RCODE_NXDOMAIN: u8 = 3;
+    // the max udp message size without EDNS
+    const UDP_SIZE: usize = 512;
+    // the fixed size of the message header
+    const HEADER_LEN: usize = 12;
+
+    /// A DNS resolver that runs on the coroutine sockets.
+    ///
+    /// The A and AAAA records are queried over UDP, and the truncated
+    /// responses are queried again over TCP. The IPv4 addresses are
+    /// returned before the IPv6 addresses.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use may::net::Resolver;
+    ///
+    /// let resolver = Resolver::new(vec!["8.8.8.8:53".parse().unwrap()]);
+    /// let ips = resolver.lookup_ip("example.com").unwrap();
+    /// ```
+    #[derive(Debug, Clone)]
+    pub struct Resolver {
+        nameservers: Vec<SocketAddr>,
+        search: Vec<String>,
+        ndots: usize,
+        timeout: Duration,
+        attempts: usize,
+        hosts: HashMap<String, Vec<IpAddr>>,
+    }
+
+    enum Answer {
+        Addrs(Vec<IpAddr>),
+        NotFound,
+        Truncated,
+    }
+
+    fn not_found(name: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("failed to lookup address information for {name}"),
+        )
+    }
+
+    fn invalid_data(msg: &str) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, msg)
+    }
+
+    // the system resolver, none if the config is not available
+    pub(super) fn system() -> Option<&'static Resolver> {
+        static SYSTEM: OnceLock<Option<Resolver>> = OnceLock::new();
+        SYSTEM.get_or_init(|| Resolver::from_system().ok()).as_ref()
+    }
+
+    impl Resolver {
+        /// Creates a resolver that queries the name servers.
+        pub fn new(nameservers: Vec<SocketAddr>) -> Resolver {
+            Resolver {
+                nameservers,
+                search: Vec::new(),
+                ndots: 1,
+                timeout: Duration::from_secs(5),
+                attempts: 2,
+                hosts: HashMap::new(),
+            }
+        }
+
+        /// Creates a resolver from `/etc/resolv.conf` and `/etc/hosts`.
+        #[cfg(unix)]
+        pub fn from_system() -> io::Result<Resolver> {
+            let conf = std::fs::read_to_string("/etc/resolv.conf")?;
+            let mut resolver = Resolver::from_resolv_conf(&conf);
+            if let Ok(hosts) = std::fs::read_to_string("/etc/hosts") {
+                resolver.add_hosts(&hosts);
+            }
+            Ok(resolver)
+        }
+
+        /// Creates a resolver from the system config.
+        #[cfg(not(unix))]
+        pub fn from_system() -> io::Result<Resolver> {
+            Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the system dns config is not supported",
+            ))
+        }
+
+        // parse the resolv.conf content
+        fn from_resolv_conf(conf: &str) -> Resolver {
+            let mut resolver = Resolver::new(Vec::new());
+            let mut domain = None;
+            for line in conf.lines() {
+                let mut words = line.split(['#', ';']).next().unwrap().split_whitespace();
+                match words.next() {
+                    Some("nameserver") => {
+                        // the scoped ipv6 address is not supported
+                        if let Some(Ok(ip)) = words.next().map(str::parse::<IpAddr>) {
+                            resolver.nameservers.push(SocketAddr::new(ip, 53));
+                        }
+                    }
+                    Some("domain") => domain = words.next().map(str::to_owned),
+                    Some("search") => resolver.search = words.map(str::to_owned).collect(),
+                    Some("options") => {
+                        for opt in words {
+                            let (key, value) = match opt.split_once(':') {
+                                Some((k, v)) => (k, v.parse::<usize>().ok()),
+                                None => continue,
+                            };
+                            match (key, value) {
+                                ("ndots", Some(n)) => resolver.ndots = n.min(15),
+                                ("timeout", Some(n)) => {
+                                    resolver.timeout = Duration::from_secs(n.clamp(1, 30) as u64)
+                                }
+                                ("attempts", Some(n)) => resolver.attempts = n.clamp(1, 5),
+                                _ => {}
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            if resolver.search.is_empty() {
+                resolver.search.extend(domain);
+            }
+            if resolver.nameservers.is_empty() {
+                // the default of the libc resolver
+                resolver.nameservers.push(([127, 0, 0, 1], 53).into());
+            }
+            resolver
+        }
+
+        // parse the hosts file content
+        fn add_hosts(&mut self, hosts: &str) {
+            for line in hosts.lines() {
+                let mut words = line.split('#').next().unwrap().split_whitespace();
+                let ip = match words.next().map(str::parse::<IpAddr>) {
+                    Some(Ok(ip)) => ip,
+                    _ => continue,
+                };
+                for name in words {
+                    self.add_host(name, ip);
+                }
+            }
+        }
+
+        /// Adds a static host entry that is used before the DNS query.
+        pub fn add_host(&mut self, name: &str, ip: IpAddr) -> &mut Self {
+            let name = name.trim_end_matches('.').to_ascii_lowercase();
+            let ips = self.hosts.entry(name).or_default();
+            if !ips.contains(&ip) {
+                ips.push(ip);
+            }
+            self
+        }
+
+        /// Sets the timeout of each query, the default is 5 seconds.
+        pub fn set_timeout(&mut self, timeout: Duration) -> &mut Self {
+            self.timeout = timeout;
+            self
+        }
+
+        /// Sets the number of attempts for each name server, the default is 2.
+        pub fn set_attempts(&mut self, attempts: usize) -> &mut Self {
+            self.attempts = attempts.max(1);
+            self
+        }
+
+        /// Resolves the `host:port` string to the socket addresses.
+        pub fn lookup_host(&self, host: &str) -> io::Result<vec::IntoIter<SocketAddr>> {
+            if let Ok(addr) = host.parse::<SocketAddr>() {
+                return Ok(vec![addr].into_iter());
+            }
+            let invalid = || io::Error::new(io::ErrorKind::InvalidInput, "invalid socket address");
+            let (name, port) = host.rsplit_once(':').ok_or_else(invalid)?;
+            let port: u16 = port.parse().map_err(|_| invalid())?;
+            let ips = self.lookup_ip(name)?;
+            let addrs: Vec<_> = ips
+                .into_iter()
+                .map(|ip| SocketAddr::new(ip, port))
+                .collect();
+            Ok(addrs.into_iter())
+        }
+
+        /// Resolves the host name to the ip addresses.
+        pub fn lookup_ip(&self, name: &str) -> io::Result<Vec<IpAddr>> {
+            if let Ok(ip) = name.parse::<IpAddr>() {
+                return Ok(vec![ip]);
+            }
+            let key = name.trim_end_matches('.').to_ascii_lowercase();
+            if let Some(ips) = self.hosts.get(&key) {
+                return Ok(ips.clone());
+            }
+
+            let mut last_err = None;
+            for fqdn in self.candidates(name) {
+                match self.query(&fqdn) {
+                    Ok(ips) if !ips.is_empty() => return Ok(ips),
+                    Ok(_) => {}
+                    Err(e) => last_err = Some(e),
+                }
+            }
+            Err(last_err.unwrap_or_else(|| not_found(name)))
+        }
+
+        // the names to query with the search domains
+        fn candidates(&self, name: &str) -> Vec<String> {
+            if name.ends_with('.') {
+                return vec![name.to_owned()];
+            }
+            let searched = self.search.iter().map(|s| format!("{name}.{s}"));
+            if name.matches('.').count() >= self.ndots {
+                std::iter::once(name.to_owned()).chain(searched).collect()
+            } else {
+                searched.chain(std::iter::once(name.to_owned())).collect()
+            }
+        }
+
+        // query both the A and AAAA records, empty if the name not exist
+        fn query(&self, name: &str) -> io::Result<Vec<IpAddr>> {
+            let mut ips = self.query_type(name, TYPE_A)?;
+            // ignore the AAAA failure if we already have the ipv4
+            match self.query_type(name, TYPE_AAAA) {
+                Ok(v6) => ips.extend(v6),
+                Err(e) if ips.is_empty() => return Err(e),
+                Err(_) => {}
+            }
+            Ok(ips)
+        }
+
+        fn query_type(&self, name: &str, qtype: u16) -> io::Result<Vec<IpAddr>> {
+            let mut last_err = io::Error::other("no name servers");
+            for _ in 0..self.attempts {
+                for ns in &self.nameservers {
+                    let id = query_id();
+                    let query = build_query(id, name, qtype)?;
+                    let ret = self
+                        .exchange_udp(*ns, &query)
+                        .and_then(|rsp| match parse_response(&rsp, id, qtype)? {
+                            Answer::Truncated => {
+                                let rsp = self.exchange_tcp(*ns, &query)?;
+                                parse_response(&rsp, id, qtype)
+                            }
+                            answer => Ok(answer),
+                        });
+                    match ret {
+                        Ok(Answer::Addrs(ips)) => return Ok(ips),
+                        Ok(Answer::NotFound) => return Ok(Vec::new()),
+                        Ok(Answer::Truncated) => last_err = invalid_data("truncated response"),
+                        Err(e) => last_err = e,
+                    }
+                }
+            }
+            Err(last_err)
+        }
+
+        fn exchange_udp(&self, ns: SocketAddr, query: &[u8]) -> io::Result<Vec<u8>> {
+            let local: SocketAddr = match ns {
+                SocketAddr::V4(_) => (Ipv4Addr::UNSPECIFIED, 0).into(),
+                SocketAddr::V6(_) => (Ipv6Addr::UNSPECIFIED, 0).into(),
+            };
+            let socket = UdpSocket::bind(local)?;
+            socket.connect(ns)?;
+            socket.set_read_timeout(Some(self.timeout))?;
+            socket.send(query)?;
+            let mut buf = vec![0; UDP_SIZE];
+            loop {
+                let n = socket.recv(&mut buf)?;
+                // drop the late responses of the previous queries
+                if n >= 2 && buf[..2] == query[..2] {
+                    buf.truncate(n);
+                    return Ok(buf);
+                }
+            }
+        }
+
+        fn exchange_tcp(&self, ns: SocketAddr, query: &[u8]) -> io::Result<Vec<u8>> {
+            let mut stream = TcpStream::connect_timeout(&ns, self.timeout)?;
+            stream.set_read_timeout(Some(self.timeout))?;
+            stream.set_write_timeout(Some(self.timeout))?;
+            let mut msg = Vec::with_capacity(query.len() + 2);
+            msg.extend_from_slice(&(query.len() as u16).to_be_bytes());
+            msg.extend_from_slice(query);
+            stream.write_all(&msg)?;
+            let mut len = [0; 2];
+            stream.read_exact(&mut len)?;
+            let mut buf = vec![0; u16::from_be_bytes(len) as usize];
+            stream.read_exact(&mut buf)?;
+            Ok(buf)
+        }
+    }
+
+    fn query_id() -> u16 {
+        // the random state is seeded per instance
+        RandomState::new().hash_one(std::time::Instant::now()) as u16
+    }
+
+    fn build_query(id: u16, name: &str, qtype: u16) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::with_capacity(UDP_SIZE);
+        buf.extend_from_slice(&id.to_be_bytes());
+        // recursion desired
+        buf.extend_from_slice(&[0x01, 0x00]);
+        // one question, no answer, authority and additional records
+        buf.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+        for label in name.trim_end_matches('.').split('.') {
+            if label.is_empty() || label.len() > 63 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("invalid host name {name}"),
+                ));
+            }
+            buf.push(label.len() as u8);
+            buf.extend_from_slice(label.as_bytes());
+        }
+        buf.push(0);
+        buf.extend_from_slice(&qtype.to_be_bytes());
+        buf.extend_from_slice(&CLASS_IN.to_be_bytes());
+        Ok(buf)
+    }
+
+    // skip the possibly compressed name, return the next position
+    fn skip_name(buf: &[u8], mut pos: usize) -> io::Result<usize> {
+        loop {
+            let len = *buf
+                .get(pos)
+                .ok_or_else(|| invalid_data("invalid dns name"))?;
+            match len {
+                0 => return Ok(pos + 1),
+                l if l & 0xc0 == 0xc0 => return Ok(pos + 2),
+                l => pos += 1 + l as usize,
+            }
+        }
+    }
+
+    fn read_u16(buf: &[u8], pos: usize) -> io::Result<u16> {
+        buf.get(pos..pos + 2)
+            .map(|b| u16::from_be_bytes([b[0], b[1]]))
+            .ok_or_else(|| invalid_data("dns message too short"))
+    }
+
+    fn parse_response(buf: &[u8], id: u16, qtype: u16) -> io::Result<Answer> {
+        if buf.len() < HEADER_LEN {
+            return Err(invalid_data("dns message too short"));
+        }
+        if read_u16(buf, 0)? != id || buf[2] & 0x80 == 0 {
+            return Err(invalid_data("invalid dns response"));
+        }
+        if buf[2] & 0x02 != 0 {
+            return Ok(Answer::Truncated);
+        }
+        match buf[3] & 0x0f {
+            0 => {}
+            RCODE_NXDOMAIN => return Ok(Answer::NotFound),
+            rcode => return Err(io::Error::other(format!("dns server error, rcode={rcode}"))),
+        }
+
+        let qdcount = read_u16(buf, 4)?;
+        let ancount = read_u16(buf, 6)?;
+        let mut pos = HEADER_LEN;
+        for _ in 0..qdcount {
+            pos = skip_name(buf, pos)? + 4;
+        }
+        let mut ips = Vec::new();
+        for _ in 0..ancount {
+            pos = skip_name(buf, pos)?;
+            let rtype = read_u16(buf, pos)?;
+            let class = read_u16(buf, pos + 2)?;
+            let len = read_u16(buf, pos + 8)? as usize;
+            pos += 10;
+            let data = buf
+                .get(pos..pos + len)
+                .ok_or_else(|| invalid_data("dns message too short"))?;
+            pos += len;
+            if rtype != qtype || class != CLASS_IN {
+                // the CNAME records are followed by the server
+                continue;
+            }
+            match (rtype, len) {
+                (TYPE_A, 4) => ips.push(IpAddr::from(<[u8; 4]>::try_from(data).unwrap())),
+                (TYPE_AAAA, 16) => ips.push(IpAddr::from(<[u8; 16]>::try_from(data).unwrap())),
+                _ => return Err(invalid_data("invalid address record")),
+            }
+        }
+        Ok(Answer::Addrs(ips))
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use crate::net::TcpListener;
+
+        // answer the A query of "example.test" and "big.test", the
+        // latter is truncated over UDP
+        fn stub_response(query: &[u8], tcp: bool) -> Vec<u8> {
+            let end = skip_name(query, 12).unwrap();
+            let mut labels = Vec::new();
+            let mut pos = 12;
+            while query[pos] != 0 {
+                let len = query[pos] as usize;
+                labels.push(std::str::from_utf8(&query[pos + 1..pos + 1 + len]).unwrap());
+                pos += 1 + len;
+            }
+            let name = labels.join(".");
+            let qtype = read_u16(query, end).unwrap();
+            let mut rsp = query[..end + 4].to_vec();
+            rsp[2] = 0x81;
+            rsp[3] = 0x80;
+            match (name.as_str(), qtype) {
+                ("example.test", TYPE_A) => {}
+                ("big.test", TYPE_A) if !tcp => {
+                    rsp[2] |= 0x02;
+                    return rsp;
+                }
+                ("big.test", TYPE_A) => {}
+                (_, TYPE_AAAA) => return rsp,
+                _ => {
+                    rsp[3] |= RCODE_NXDOMAIN;
+                    return rsp;
+                }
+            }
+            rsp[7] = 1;
+            // the compressed name points to the question
+            rsp.extend_from_slice(&[0xc0, 12, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 10, 1, 2, 3]);
+            rsp
+        }
+
+        #[test]
+        fn dns_stub_server() {
+            let udp = UdpSocket::bind("127.0.0.1:0").unwrap();
+            let ns = udp.local_addr().unwrap();
+            let tcp = TcpListener::bind(ns).unwrap();
+            go!(move || {
+                let mut buf = [0; 512];
+                loop {
+                    let (n, peer) = udp.recv_from(&mut buf).unwrap();
+                    udp.send_to(&stub_response(&buf[..n], false), peer).unwrap();
+                }
+            });
+            go!(move || {
+                let (mut s, _) = tcp.accept().unwrap();
+                let mut len = [0; 2];
+                s.read_exact(&mut len).unwrap();
+                let mut query = vec![0; u16::from_be_bytes(len) as usize];
+                s.read_exact(&mut query).unwrap();
+                let rsp = stub_response(&query, true);
+                s.write_all(&(rsp.len() as u16).to_be_bytes()).unwrap();
+                s.write_all(&rsp).unwrap();
+            });
+
+            let h = go!(move || {
+                let mut resolver = Resolver::new(vec![ns]);
+                resolver.add_host("static.test", [10, 0, 0, 1].into());
+                let addrs: Vec<_> = resolver.lookup_host("example.test:80").unwrap().collect();
+                assert_eq!(addrs, [SocketAddr::from(([10, 1, 2, 3], 80))]);
+                let ips = resolver.lookup_ip("big.test").unwrap();
+                assert_eq!(ips, [IpAddr::from([10, 1, 2, 3])]);
+                let ips = resolver.lookup_ip("Static.Test.").unwrap();
+                assert_eq!(ips, [IpAddr::from([10, 0, 0, 1])]);
+                let err = resolver.lookup_ip("missing.test").unwrap_err();
+                assert_eq!(err.kind(), io::ErrorKind::NotFound);
+            });
+            h.join().unwrap();
+        }
+
+        #[test]
+        fn dns_short_response() {
+            for len in 0..HEADER_LEN {
+                let mut rsp = vec![0; len];
+                if len >= 2 {
+                    rsp[..2].copy_from_slice(&7u16.to_be_bytes());
+                }
+                let err = parse_response(&rsp, 7, TYPE_A).err().unwrap();
+                assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            }
+        }
+
+        #[test]
+        fn dns_resolv_conf() {
+            let conf = "# comment\nnameserver 10.0.0.1\nnameserver fe80::1%eth0\n\
+                        search a.test b.test\noptions ndots:2 timeout:3 attempts:4\n";
+            let r = Resolver::from_resolv_conf(conf);
+            assert_eq!(r.nameservers, [SocketAddr::from(([10, 0, 0, 1], 53))]);
+            assert_eq!(r.timeout, Duration::from_secs(3));
+            assert_eq!(r.attempts, 4);
+            assert_eq!(r.candidates("x.y"), ["x.y.a.test", "x.y.b.test", "x.y"]);
+            assert_eq!(
+                r.candidates("x.y.z"),
+                ["x.y.z", "x.y.z.a.test", "x.y.z.b.test"]
+            );
+            assert_eq!(r.candidates("x.y."), ["x.y."]);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dns_never_blocks() {
+        assert!(never_blocks::<SocketAddr>());
+        assert!(never_blocks::<&SocketAddr>());
+        assert!(never_blocks::<(std::net::Ipv4Addr, u16)>());
+        assert!(never_blocks::<&[SocketAddr]>());
+        assert!(!never_blocks::<str>());
+        assert!(!never_blocks::<String>());
+        assert!(!never_blocks::<(&str, u16)>());
+    }
+
+    #[test]
+    fn dns_std_bound() {
+        fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<crate::net::UdpSocket> {
+            crate::net::UdpSocket::bind(addr)
+        }
+        let h = go!(|| bind("localhost:0").unwrap().local_addr().unwrap());
+        assert!(h.join().unwrap().ip().is_loopback());
+    }
+
+    #[test]
+    fn dns_resolve_localhost() {
+        let h = go!(|| {
+            let addr = resolve_first(&("localhost", 80)).unwrap();
+            let addrs: Vec<_> = lookup_host("localhost:80").unwrap().collect();
+            (addr, addrs)
+        });
+        let (addr, addrs) = h.join().unwrap();
+        assert!(addr.ip().is_loopback());
+        assert!(addrs.iter().all(|a| a.ip().is_loopback() && a.port() == 80));
+    }
+}
@@ -1,8 +1,29 @@
 //! Networking primitives
 //!
 
+#[cfg(unix)]
+mod cmsg;
+mod dns;
+#[cfg(unix)]
+mod socket;
 mod tcp;
+mod tcp_socket;
 mod udp;
 
+#[cfg(unix)]
+pub(crate) use self::cmsg::{recv_msg, send_msg, set_bool_opt};
+#[cfg(unix)]
+pub use self::cmsg::{CmsgBuffer, ControlMessage, ControlMessages, UCred};
+pub use self::dns::lookup_host;
+#[cfg(feature = "io_timeout")]
+pub use self::dns::Resolver;
+pub(crate) use self::dns::{resolve, resolve_first};
+#[cfg(unix)]
+pub use self::socket::{Domain, Protocol, SockAddr, Socket, Type};
+#[cfg(unix)]
+pub use self::tcp::ShardedTcpListener;
 pub use self::tcp::{TcpListener, TcpStream};
+pub use self::tcp_socket::{TcpKeepalive, TcpSocket};
 pub use self::udp::UdpSocket;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use self::udp::{RecvMsg, SendMsg};
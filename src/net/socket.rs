@@ -0,0 +1,366 @@
+use std::fmt;
+use std::io::{self, IoSlice, IoSliceMut};
+use std::mem::MaybeUninit;
+use std::net::Shutdown;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+#[cfg(feature = "io_timeout")]
+use std::time::Duration;
+
+use crate::io::sys::net as net_impl;
+use crate::io::{self as io_impl, CoIo};
+use crate::net::{recv_msg, send_msg, CmsgBuffer};
+use socket2::SockAddrStorage;
+
+pub use socket2::{Domain, Protocol, SockAddr, Type};
+
+// the kernel never reads the buffer, it's only written
+fn as_uninit(buf: &mut [u8]) -> &mut [MaybeUninit<u8>] {
+    unsafe { &mut *(buf as *mut [u8] as *mut [MaybeUninit<u8>]) }
+}
+
+/// A socket of any type that blocks the coroutine instead of the thread.
+///
+/// It wraps a [`socket2::Socket`] so that the protocols that don't have a
+/// dedicated type, like raw ICMP, packet, netlink or vsock sockets, could be
+/// used in coroutines. The socket options are set by [`Socket::inner`].
+///
+/// # Examples
+///
+/// ```no_run
+/// use may::net::{Domain, Protocol, SockAddr, Socket, Type};
+/// use std::net::SocketAddr;
+///
+/// let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::ICMPV4)).unwrap();
+/// let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+/// let echo = [8, 0, 0xf7, 0xff, 0, 0, 0, 0];
+/// socket.send_to(&echo, &SockAddr::from(addr)).unwrap();
+/// let mut buf = [0; 64];
+/// let (n, _from) = socket.recv_from(&mut buf).unwrap();
+/// println!("reply: {:?}", &buf[..n]);
+/// ```
+pub struct Socket(CoIo<socket2::Socket>);
+
+impl fmt::Debug for Socket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Socket")
+            .field("fd", &self.as_raw_fd())
+            .finish()
+    }
+}
+
+impl Socket {
+    /// Creates a new socket and registers it to the coroutine selector.
+    pub fn new(domain: Domain, ty: Type, protocol: Option<Protocol>) -> io::Result<Socket> {
+        Socket::from_socket(socket2::Socket::new(domain, ty, protocol)?)
+    }
+
+    /// Converts the socket into a coroutine socket, it's set to nonblocking.
+    pub fn from_socket(socket: socket2::Socket) -> io::Result<Socket> {
+        Ok(Socket(CoIo::new(socket)?))
+    }
+
+    /// Returns the underlying socket to get and set the socket options.
+    pub fn inner(&self) -> &socket2::Socket {
+        self.0.inner()
+    }
+
+    /// Returns the underlying socket, it's still in nonblocking mode.
+    pub fn into_inner(self) -> socket2::Socket {
+        self.0.into_inner()
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    pub fn try_clone(&self) -> io::Result<Socket> {
+        Socket::from_socket(self.0.inner().try_clone()?)
+    }
+
+    /// Binds the socket to the address.
+    pub fn bind(&self, addr: &SockAddr) -> io::Result<()> {
+        self.0.inner().bind(addr)
+    }
+
+    /// Marks the socket as ready to accept the connections.
+    pub fn listen(&self, backlog: i32) -> io::Result<()> {
+        self.0.inner().listen(backlog)
+    }
+
+    /// Connects the socket to the address.
+    ///
+    /// The coroutine is blocked until the connection is established.
+    pub fn connect(&self, addr: &SockAddr) -> io::Result<()> {
+        match self.0.inner().connect(addr) {
+            Ok(()) => return Ok(()),
+            Err(e) if e.raw_os_error() == Some(libc::EINPROGRESS) => {}
+            Err(e) => return Err(e),
+        }
+
+        // the result of the pending connection is got by connecting again
+        let connect = || match self.0.inner().connect(addr) {
+            Ok(()) => Ok(()),
+            Err(e) => match e.raw_os_error() {
+                Some(libc::EISCONN) => Ok(()),
+                Some(libc::EALREADY) | Some(libc::EINPROGRESS) => {
+                    Err(io::Error::from_raw_os_error(libc::EAGAIN))
+                }
+                _ => Err(e),
+            },
+        };
+        net_impl::SocketIo::new(
+            &self.0,
+            connect,
+            #[cfg(feature = "io_timeout")]
+            self.0.get_write_timeout(),
+        )
+        .done()
+    }
+
+    /// Accepts a new connection.
+    ///
+    /// The coroutine is blocked until a new connection is established.
+    pub fn accept(&self) -> io::Result<(Socket, SockAddr)> {
+        let (socket, addr) = net_impl::SocketIo::new(
+            &self.0,
+            || self.0.inner().accept(),
+            #[cfg(feature = "io_timeout")]
+            self.0.get_read_timeout(),
+        )
+        .done()?;
+        Ok((Socket::from_socket(socket)?, addr))
+    }
+
+    /// Returns the local address of the socket.
+    pub fn local_addr(&self) -> io::Result<SockAddr> {
+        self.0.inner().local_addr()
+    }
+
+    /// Returns the address of the connected peer.
+    pub fn peer_addr(&self) -> io::Result<SockAddr> {
+        self.0.inner().peer_addr()
+    }
+
+    /// Receives data from the connected peer.
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.recv_with_flags(buf, 0)
+    }
+
+    /// Receives data from the connected peer with the `MSG_*` flags.
+    pub fn recv_with_flags(&self, buf: &mut [u8], flags: libc::c_int) -> io::Result<usize> {
+        let buf = as_uninit(buf);
+        net_impl::SocketIo::new(
+            &self.0,
+            || self.0.inner().recv_with_flags(buf, flags),
+            #[cfg(feature = "io_timeout")]
+            self.0.get_read_timeout(),
+        )
+        .done()
+    }
+
+    /// Receives a message and the source address.
+    pub fn recv_from(&self, buf: &mut [u8]) -> io::Result<(usize, SockAddr)> {
+        let buf = as_uninit(buf);
+        net_impl::SocketIo::new(
+            &self.0,
+            || self.0.inner().recv_from(buf),
+            #[cfg(feature = "io_timeout")]
+            self.0.get_read_timeout(),
+        )
+        .done()
+    }
+
+    /// Receives into the buffers with the control messages, returns the
+    /// number of bytes and the source address.
+    pub fn recv_msg(
+        &self,
+        bufs: &mut [IoSliceMut<'_>],
+        cmsg: &mut CmsgBuffer,
+    ) -> io::Result<(usize, SockAddr)> {
+        let mut storage = SockAddrStorage::zeroed();
+        let (n, len) = recv_msg(
+            &self.0,
+            bufs,
+            cmsg,
+            Some(&mut storage),
+            #[cfg(feature = "io_timeout")]
+            self.0.get_read_timeout(),
+        )?;
+        Ok((n, unsafe { SockAddr::new(storage, len) }))
+    }
+
+    /// Sends data to the connected peer.
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.send_with_flags(buf, 0)
+    }
+
+    /// Sends data to the connected peer with the `MSG_*` flags.
+    pub fn send_with_flags(&self, buf: &[u8], flags: libc::c_int) -> io::Result<usize> {
+        net_impl::SocketIo::new(
+            &self.0,
+            || self.0.inner().send_with_flags(buf, flags),
+            #[cfg(feature = "io_timeout")]
+            self.0.get_write_timeout(),
+        )
+        .done()
+    }
+
+    /// Sends a message to the address.
+    pub fn send_to(&self, buf: &[u8], addr: &SockAddr) -> io::Result<usize> {
+        net_impl::SocketIo::new(
+            &self.0,
+            || self.0.inner().send_to(buf, addr),
+            #[cfg(feature = "io_timeout")]
+            self.0.get_write_timeout(),
+        )
+        .done()
+    }
+
+    /// Sends the buffers with the control messages, to the connected peer
+    /// if `addr` is `None`.
+    pub fn send_msg(
+        &self,
+        bufs: &[IoSlice<'_>],
+        cmsg: &CmsgBuffer,
+        addr: Option<&SockAddr>,
+    ) -> io::Result<usize> {
+        send_msg(
+            &self.0,
+            bufs,
+            cmsg,
+            addr,
+            #[cfg(feature = "io_timeout")]
+            self.0.get_write_timeout(),
+        )
+    }
+
+    /// Sets the read timeout of the coroutine-blocking receive calls.
+    #[cfg(feature = "io_timeout")]
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    /// Sets the write timeout of the coroutine-blocking send calls.
+    #[cfg(feature = "io_timeout")]
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+
+    /// Returns the read timeout.
+    #[cfg(feature = "io_timeout")]
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+
+    /// Returns the write timeout.
+    #[cfg(feature = "io_timeout")]
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.0.inner().take_error()
+    }
+
+    /// Shuts down the read, write, or both halves of the connection.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.0.inner().shutdown(how)
+    }
+}
+
+impl io::Read for Socket {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.recv(buf)
+    }
+}
+
+impl io::Write for Socket {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.send(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsRawFd for Socket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl FromRawFd for Socket {
+    unsafe fn from_raw_fd(fd: RawFd) -> Socket {
+        let socket = FromRawFd::from_raw_fd(fd);
+        Socket(CoIo::new(socket).expect("can't convert to Socket"))
+    }
+}
+
+impl IntoRawFd for Socket {
+    fn into_raw_fd(self) -> RawFd {
+        self.0.into_raw_fd()
+    }
+}
+
+impl io_impl::AsIoData for Socket {
+    fn as_io_data(&self) -> &io_impl::IoData {
+        self.0.as_io_data()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::SocketAddr;
+
+    #[test]
+    fn socket_stream() {
+        let listener = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        listener.bind(&addr.into()).unwrap();
+        listener.listen(16).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let h = go!(move || {
+            let (conn, _) = listener.accept().unwrap();
+            let mut buf = [0; 16];
+            let n = conn.recv(&mut buf).unwrap();
+            conn.send(&buf[..n]).unwrap();
+        });
+
+        let conn = Socket::new(Domain::IPV4, Type::STREAM, None).unwrap();
+        conn.connect(&addr).unwrap();
+        assert_eq!(conn.peer_addr().unwrap().as_socket(), addr.as_socket());
+        conn.send(b"echo").unwrap();
+        let mut buf = [0; 16];
+        let n = conn.recv(&mut buf).unwrap();
+        assert_eq!(&buf[..n], b"echo");
+        h.join().unwrap();
+    }
+
+    #[test]
+    fn socket_datagram() {
+        let receiver = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+        let addr: SocketAddr = "127.0.0.1:0".parse().unwrap();
+        receiver.bind(&addr.into()).unwrap();
+        let addr = receiver.local_addr().unwrap();
+
+        let h = go!(move || {
+            let mut buf = [0; 16];
+            let (n, from) = receiver.recv_from(&mut buf).unwrap();
+            (buf[..n].to_vec(), from.as_socket())
+        });
+
+        let sender = Socket::new(Domain::IPV4, Type::DGRAM, None).unwrap();
+        sender
+            .bind(&"127.0.0.1:0".parse::<SocketAddr>().unwrap().into())
+            .unwrap();
+        let bufs = [IoSlice::new(b"data"), IoSlice::new(b"gram")];
+        sender
+            .send_msg(&bufs, &CmsgBuffer::new(), Some(&addr))
+            .unwrap();
+
+        let (data, from) = h.join().unwrap();
+        assert_eq!(data, b"datagram");
+        assert_eq!(from, sender.local_addr().unwrap().as_socket());
+    }
+}
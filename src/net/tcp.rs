@@ -1,15 +1,18 @@
 use std::io::{self, Read, Write};
 use std::net::{self, Shutdown, SocketAddr, ToSocketAddrs};
+#[cfg(unix)]
+use std::sync::Arc;
 #[cfg(feature = "io_timeout")]
 use std::time::Duration;
 
+#[cfg(unix)]
+use crate::coroutine::{Builder, JoinHandle};
 use crate::io as io_impl;
 use crate::io::net as net_impl;
-use crate::io::split_io::{SplitIo, SplitReader, SplitWriter};
-#[cfg(unix)]
-use crate::io::sys::mod_socket;
+use crate::io::split_io::{self, SharedIo, SplitIo, SplitReader, SplitWriter};
 #[cfg(unix)]
-use crate::io::AsIoData;
+use crate::net::resolve_first;
+use crate::net::{resolve, TcpSocket};
 #[cfg(feature = "io_timeout")]
 use crate::sync::atomic_dur::AtomicDuration;
 use crate::yield_now::yield_with_io;
@@ -56,8 +59,9 @@ impl TcpStream {
     }
 
     pub fn connect<A: ToSocketAddrs>(addr: A) -> io::Result<TcpStream> {
+        let addrs = resolve(&addr)?;
         let mut c = net_impl::TcpStreamConnect::new(
-            addr,
+            &addrs[..],
             #[cfg(feature = "io_timeout")]
             None,
         )?;
@@ -88,6 +92,23 @@ impl TcpStream {
         c.done()
     }
 
+    /// Sends up to `len` bytes of the file starting at `offset` to the
+    /// stream with `sendfile(2)`, the data is not copied to the userspace.
+    ///
+    /// The file position is not changed. Returns the number of bytes sent,
+    /// which may be less than `len` like `write`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn sendfile<F: AsRawFd>(&self, file: &F, offset: u64, len: usize) -> io::Result<usize> {
+        let (socket, file) = (self.as_raw_fd(), file.as_raw_fd());
+        net_impl::SocketIo::new(
+            self,
+            || net_impl::sendfile(socket, file, offset, len),
+            #[cfg(feature = "io_timeout")]
+            self.write_timeout.get(),
+        )
+        .done()
+    }
+
     pub fn peer_addr(&self) -> io::Result<SocketAddr> {
         self.sys.peer_addr()
     }
@@ -150,6 +171,14 @@ impl TcpStream {
         Ok(())
     }
 
+    /// Sets whether the io timeouts of the socket ignore the [`timer slack`].
+    ///
+    /// [`timer slack`]: crate::Config::set_timer_slack
+    #[cfg(all(unix, feature = "io_timeout"))]
+    pub fn set_precise_timer(&self, precise: bool) {
+        self._io.set_precise_timer(precise);
+    }
+
     #[cfg(feature = "io_timeout")]
     pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
         Ok(self.read_timeout.get())
@@ -214,14 +243,14 @@ impl TcpStream {
     }
 }
 
-impl Read for TcpStream {
-    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+impl SharedIo for TcpStream {
+    fn shared_read(&self, buf: &mut [u8]) -> io::Result<usize> {
         #[cfg(unix)]
         {
             self._io.reset();
             // this is an earlier return try for nonblocking read
             // it's useful for server but not necessary for client
-            match self.sys.read(buf) {
+            match (&self.sys).read(buf) {
                 Ok(n) => return Ok(n),
                 Err(e) => {
                     // raw_os_error is faster than kind
@@ -244,15 +273,49 @@ impl Read for TcpStream {
         yield_with_io(&reader, reader.is_coroutine);
         reader.done()
     }
-}
 
-impl Write for TcpStream {
-    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+    #[cfg(unix)]
+    fn shared_read_vectored(&self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        self._io.reset();
+        // this is an earlier return try for nonblocking read
+        match (&self.sys).read_vectored(bufs) {
+            Ok(n) => return Ok(n),
+            Err(e) => {
+                // raw_os_error is faster than kind
+                let raw_err = e.raw_os_error();
+                if raw_err == Some(libc::EAGAIN) || raw_err == Some(libc::EWOULDBLOCK) {
+                    // do nothing here
+                } else {
+                    return Err(e);
+                }
+            }
+        }
+
+        let mut reader = net_impl::SocketReadVectored::new(
+            self,
+            bufs,
+            #[cfg(feature = "io_timeout")]
+            self.read_timeout.get(),
+        );
+        yield_with_io(&reader, reader.is_coroutine);
+        reader.done()
+    }
+
+    #[cfg(windows)]
+    fn shared_read_vectored(&self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        // read into the first non empty buffer
+        match bufs.iter_mut().find(|b| !b.is_empty()) {
+            Some(buf) => self.shared_read(buf),
+            None => Ok(0),
+        }
+    }
+
+    fn shared_write(&self, io: &io_impl::IoData, buf: &[u8]) -> io::Result<usize> {
         #[cfg(unix)]
         {
-            self._io.reset();
+            io.reset();
             // this is an earlier return try for nonblocking write
-            match self.sys.write(buf) {
+            match (&self.sys).write(buf) {
                 Ok(n) => return Ok(n),
                 Err(e) => {
                     // raw_os_error is faster than kind
@@ -266,8 +329,13 @@ impl Write for TcpStream {
             }
         }
 
+        // the writer waits on the given io data
+        #[cfg(unix)]
+        let s = io;
+        #[cfg(windows)]
+        let (s, _) = (self, io);
         let mut writer = net_impl::SocketWrite::new(
-            self,
+            s,
             buf,
             #[cfg(feature = "io_timeout")]
             self.write_timeout.get(),
@@ -277,28 +345,28 @@ impl Write for TcpStream {
     }
 
     #[cfg(unix)]
-    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
-        #[cfg(unix)]
-        {
-            self._io.reset();
-            // this is an earlier return try for nonblocking write
-            match self.sys.write_vectored(bufs) {
-                Ok(n) => return Ok(n),
-                Err(e) => {
-                    // raw_os_error is faster than kind
-                    let raw_err = e.raw_os_error();
-                    if raw_err == Some(libc::EAGAIN) || raw_err == Some(libc::EWOULDBLOCK) {
-                        // do nothing here
-                    } else {
-                        return Err(e);
-                    }
+    fn shared_write_vectored(
+        &self,
+        io: &io_impl::IoData,
+        bufs: &[io::IoSlice<'_>],
+    ) -> io::Result<usize> {
+        io.reset();
+        // this is an earlier return try for nonblocking write
+        match (&self.sys).write_vectored(bufs) {
+            Ok(n) => return Ok(n),
+            Err(e) => {
+                // raw_os_error is faster than kind
+                let raw_err = e.raw_os_error();
+                if raw_err == Some(libc::EAGAIN) || raw_err == Some(libc::EWOULDBLOCK) {
+                    // do nothing here
+                } else {
+                    return Err(e);
                 }
             }
         }
 
         let mut writer = net_impl::SocketWriteVectored::new(
-            self,
-            &self.sys,
+            io,
             bufs,
             #[cfg(feature = "io_timeout")]
             self.write_timeout.get(),
@@ -307,6 +375,51 @@ impl Write for TcpStream {
         writer.done()
     }
 
+    #[cfg(windows)]
+    fn shared_write_vectored(
+        &self,
+        io: &io_impl::IoData,
+        bufs: &[io::IoSlice<'_>],
+    ) -> io::Result<usize> {
+        // write the first non empty buffer
+        match bufs.iter().find(|b| !b.is_empty()) {
+            Some(buf) => self.shared_write(io, buf),
+            None => Ok(0),
+        }
+    }
+
+    #[cfg(feature = "io_timeout")]
+    fn shared_read_timeout(&self) -> Option<Duration> {
+        self.read_timeout.get()
+    }
+
+    #[cfg(feature = "io_timeout")]
+    fn shared_write_timeout(&self) -> Option<Duration> {
+        self.write_timeout.get()
+    }
+}
+
+impl Read for TcpStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.shared_read(buf)
+    }
+
+    #[cfg(unix)]
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        self.shared_read_vectored(bufs)
+    }
+}
+
+impl Write for TcpStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.shared_write(&self._io, buf)
+    }
+
+    #[cfg(unix)]
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        self.shared_write_vectored(&self._io, bufs)
+    }
+
     fn flush(&mut self) -> io::Result<()> {
         // TcpStream just return Ok(()), no need to yield
         self.sys.flush()
@@ -347,16 +460,45 @@ impl io_impl::AsIoData for TcpStream {
 pub struct TcpListener {
     _io: io_impl::IoData,
     sys: net::TcpListener,
+    // the accepted streams are registered to this worker, set for a shard
+    #[cfg(unix)]
+    worker: Option<usize>,
 }
 
 impl TcpListener {
-    fn new(s: net::TcpListener) -> io::Result<TcpListener> {
+    pub(crate) fn new(s: net::TcpListener) -> io::Result<TcpListener> {
         // only set non blocking in coroutine context
         // we would first call nonblocking io in the coroutine
         // to avoid unnecessary context switch
         s.set_nonblocking(true)?;
 
-        io_impl::add_socket(&s).map(|io| TcpListener { _io: io, sys: s })
+        io_impl::add_socket(&s).map(|io| TcpListener {
+            _io: io,
+            sys: s,
+            #[cfg(unix)]
+            worker: None,
+        })
+    }
+
+    // register the listener and the accepted streams to the selector of the worker
+    #[cfg(unix)]
+    pub(crate) fn new_on_worker(s: net::TcpListener, id: usize) -> io::Result<TcpListener> {
+        s.set_nonblocking(true)?;
+        io_impl::add_socket_to(&s, id).map(|io| TcpListener {
+            _io: io,
+            sys: s,
+            worker: Some(id),
+        })
+    }
+
+    // register the accepted nonblocking stream
+    #[cfg(unix)]
+    pub(crate) fn register_stream(&self, s: net::TcpStream) -> io::Result<TcpStream> {
+        let io = match self.worker {
+            Some(id) => io_impl::add_socket_to(&s, id)?,
+            None => io_impl::add_socket(&s)?,
+        };
+        Ok(TcpStream::from_stream(s, io))
     }
 
     #[inline]
@@ -364,29 +506,60 @@ impl TcpListener {
         &self.sys
     }
 
+    /// Creates a new `TcpListener` bound to the address.
+    ///
+    /// The listener is created with `SO_REUSEADDR`, `SO_REUSEPORT` on unix
+    /// and a backlog of 1024, each resolved address is tried in turn. Use
+    /// [`TcpSocket`](crate::net::TcpSocket) to configure it differently.
     pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<TcpListener> {
-        use socket2::{Domain, Socket, Type};
-        let mut addrs = addr.to_socket_addrs()?;
-        let addr = addrs.next().unwrap();
-        let listener = match &addr {
-            SocketAddr::V4(_) => Socket::new(Domain::IPV4, Type::STREAM, None)?,
-            SocketAddr::V6(_) => Socket::new(Domain::IPV6, Type::STREAM, None)?,
-        };
+        let mut last_err = None;
+        for addr in resolve(&addr)? {
+            match Self::bind_addr(addr) {
+                Ok(listener) => return Ok(listener),
+                Err(e) => last_err = Some(e),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "could not resolve to any addresses",
+            )
+        }))
+    }
 
+    fn bind_addr(addr: SocketAddr) -> io::Result<TcpListener> {
+        let socket = TcpSocket::new_for_addr(addr)?;
         // windows not have reuse port but reuse address is not safe
-        listener.set_reuse_address(true)?;
-
+        socket.set_reuseaddr(true)?;
         #[cfg(unix)]
-        listener.set_reuse_port(true)?;
-
-        listener.bind(&addr.into())?;
-        // for addr in addrs {
-        //     listener.bind(&addr.into())?;
-        // }
-        listener.listen(1024)?;
-
-        let s = listener.into();
-        TcpListener::new(s)
+        socket.set_reuseport(true)?;
+        socket.bind(addr)?;
+        socket.listen(1024)
+    }
+
+    /// Creates one `SO_REUSEPORT` listener per worker bound to the address.
+    ///
+    /// Each listener is registered in the selector of its own worker, the
+    /// kernel balances the incoming connections among them. If the port of
+    /// the address is 0 all the listeners share the port picked for the first
+    /// one. See [`ShardedTcpListener::serve`] to run the worker local accept
+    /// loops.
+    #[cfg(unix)]
+    pub fn bind_sharded<A: ToSocketAddrs>(addr: A) -> io::Result<ShardedTcpListener> {
+        let mut addr = resolve_first(&addr)?;
+        let workers = crate::scheduler::get_scheduler().workers;
+
+        let mut shards = Vec::with_capacity(workers);
+        for id in 0..workers {
+            let socket = TcpSocket::new_for_addr(addr)?;
+            socket.set_reuseaddr(true)?;
+            socket.set_reuseport(true)?;
+            socket.bind(addr)?;
+            // the rest bind to the same port
+            addr = socket.local_addr()?;
+            shards.push(socket.listen_on_worker(1024, id)?);
+        }
+        Ok(ShardedTcpListener { shards })
     }
 
     pub fn accept(&self) -> io::Result<(TcpStream, SocketAddr)> {
@@ -394,7 +567,10 @@ impl TcpListener {
         {
             self._io.reset();
             match self.sys.accept() {
-                Ok((s, a)) => return TcpStream::new(s).map(|s| (s, a)),
+                Ok((s, a)) => {
+                    s.set_nonblocking(true)?;
+                    return self.register_stream(s).map(|s| (s, a));
+                }
                 Err(e) => {
                     // raw_os_error is faster than kind
                     let raw_err = e.raw_os_error();
@@ -451,6 +627,68 @@ impl io_impl::AsIoData for TcpListener {
     }
 }
 
+// ===== ShardedTcpListener =====
+//
+//
+
+/// A set of `SO_REUSEPORT` listeners, one for each worker.
+///
+/// Created by [`TcpListener::bind_sharded`], the listener at index `i` is
+/// registered in the selector of worker `i`.
+#[cfg(unix)]
+#[derive(Debug)]
+pub struct ShardedTcpListener {
+    shards: Vec<TcpListener>,
+}
+
+#[cfg(unix)]
+impl ShardedTcpListener {
+    /// Returns the local address that the listeners are bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.shards[0].local_addr()
+    }
+
+    /// Returns the listeners, indexed by the worker id.
+    pub fn shards(&self) -> &[TcpListener] {
+        &self.shards
+    }
+
+    /// Consumes the sharded listener and returns the listeners.
+    pub fn into_shards(self) -> Vec<TcpListener> {
+        self.shards
+    }
+
+    /// Runs an accept loop on each worker.
+    ///
+    /// Every accepted connection is handled by `f` in a new coroutine that
+    /// is spawned on the accepting worker. The accept loop exits with the
+    /// first error except the aborted connections, cancel the returned
+    /// coroutines to stop serving.
+    pub fn serve<F>(self, f: F) -> io::Result<Vec<JoinHandle<io::Result<()>>>>
+    where
+        F: Fn(TcpStream, SocketAddr) + Send + Sync + 'static,
+    {
+        let f = Arc::new(f);
+        let mut handles = Vec::with_capacity(self.shards.len());
+        for (id, listener) in self.shards.into_iter().enumerate() {
+            let f = f.clone();
+            let h = go!(Builder::new().id(id), move || -> io::Result<()> {
+                loop {
+                    let (s, addr) = match listener.accept() {
+                        Ok(conn) => conn,
+                        Err(e) if e.kind() == io::ErrorKind::ConnectionAborted => continue,
+                        Err(e) => return Err(e),
+                    };
+                    let f = f.clone();
+                    go!(Builder::new().id(id), move || f(s, addr))?;
+                }
+            })?;
+            handles.push(h);
+        }
+        Ok(handles)
+    }
+}
+
 // ===== Incoming =====
 //
 //
@@ -522,12 +760,8 @@ impl FromRawFd for TcpListener {
 
 impl SplitIo for TcpStream {
     fn split(self) -> io::Result<(SplitReader<Self>, SplitWriter<Self>)> {
-        let writer = self.try_clone()?;
-        #[cfg(unix)]
-        mod_socket(writer.as_io_data(), false)?;
-        #[cfg(unix)]
-        mod_socket(self.as_io_data(), true)?;
-        Ok((SplitReader::new(self), SplitWriter::new(writer)))
+        let writer_io = self._io.split_writer();
+        Ok(split_io::split(self, writer_io))
     }
 }
 
@@ -584,3 +818,180 @@ impl FromRawSocket for TcpListener {
             .unwrap_or_else(|e| panic!("from_raw_socket for TcpListener, err = {e:?}"))
     }
 }
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::io::AsIoData;
+    use crate::scheduler::get_scheduler;
+    use std::sync::atomic::Ordering;
+
+    #[test]
+    fn tcp_bind_sharded() {
+        let listener = TcpListener::bind_sharded("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let workers = get_scheduler().workers;
+        assert_eq!(listener.shards().len(), workers);
+        for (id, shard) in listener.shards().iter().enumerate() {
+            let worker = shard.as_io_data().worker.load(Ordering::Relaxed);
+            assert_eq!(worker, id);
+            assert_eq!(shard.local_addr().unwrap(), addr);
+        }
+
+        // the accepted streams are registered to the worker of the shard
+        for _ in 0..workers * 2 {
+            let _c = net::TcpStream::connect(addr).unwrap();
+            'accept: loop {
+                for (id, shard) in listener.shards().iter().enumerate() {
+                    match shard.inner().accept() {
+                        Ok((s, _)) => {
+                            s.set_nonblocking(true).unwrap();
+                            let s = shard.register_stream(s).unwrap();
+                            assert_eq!(s.as_io_data().worker.load(Ordering::Relaxed), id);
+                            break 'accept;
+                        }
+                        Err(e) => assert_eq!(e.kind(), io::ErrorKind::WouldBlock),
+                    }
+                }
+            }
+        }
+
+        #[cfg_attr(not(feature = "io_cancel"), allow(unused_variables))]
+        let handles = listener
+            .serve(|mut s, _| {
+                // the connection stays on the accepting worker
+                #[cfg(not(feature = "work_steal"))]
+                assert_eq!(
+                    s.as_io_data().worker.load(Ordering::Relaxed),
+                    crate::scheduler::WORKER_ID.get()
+                );
+                let mut buf = [0; 5];
+                s.read_exact(&mut buf).unwrap();
+                s.write_all(&buf).unwrap();
+            })
+            .unwrap();
+
+        for _ in 0..workers * 4 {
+            let mut s = TcpStream::connect(addr).unwrap();
+            s.write_all(b"shard").unwrap();
+            let mut buf = [0; 5];
+            s.read_exact(&mut buf).unwrap();
+            assert_eq!(&buf, b"shard");
+        }
+
+        // stop the accept loops
+        #[cfg(feature = "io_cancel")]
+        for h in handles {
+            unsafe { h.coroutine().cancel() };
+            assert!(h.join().is_err());
+        }
+    }
+
+    #[test]
+    fn tcp_vectored_split() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let h = go!(move || {
+            let (s, _) = listener.accept().unwrap();
+            let (mut r, mut w) = s.split().unwrap();
+            let mut head = [0; 4];
+            let mut body = [0; 8];
+            let mut n = 0;
+            while n < 12 {
+                let mut bufs = [
+                    io::IoSliceMut::new(&mut head[n.min(4)..]),
+                    io::IoSliceMut::new(&mut body[n.max(4) - 4..]),
+                ];
+                n += r.read_vectored(&mut bufs).unwrap();
+            }
+            let bufs = [io::IoSlice::new(&body), io::IoSlice::new(&head)];
+            let n = w.write_vectored(&bufs).unwrap();
+            assert_eq!(n, 12);
+        });
+
+        let mut s = TcpStream::connect(addr).unwrap();
+        let bufs = [io::IoSlice::new(b"head"), io::IoSlice::new(b"vectored")];
+        assert_eq!(s.write_vectored(&bufs).unwrap(), 12);
+        let mut buf = [0; 12];
+        s.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"vectoredhead");
+        h.join().unwrap();
+    }
+
+    #[test]
+    fn tcp_split_reunite() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let data = "split".repeat(400_000);
+        let len = data.len();
+
+        let h = go!(move || {
+            let (s, _) = listener.accept().unwrap();
+            let fd = s.as_raw_fd();
+            let (mut r, mut w) = s.split().unwrap();
+            // the two parts share the same fd
+            assert_eq!(r.as_raw_fd(), fd);
+            assert_eq!(w.as_raw_fd(), fd);
+
+            // both parts wait at the same time
+            let reader = go!(move || {
+                let mut buf = [0; 4];
+                r.read_exact(&mut buf).unwrap();
+                assert_eq!(&buf, b"ping");
+                r
+            });
+            w.write_all(data.as_bytes()).unwrap();
+            let r = reader.join().unwrap();
+
+            let mut s = r.reunite(w).unwrap();
+            assert_eq!(s.as_raw_fd(), fd);
+            s.write_all(b"done").unwrap();
+
+            // dropping both parts closes the connection
+            let (r, w) = s.split().unwrap();
+            drop(w);
+            drop(r);
+        });
+
+        let mut s = TcpStream::connect(addr).unwrap();
+        let mut buf = vec![0; len];
+        s.read_exact(&mut buf).unwrap();
+        s.write_all(b"ping").unwrap();
+        let mut buf = vec![];
+        s.read_to_end(&mut buf).unwrap();
+        assert_eq!(buf, b"done");
+        h.join().unwrap();
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn tcp_sendfile() {
+        use std::io::Seek;
+
+        let mut file = tempfile::tempfile().unwrap();
+        let data = "sendfile".repeat(100_000);
+        file.write_all(data.as_bytes()).unwrap();
+        file.rewind().unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let h = go!(move || {
+            let (s, _) = listener.accept().unwrap();
+            let len = data.len() - 8;
+            let mut sent = 0;
+            while sent < len {
+                sent += s.sendfile(&file, 8 + sent as u64, len - sent).unwrap();
+            }
+            // the file position is not changed
+            assert_eq!(file.stream_position().unwrap(), 0);
+            sent
+        });
+
+        let mut s = TcpStream::connect(addr).unwrap();
+        let mut buf = String::new();
+        s.read_to_string(&mut buf).unwrap();
+        assert_eq!(buf, "sendfile".repeat(99_999));
+        assert_eq!(h.join().unwrap(), buf.len());
+    }
+}
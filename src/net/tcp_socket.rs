@@ -0,0 +1,355 @@
+use std::fmt;
+use std::io;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::io::net as net_impl;
+use crate::net::{TcpListener, TcpStream};
+use crate::yield_now::yield_with_io;
+use socket2::{Domain, Socket, Type};
+
+pub use socket2::TcpKeepalive;
+
+/// A TCP socket that has not yet been converted to a `TcpStream` or
+/// `TcpListener`.
+///
+/// This is used to configure the socket options before binding or
+/// connecting, nothing is set by default.
+///
+/// # Examples
+///
+/// ```no_run
+/// use may::net::TcpSocket;
+///
+/// let addr = "127.0.0.1:8080".parse().unwrap();
+/// let socket = TcpSocket::new_v4().unwrap();
+/// socket.set_reuseaddr(true).unwrap();
+/// socket.bind(addr).unwrap();
+/// let listener = socket.listen(128).unwrap();
+/// ```
+pub struct TcpSocket {
+    inner: Socket,
+}
+
+impl TcpSocket {
+    /// Creates a new socket configured for IPv4.
+    pub fn new_v4() -> io::Result<TcpSocket> {
+        TcpSocket::new(Domain::IPV4)
+    }
+
+    /// Creates a new socket configured for IPv6.
+    pub fn new_v6() -> io::Result<TcpSocket> {
+        TcpSocket::new(Domain::IPV6)
+    }
+
+    /// Creates a new socket with the same family of the address.
+    pub fn new_for_addr(addr: SocketAddr) -> io::Result<TcpSocket> {
+        TcpSocket::new(Domain::for_address(addr))
+    }
+
+    fn new(domain: Domain) -> io::Result<TcpSocket> {
+        let inner = Socket::new(domain, Type::STREAM, None)?;
+        Ok(TcpSocket { inner })
+    }
+
+    /// Allows the socket to bind to an in-use address, the `SO_REUSEADDR`.
+    pub fn set_reuseaddr(&self, reuseaddr: bool) -> io::Result<()> {
+        self.inner.set_reuse_address(reuseaddr)
+    }
+
+    /// Gets the value of the `SO_REUSEADDR` option.
+    pub fn reuseaddr(&self) -> io::Result<bool> {
+        self.inner.reuse_address()
+    }
+
+    /// Allows the socket to bind to an in-use port, the `SO_REUSEPORT`.
+    #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+    pub fn set_reuseport(&self, reuseport: bool) -> io::Result<()> {
+        self.inner.set_reuse_port(reuseport)
+    }
+
+    /// Gets the value of the `SO_REUSEPORT` option.
+    #[cfg(all(unix, not(any(target_os = "solaris", target_os = "illumos"))))]
+    pub fn reuseport(&self) -> io::Result<bool> {
+        self.inner.reuse_port()
+    }
+
+    /// Restricts the IPv6 socket to IPv6 traffic only, the `IPV6_V6ONLY`.
+    pub fn set_only_v6(&self, only_v6: bool) -> io::Result<()> {
+        self.inner.set_only_v6(only_v6)
+    }
+
+    /// Gets the value of the `IPV6_V6ONLY` option.
+    pub fn only_v6(&self) -> io::Result<bool> {
+        self.inner.only_v6()
+    }
+
+    /// Sets the size of the TCP send buffer, the `SO_SNDBUF`.
+    pub fn set_send_buffer_size(&self, size: u32) -> io::Result<()> {
+        self.inner.set_send_buffer_size(size as usize)
+    }
+
+    /// Returns the size of the TCP send buffer.
+    pub fn send_buffer_size(&self) -> io::Result<u32> {
+        self.inner.send_buffer_size().map(|n| n as u32)
+    }
+
+    /// Sets the size of the TCP receive buffer, the `SO_RCVBUF`.
+    pub fn set_recv_buffer_size(&self, size: u32) -> io::Result<()> {
+        self.inner.set_recv_buffer_size(size as usize)
+    }
+
+    /// Returns the size of the TCP receive buffer.
+    pub fn recv_buffer_size(&self) -> io::Result<u32> {
+        self.inner.recv_buffer_size().map(|n| n as u32)
+    }
+
+    /// Enables or disables the `SO_KEEPALIVE` option.
+    pub fn set_keepalive(&self, keepalive: bool) -> io::Result<()> {
+        self.inner.set_keepalive(keepalive)
+    }
+
+    /// Gets the value of the `SO_KEEPALIVE` option.
+    pub fn keepalive(&self) -> io::Result<bool> {
+        self.inner.keepalive()
+    }
+
+    /// Enables the keepalive with the idle time, interval and retries.
+    pub fn set_tcp_keepalive(&self, params: &TcpKeepalive) -> io::Result<()> {
+        self.inner.set_tcp_keepalive(params)
+    }
+
+    /// Sets the linger duration of the socket, the `SO_LINGER`.
+    pub fn set_linger(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.inner.set_linger(dur)
+    }
+
+    /// Gets the value of the `SO_LINGER` option.
+    pub fn linger(&self) -> io::Result<Option<Duration>> {
+        self.inner.linger()
+    }
+
+    /// Sets the value of the `TCP_NODELAY` option.
+    pub fn set_nodelay(&self, nodelay: bool) -> io::Result<()> {
+        self.inner.set_tcp_nodelay(nodelay)
+    }
+
+    /// Gets the value of the `TCP_NODELAY` option.
+    pub fn nodelay(&self) -> io::Result<bool> {
+        self.inner.tcp_nodelay()
+    }
+
+    /// Enables the TCP fast open on the listener with the max queue length
+    /// of the pending fast open requests, the `TCP_FASTOPEN`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_fastopen(&self, qlen: u32) -> io::Result<()> {
+        self.setsockopt(libc::TCP_FASTOPEN, qlen as libc::c_int)
+    }
+
+    /// Enables the TCP fast open for the outbound connection, the data
+    /// of the first write is sent with the SYN, the `TCP_FASTOPEN_CONNECT`.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_fastopen_connect(&self, enable: bool) -> io::Result<()> {
+        self.setsockopt(libc::TCP_FASTOPEN_CONNECT, enable as libc::c_int)
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    fn setsockopt(&self, opt: libc::c_int, value: libc::c_int) -> io::Result<()> {
+        use std::os::unix::io::AsRawFd;
+
+        let ret = unsafe {
+            libc::setsockopt(
+                self.inner.as_raw_fd(),
+                libc::IPPROTO_TCP,
+                opt,
+                &value as *const libc::c_int as *const libc::c_void,
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Binds the socket to the address, used before `listen` or `connect`.
+    pub fn bind(&self, addr: SocketAddr) -> io::Result<()> {
+        self.inner.bind(&addr.into())
+    }
+
+    /// Returns the local address that this socket is bound to.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner
+            .local_addr()?
+            .as_socket()
+            .ok_or_else(|| io::Error::other("invalid socket address"))
+    }
+
+    /// Converts the socket into a `TcpListener` with the backlog.
+    pub fn listen(self, backlog: u32) -> io::Result<TcpListener> {
+        let backlog = backlog.min(i32::MAX as u32) as i32;
+        self.inner.listen(backlog)?;
+        TcpListener::new(self.inner.into())
+    }
+
+    // listen and register the listener to the selector of the worker
+    #[cfg(unix)]
+    pub(crate) fn listen_on_worker(self, backlog: u32, id: usize) -> io::Result<TcpListener> {
+        let backlog = backlog.min(i32::MAX as u32) as i32;
+        self.inner.listen(backlog)?;
+        TcpListener::new_on_worker(self.inner.into(), id)
+    }
+
+    /// Connects the socket to the address and returns a `TcpStream`.
+    pub fn connect(self, addr: SocketAddr) -> io::Result<TcpStream> {
+        self.connect_impl(
+            addr,
+            #[cfg(feature = "io_timeout")]
+            None,
+        )
+    }
+
+    /// Connects the socket to the address with a timeout.
+    #[cfg(feature = "io_timeout")]
+    pub fn connect_timeout(self, addr: SocketAddr, timeout: Duration) -> io::Result<TcpStream> {
+        self.connect_impl(addr, Some(timeout))
+    }
+
+    fn connect_impl(
+        self,
+        addr: SocketAddr,
+        #[cfg(feature = "io_timeout")] timeout: Option<Duration>,
+    ) -> io::Result<TcpStream> {
+        let mut c = net_impl::TcpStreamConnect::from_socket(
+            self.inner,
+            addr,
+            #[cfg(feature = "io_timeout")]
+            timeout,
+        )?;
+
+        #[cfg(unix)]
+        {
+            if c.check_connected()? {
+                return c.done();
+            }
+        }
+
+        yield_with_io(&c, c.is_coroutine);
+        c.done()
+    }
+}
+
+impl fmt::Debug for TcpSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.inner.fmt(f)
+    }
+}
+
+impl From<Socket> for TcpSocket {
+    fn from(inner: Socket) -> Self {
+        TcpSocket { inner }
+    }
+}
+
+#[cfg(unix)]
+mod unix_impl {
+    use super::TcpSocket;
+    use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+
+    impl AsRawFd for TcpSocket {
+        fn as_raw_fd(&self) -> RawFd {
+            self.inner.as_raw_fd()
+        }
+    }
+
+    impl FromRawFd for TcpSocket {
+        unsafe fn from_raw_fd(fd: RawFd) -> TcpSocket {
+            TcpSocket {
+                inner: FromRawFd::from_raw_fd(fd),
+            }
+        }
+    }
+
+    impl IntoRawFd for TcpSocket {
+        fn into_raw_fd(self) -> RawFd {
+            self.inner.into_raw_fd()
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows_impl {
+    use super::TcpSocket;
+    use std::os::windows::io::{AsRawSocket, FromRawSocket, IntoRawSocket, RawSocket};
+
+    impl AsRawSocket for TcpSocket {
+        fn as_raw_socket(&self) -> RawSocket {
+            self.inner.as_raw_socket()
+        }
+    }
+
+    impl FromRawSocket for TcpSocket {
+        unsafe fn from_raw_socket(s: RawSocket) -> TcpSocket {
+            TcpSocket {
+                inner: FromRawSocket::from_raw_socket(s),
+            }
+        }
+    }
+
+    impl IntoRawSocket for TcpSocket {
+        fn into_raw_socket(self) -> RawSocket {
+            self.inner.into_raw_socket()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{Read, Write};
+
+    #[test]
+    fn tcp_socket_bind_connect() {
+        let socket = TcpSocket::new_v4().unwrap();
+        socket.set_reuseaddr(true).unwrap();
+        socket.set_recv_buffer_size(64 * 1024).unwrap();
+        assert!(socket.reuseaddr().unwrap());
+        socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let listener = socket.listen(16).unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let h = go!(move || {
+            let socket = TcpSocket::new_for_addr(addr).unwrap();
+            socket.set_nodelay(true).unwrap();
+            socket.set_linger(Some(Duration::from_secs(1))).unwrap();
+            // bind before connect
+            socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+            let local = socket.local_addr().unwrap();
+            let mut s = socket.connect(addr).unwrap();
+            assert_eq!(s.local_addr().unwrap(), local);
+            s.write_all(b"socket").unwrap();
+            local
+        });
+
+        let (mut s, peer) = listener.accept().unwrap();
+        let mut buf = [0; 6];
+        s.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"socket");
+        assert_eq!(h.join().unwrap(), peer);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn tcp_socket_no_reuseport() {
+        let socket = TcpSocket::new_v4().unwrap();
+        socket.set_reuseport(false).unwrap();
+        socket.bind("127.0.0.1:0".parse().unwrap()).unwrap();
+        let addr = socket.local_addr().unwrap();
+        let _listener = socket.listen(16).unwrap();
+
+        let socket = TcpSocket::new_v4().unwrap();
+        assert!(!socket.reuseport().unwrap());
+        let err = socket.bind(addr).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AddrInUse);
+    }
+}
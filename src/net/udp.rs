@@ -5,9 +5,16 @@ use std::time::Duration;
 
 use crate::io as io_impl;
 use crate::io::net as net_impl;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use crate::net::set_bool_opt;
+#[cfg(unix)]
+use crate::net::{recv_msg, send_msg, CmsgBuffer};
+use crate::net::{resolve, resolve_first};
 #[cfg(feature = "io_timeout")]
 use crate::sync::atomic_dur::AtomicDuration;
 use crate::yield_now::yield_with_io;
+#[cfg(unix)]
+use socket2::{SockAddr, SockAddrStorage};
 
 #[derive(Debug)]
 pub struct UdpSocket {
@@ -41,13 +48,13 @@ impl UdpSocket {
     }
 
     pub fn bind<A: ToSocketAddrs>(addr: A) -> io::Result<UdpSocket> {
-        net::UdpSocket::bind(addr).and_then(UdpSocket::new)
+        net::UdpSocket::bind(&resolve(&addr)?[..]).and_then(UdpSocket::new)
     }
 
     pub fn connect<A: ToSocketAddrs>(&self, addr: A) -> io::Result<()> {
         // for udp connect it's a nonblocking operation
         // so we just use the system call
-        self.sys.connect(addr)
+        self.sys.connect(&resolve(&addr)?[..])
     }
 
     pub fn local_addr(&self) -> io::Result<SocketAddr> {
@@ -81,11 +88,13 @@ impl UdpSocket {
     }
 
     pub fn send_to<A: ToSocketAddrs>(&self, buf: &[u8], addr: A) -> io::Result<usize> {
+        // only send to the first address like the std
+        let addr = resolve_first(&addr)?;
         #[cfg(unix)]
         {
             self._io.reset();
             // this is an earlier return try for nonblocking read
-            match self.sys.send_to(buf, &addr) {
+            match self.sys.send_to(buf, addr) {
                 Ok(n) => return Ok(n),
                 Err(e) => {
                     // raw_os_error is faster than kind
@@ -186,6 +195,144 @@ impl UdpSocket {
         reader.done()
     }
 
+    /// Receives a single datagram from the connected peer into the buffers.
+    #[cfg(unix)]
+    pub fn recv_vectored(&self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        self._io.reset();
+        // this is an earlier return try for nonblocking read
+        match net_impl::readv(self.sys.as_raw_fd(), bufs) {
+            Ok(n) => return Ok(n),
+            Err(e) => {
+                // raw_os_error is faster than kind
+                let raw_err = e.raw_os_error();
+                if raw_err == Some(libc::EAGAIN) || raw_err == Some(libc::EWOULDBLOCK) {
+                    // do nothing here
+                } else {
+                    return Err(e);
+                }
+            }
+        }
+
+        let mut reader = net_impl::SocketReadVectored::new(
+            self,
+            bufs,
+            #[cfg(feature = "io_timeout")]
+            self.read_timeout.get(),
+        );
+        yield_with_io(&reader, reader.is_coroutine);
+        reader.done()
+    }
+
+    /// Sends the buffers as a single datagram to the connected peer.
+    #[cfg(unix)]
+    pub fn send_vectored(&self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        self._io.reset();
+        // this is an earlier return try for nonblocking write
+        match net_impl::writev(self.sys.as_raw_fd(), bufs) {
+            Ok(n) => return Ok(n),
+            Err(e) => {
+                // raw_os_error is faster than kind
+                let raw_err = e.raw_os_error();
+                if raw_err == Some(libc::EAGAIN) || raw_err == Some(libc::EWOULDBLOCK) {
+                    // do nothing here
+                } else {
+                    return Err(e);
+                }
+            }
+        }
+
+        let mut writer = net_impl::SocketWriteVectored::new(
+            self,
+            bufs,
+            #[cfg(feature = "io_timeout")]
+            self.write_timeout.get(),
+        );
+        yield_with_io(&writer, writer.is_coroutine);
+        writer.done()
+    }
+
+    /// Receives a datagram into the buffers with the control messages.
+    ///
+    /// The control messages, like the `IP_PKTINFO` and the timestamp, are
+    /// enabled by the socket options, see [`UdpSocket::set_recv_pktinfo`].
+    #[cfg(unix)]
+    pub fn recv_msg(
+        &self,
+        bufs: &mut [io::IoSliceMut<'_>],
+        cmsg: &mut CmsgBuffer,
+    ) -> io::Result<(usize, SocketAddr)> {
+        let mut storage = SockAddrStorage::zeroed();
+        let (n, len) = recv_msg(
+            self,
+            bufs,
+            cmsg,
+            Some(&mut storage),
+            #[cfg(feature = "io_timeout")]
+            self.read_timeout.get(),
+        )?;
+        let addr = unsafe { SockAddr::new(storage, len) };
+        let addr = addr
+            .as_socket()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "invalid source address"))?;
+        Ok((n, addr))
+    }
+
+    /// Sends the buffers as a single datagram with the control messages.
+    ///
+    /// The datagram is sent to the connected peer if `addr` is `None`.
+    #[cfg(unix)]
+    pub fn send_msg(
+        &self,
+        bufs: &[io::IoSlice<'_>],
+        cmsg: &CmsgBuffer,
+        addr: Option<SocketAddr>,
+    ) -> io::Result<usize> {
+        let addr = addr.map(SockAddr::from);
+        send_msg(
+            self,
+            bufs,
+            cmsg,
+            addr.as_ref(),
+            #[cfg(feature = "io_timeout")]
+            self.write_timeout.get(),
+        )
+    }
+
+    /// Receives the destination address and the interface of the datagrams,
+    /// the `IP_PKTINFO` or `IPV6_RECVPKTINFO` option.
+    ///
+    /// They are reported by [`UdpSocket::recv_msg`] as
+    /// [`ControlMessage::PktInfoV4`] or [`ControlMessage::PktInfoV6`].
+    ///
+    /// [`ControlMessage::PktInfoV4`]: crate::net::ControlMessage::PktInfoV4
+    /// [`ControlMessage::PktInfoV6`]: crate::net::ControlMessage::PktInfoV6
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_recv_pktinfo(&self, on: bool) -> io::Result<()> {
+        let fd = self.sys.as_raw_fd();
+        if self.local_addr()?.is_ipv4() {
+            set_bool_opt(fd, libc::IPPROTO_IP, libc::IP_PKTINFO, on)
+        } else {
+            set_bool_opt(fd, libc::IPPROTO_IPV6, libc::IPV6_RECVPKTINFO, on)
+        }
+    }
+
+    /// Receives the kernel timestamp of the datagrams, the `SO_TIMESTAMPNS`
+    /// option.
+    ///
+    /// It's reported by [`UdpSocket::recv_msg`] as
+    /// [`ControlMessage::Timestamp`].
+    ///
+    /// [`ControlMessage::Timestamp`]: crate::net::ControlMessage::Timestamp
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_recv_timestamp(&self, on: bool) -> io::Result<()> {
+        set_bool_opt(
+            self.sys.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_TIMESTAMPNS,
+            on,
+        )
+    }
+
     #[cfg(feature = "io_timeout")]
     pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
         self.sys.set_read_timeout(dur)?;
@@ -200,6 +347,14 @@ impl UdpSocket {
         Ok(())
     }
 
+    /// Sets whether the io timeouts of the socket ignore the [`timer slack`].
+    ///
+    /// [`timer slack`]: crate::Config::set_timer_slack
+    #[cfg(all(unix, feature = "io_timeout"))]
+    pub fn set_precise_timer(&self, precise: bool) {
+        self._io.set_precise_timer(precise);
+    }
+
     #[cfg(feature = "io_timeout")]
     pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
         Ok(self.read_timeout.get())
@@ -266,11 +421,159 @@ impl UdpSocket {
         self.sys.leave_multicast_v6(multiaddr, interface)
     }
 
+    /// Receives many datagrams with one `recvmmsg` call.
+    ///
+    /// Returns the number of the messages that are filled, at least one.
+    /// The coroutine is blocked until there is any datagram. Returns an
+    /// `InvalidInput` error if `msgs` is empty.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn recv_many(&self, msgs: &mut [RecvMsg<'_>]) -> io::Result<usize> {
+        if msgs.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "no messages"));
+        }
+        net_impl::recv_many(
+            self,
+            msgs,
+            #[cfg(feature = "io_timeout")]
+            self.read_timeout.get(),
+        )
+    }
+
+    /// Sends many datagrams with one `sendmmsg` call.
+    ///
+    /// Returns the number of the messages that are sent, at least one.
+    /// The coroutine is blocked until the socket is writable. Returns an
+    /// `InvalidInput` error if `msgs` is empty.
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn send_many(&self, msgs: &[SendMsg<'_>]) -> io::Result<usize> {
+        if msgs.is_empty() {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "no messages"));
+        }
+        net_impl::send_many(
+            self,
+            msgs,
+            #[cfg(feature = "io_timeout")]
+            self.write_timeout.get(),
+        )
+    }
+
+    /// Enables the UDP generic receive offload, the `UDP_GRO` option.
+    ///
+    /// The datagrams of the same flow could be coalesced into one message,
+    /// see [`RecvMsg::segment_size`].
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_gro(&self, on: bool) -> io::Result<()> {
+        let value = on as libc::c_int;
+        let ret = unsafe {
+            libc::setsockopt(
+                self.sys.as_raw_fd(),
+                libc::SOL_UDP,
+                libc::UDP_GRO,
+                &value as *const libc::c_int as *const libc::c_void,
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if ret != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
     pub fn take_error(&self) -> io::Result<Option<io::Error>> {
         self.sys.take_error()
     }
 }
 
+/// A datagram buffer for [`UdpSocket::recv_many`].
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Debug)]
+pub struct RecvMsg<'a> {
+    pub(crate) buf: &'a mut [u8],
+    pub(crate) len: usize,
+    pub(crate) addr: Option<SocketAddr>,
+    pub(crate) segment_size: Option<usize>,
+    pub(crate) truncated: bool,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<'a> RecvMsg<'a> {
+    /// Creates a message that receives into the buffer.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        RecvMsg {
+            buf,
+            len: 0,
+            addr: None,
+            segment_size: None,
+            truncated: false,
+        }
+    }
+
+    /// Returns the length of the received data.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if no data is received.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the received data.
+    pub fn data(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+
+    /// Returns the source address of the datagram.
+    pub fn addr(&self) -> Option<SocketAddr> {
+        self.addr
+    }
+
+    /// Returns the size of each datagram if they are coalesced by GRO, the
+    /// data is split by it and the last one may be shorter.
+    pub fn segment_size(&self) -> Option<usize> {
+        self.segment_size
+    }
+
+    /// Returns true if the datagram is larger than the buffer.
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+/// A datagram for [`UdpSocket::send_many`].
+#[cfg(any(target_os = "linux", target_os = "android"))]
+#[derive(Debug, Clone, Copy)]
+pub struct SendMsg<'a> {
+    pub(crate) buf: &'a [u8],
+    pub(crate) addr: Option<SocketAddr>,
+    pub(crate) segment_size: Option<u16>,
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+impl<'a> SendMsg<'a> {
+    /// Creates a message that is sent to the connected peer.
+    pub fn new(buf: &'a [u8]) -> Self {
+        SendMsg {
+            buf,
+            addr: None,
+            segment_size: None,
+        }
+    }
+
+    /// Sets the destination address of the message.
+    pub fn addr(mut self, addr: SocketAddr) -> Self {
+        self.addr = Some(addr);
+        self
+    }
+
+    /// Splits the buffer into datagrams of the size by the kernel, the
+    /// UDP generic segmentation offload.
+    pub fn segment_size(mut self, size: u16) -> Self {
+        self.segment_size = Some(size);
+        self
+    }
+}
+
 #[cfg(unix)]
 impl io_impl::AsIoData for UdpSocket {
     fn as_io_data(&self) -> &io_impl::IoData {
@@ -338,3 +641,110 @@ impl FromRawSocket for UdpSocket {
             .unwrap_or_else(|e| panic!("from_raw_socket for UdpSocket, err = {e:?}"))
     }
 }
+
+#[cfg(all(test, any(target_os = "linux", target_os = "android")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn udp_send_recv_many() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let addr = receiver.local_addr().unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let from = sender.local_addr().unwrap();
+
+        let h = go!(move || {
+            let mut bufs = [[0u8; 16]; 4];
+            let mut got = vec![];
+            while got.len() < 3 {
+                let mut msgs: Vec<_> = bufs.iter_mut().map(|b| RecvMsg::new(b)).collect();
+                let n = receiver.recv_many(&mut msgs).unwrap();
+                for msg in &msgs[..n] {
+                    assert_eq!(msg.addr(), Some(from));
+                    assert!(!msg.is_truncated());
+                    got.push(msg.data().to_vec());
+                }
+            }
+            got
+        });
+
+        let msgs = [
+            SendMsg::new(b"one").addr(addr),
+            SendMsg::new(b"two").addr(addr),
+            SendMsg::new(b"three").addr(addr),
+        ];
+        let mut sent = 0;
+        while sent < msgs.len() {
+            sent += sender.send_many(&msgs[sent..]).unwrap();
+        }
+        let got = h.join().unwrap();
+        assert_eq!(got, [&b"one"[..], b"two", b"three"]);
+    }
+
+    #[test]
+    fn udp_many_empty() {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let err = socket.recv_many(&mut []).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = socket.send_many(&[]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn udp_send_gso() {
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+        sender.connect(receiver.local_addr().unwrap()).unwrap();
+
+        // the kernel splits it into 3 datagrams
+        let data = [7u8; 2500];
+        let msg = SendMsg::new(&data).segment_size(1000);
+        assert_eq!(sender.send_many(&[msg]).unwrap(), 1);
+
+        let mut sizes = vec![];
+        let mut buf = [0u8; 2000];
+        while sizes.len() < 3 {
+            sizes.push(receiver.recv(&mut buf).unwrap());
+        }
+        assert_eq!(sizes, [1000, 1000, 500]);
+    }
+
+    #[test]
+    fn udp_recv_msg_pktinfo() {
+        use crate::net::{CmsgBuffer, ControlMessage};
+
+        let receiver = UdpSocket::bind("127.0.0.1:0").unwrap();
+        receiver.set_recv_pktinfo(true).unwrap();
+        receiver.set_recv_timestamp(true).unwrap();
+        let sender = UdpSocket::bind("127.0.0.1:0").unwrap();
+
+        let mut cmsg = CmsgBuffer::new();
+        cmsg.push_pktinfo_v4(0, Ipv4Addr::LOCALHOST);
+        let bufs = [io::IoSlice::new(b"pkt"), io::IoSlice::new(b"info")];
+        let addr = receiver.local_addr().unwrap();
+        assert_eq!(sender.send_msg(&bufs, &cmsg, Some(addr)).unwrap(), 7);
+
+        let mut buf = [0u8; 16];
+        let mut cmsg = CmsgBuffer::with_capacity(128);
+        let (n, from) = receiver
+            .recv_msg(&mut [io::IoSliceMut::new(&mut buf)], &mut cmsg)
+            .unwrap();
+        assert_eq!(&buf[..n], b"pktinfo");
+        assert_eq!(from, sender.local_addr().unwrap());
+        assert!(!cmsg.is_truncated());
+
+        let mut pktinfo = false;
+        let mut timestamp = false;
+        for msg in &cmsg {
+            match msg {
+                ControlMessage::PktInfoV4 { addr, .. } => {
+                    assert_eq!(addr, Ipv4Addr::LOCALHOST);
+                    pktinfo = true;
+                }
+                ControlMessage::Timestamp(_) => timestamp = true,
+                msg => panic!("unexpected {msg:?}"),
+            }
+        }
+        assert!(pktinfo && timestamp);
+    }
+}
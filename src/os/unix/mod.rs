@@ -1,3 +1,8 @@
 #![cfg(unix)]
 
 pub mod net;
+mod pipe;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod seqpacket;
+
+pub use self::pipe::{pipe, PipeReader, PipeWriter};
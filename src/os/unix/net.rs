@@ -2,20 +2,36 @@
 
 use std::fmt;
 use std::io;
+use std::mem;
 use std::net::Shutdown;
+use std::os::fd::OwnedFd;
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use std::os::unix::ffi::OsStrExt;
 use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
-use std::os::unix::net::{self, SocketAddr};
+use std::os::unix::net;
 use std::path::Path;
 #[cfg(feature = "io_timeout")]
 use std::time::Duration;
 
 use crate::coroutine_impl::is_coroutine;
-use crate::io::split_io::{SplitIo, SplitReader, SplitWriter};
-use crate::io::sys::mod_socket;
+use crate::io::split_io::{self, SharedIo, SplitIo, SplitReader, SplitWriter};
 use crate::io::sys::net as net_impl;
 use crate::io::CoIo;
 use crate::io::{self as io_impl, AsIoData};
+#[cfg(any(target_os = "linux", target_os = "android"))]
+use crate::net::set_bool_opt;
+use crate::net::{recv_msg, send_msg, CmsgBuffer, ControlMessage};
 use crate::yield_now::yield_with_io;
+use socket2::{SockAddr, Type};
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+pub use super::seqpacket::{UnixSeqpacket, UnixSeqpacketListener};
+pub use crate::net::UCred;
+#[cfg(target_os = "android")]
+pub use std::os::android::net::SocketAddrExt;
+#[cfg(target_os = "linux")]
+pub use std::os::linux::net::SocketAddrExt;
+pub use std::os::unix::net::SocketAddr;
 
 /// A Unix stream socket.
 ///
@@ -84,6 +100,33 @@ impl UnixStream {
         c.done()
     }
 
+    /// Connects to the socket specified by the address, like an abstract
+    /// address on Linux.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use may::os::unix::net::{SocketAddr, SocketAddrExt, UnixStream};
+    ///
+    /// let addr = SocketAddr::from_abstract_name(b"may.sock").unwrap();
+    /// let socket = UnixStream::connect_addr(&addr).expect("Couldn't connect");
+    /// ```
+    pub fn connect_addr(addr: &SocketAddr) -> io::Result<UnixStream> {
+        if !is_coroutine() {
+            let stream = net::UnixStream::connect_addr(addr)?;
+            return Ok(UnixStream(CoIo::new(stream)?));
+        }
+
+        let mut c = net_impl::UnixStreamConnect::with_addr(to_sock_addr(addr)?, Type::STREAM)?;
+
+        if c.check_connected()? {
+            return c.done();
+        }
+
+        yield_with_io(&c, c.is_coroutine);
+        c.done()
+    }
+
     /// Creates an unnamed pair of connected sockets.
     ///
     /// Returns two `UnixStream`s which are connected to each other.
@@ -239,6 +282,126 @@ impl UnixStream {
         self.0.peek(buf)
     }
 
+    /// Sends the data with the file descriptors, the `SCM_RIGHTS` message.
+    ///
+    /// The descriptors are duplicated into the receiving process, they are
+    /// still owned by the caller.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use may::os::unix::net::UnixStream;
+    /// use std::os::unix::io::AsRawFd;
+    ///
+    /// let file = std::fs::File::open("/etc/hosts").unwrap();
+    /// let socket = UnixStream::connect("/tmp/sock").unwrap();
+    /// socket.send_with_fds(b"file", &[file.as_raw_fd()]).unwrap();
+    /// ```
+    pub fn send_with_fds(&self, buf: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+        let mut cmsg = CmsgBuffer::new();
+        if !fds.is_empty() {
+            cmsg.push_rights(fds);
+        }
+        self.send_msg(&[io::IoSlice::new(buf)], &cmsg)
+    }
+
+    /// Receives the data with the file descriptors that are sent by
+    /// [`send_with_fds`], the descriptors are appended to `fds`.
+    ///
+    /// The received descriptors are closed on exec.
+    ///
+    /// [`send_with_fds`]: #method.send_with_fds
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use may::os::unix::net::UnixStream;
+    ///
+    /// let socket = UnixStream::connect("/tmp/sock").unwrap();
+    /// let mut buf = [0; 16];
+    /// let mut fds = Vec::new();
+    /// let n = socket.recv_with_fds(&mut buf, &mut fds).unwrap();
+    /// println!("received {} bytes and {} fds", n, fds.len());
+    /// ```
+    pub fn recv_with_fds(&self, buf: &mut [u8], fds: &mut Vec<OwnedFd>) -> io::Result<usize> {
+        let space = CmsgBuffer::space(SCM_MAX_FD * mem::size_of::<RawFd>());
+        let mut cmsg = CmsgBuffer::with_capacity(space);
+        let n = self.recv_msg(&mut [io::IoSliceMut::new(buf)], &mut cmsg)?;
+        take_fds(&cmsg, fds);
+        Ok(n)
+    }
+
+    /// Sends the buffers with the control messages.
+    pub fn send_msg(&self, bufs: &[io::IoSlice<'_>], cmsg: &CmsgBuffer) -> io::Result<usize> {
+        send_msg(
+            &self.0,
+            bufs,
+            cmsg,
+            None,
+            #[cfg(feature = "io_timeout")]
+            self.0.get_write_timeout(),
+        )
+    }
+
+    /// Receives into the buffers with the control messages.
+    ///
+    /// The received file descriptors in the [`ControlMessage::Rights`] are
+    /// owned by the caller.
+    ///
+    /// [`ControlMessage::Rights`]: crate::net::ControlMessage::Rights
+    pub fn recv_msg(
+        &self,
+        bufs: &mut [io::IoSliceMut<'_>],
+        cmsg: &mut CmsgBuffer,
+    ) -> io::Result<usize> {
+        let (n, _) = recv_msg(
+            &self.0,
+            bufs,
+            cmsg,
+            None,
+            #[cfg(feature = "io_timeout")]
+            self.0.get_read_timeout(),
+        )?;
+        Ok(n)
+    }
+
+    /// Receives the credentials of the sender with each message, the
+    /// `SO_PASSCRED` option.
+    ///
+    /// They are reported by [`recv_msg`] as [`ControlMessage::Credentials`].
+    ///
+    /// [`recv_msg`]: #method.recv_msg
+    /// [`ControlMessage::Credentials`]: crate::net::ControlMessage::Credentials
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_passcred(&self, on: bool) -> io::Result<()> {
+        set_bool_opt(self.as_raw_fd(), libc::SOL_SOCKET, libc::SO_PASSCRED, on)
+    }
+
+    /// Returns the credentials of the process that created the peer socket.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use may::os::unix::net::UnixStream;
+    ///
+    /// let socket = UnixStream::connect("/tmp/sock").unwrap();
+    /// let cred = socket.peer_cred().unwrap();
+    /// println!("peer uid: {}", cred.uid);
+    /// ```
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "android",
+        target_os = "macos",
+        target_os = "ios",
+        target_os = "freebsd",
+        target_os = "openbsd",
+        target_os = "netbsd",
+        target_os = "dragonfly"
+    ))]
+    pub fn peer_cred(&self) -> io::Result<UCred> {
+        peer_cred(self.as_raw_fd())
+    }
+
     /// Returns the value of the `SO_ERROR` option.
     ///
     /// # Examples
@@ -289,6 +452,10 @@ impl io::Read for UnixStream {
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
         self.0.read(buf)
     }
+
+    fn read_vectored(&mut self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        self.0.read_vectored(bufs)
+    }
 }
 
 // impl<'a> io::Read for &'a UnixStream {
@@ -302,6 +469,10 @@ impl io::Write for UnixStream {
         self.0.write(buf)
     }
 
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        self.0.write_vectored(bufs)
+    }
+
     fn flush(&mut self) -> io::Result<()> {
         self.0.flush()
     }
@@ -342,12 +513,42 @@ impl io_impl::AsIoData for UnixStream {
     }
 }
 
+impl SharedIo for UnixStream {
+    fn shared_read(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.shared_read(buf)
+    }
+
+    fn shared_read_vectored(&self, bufs: &mut [io::IoSliceMut<'_>]) -> io::Result<usize> {
+        self.0.shared_read_vectored(bufs)
+    }
+
+    fn shared_write(&self, io: &io_impl::IoData, buf: &[u8]) -> io::Result<usize> {
+        self.0.shared_write(io, buf)
+    }
+
+    fn shared_write_vectored(
+        &self,
+        io: &io_impl::IoData,
+        bufs: &[io::IoSlice<'_>],
+    ) -> io::Result<usize> {
+        self.0.shared_write_vectored(io, bufs)
+    }
+
+    #[cfg(feature = "io_timeout")]
+    fn shared_read_timeout(&self) -> Option<Duration> {
+        self.0.shared_read_timeout()
+    }
+
+    #[cfg(feature = "io_timeout")]
+    fn shared_write_timeout(&self) -> Option<Duration> {
+        self.0.shared_write_timeout()
+    }
+}
+
 impl SplitIo for UnixStream {
     fn split(self) -> io::Result<(SplitReader<Self>, SplitWriter<Self>)> {
-        let writer = self.try_clone()?;
-        mod_socket(writer.as_io_data(), false)?;
-        mod_socket(self.as_io_data(), true)?;
-        Ok((SplitReader::new(self), SplitWriter::new(writer)))
+        let writer_io = self.as_io_data().split_writer();
+        Ok(split_io::split(self, writer_io))
     }
 }
 
@@ -413,6 +614,22 @@ impl UnixListener {
         Ok(UnixListener(CoIo::new(listener)?))
     }
 
+    /// Creates a new `UnixListener` bound to the specified address, like an
+    /// abstract address on Linux that leaves no socket file behind.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use may::os::unix::net::{SocketAddr, SocketAddrExt, UnixListener};
+    ///
+    /// let addr = SocketAddr::from_abstract_name(b"may.sock").unwrap();
+    /// let listener = UnixListener::bind_addr(&addr).expect("Couldn't bind");
+    /// ```
+    pub fn bind_addr(addr: &SocketAddr) -> io::Result<UnixListener> {
+        let listener = net::UnixListener::bind_addr(addr)?;
+        Ok(UnixListener(CoIo::new(listener)?))
+    }
+
     /// Accepts a new incoming connection to this listener.
     ///
     /// This function will block the calling thread until a new Unix connection
@@ -658,6 +875,21 @@ impl UnixDatagram {
         Ok(UnixDatagram(CoIo::new(datagram)?))
     }
 
+    /// Creates a Unix datagram socket bound to the specified address.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use may::os::unix::net::{SocketAddr, SocketAddrExt, UnixDatagram};
+    ///
+    /// let addr = SocketAddr::from_abstract_name(b"may.sock").unwrap();
+    /// let sock = UnixDatagram::bind_addr(&addr).expect("Couldn't bind");
+    /// ```
+    pub fn bind_addr(addr: &SocketAddr) -> io::Result<UnixDatagram> {
+        let datagram = net::UnixDatagram::bind_addr(addr)?;
+        Ok(UnixDatagram(CoIo::new(datagram)?))
+    }
+
     /// Creates a Unix Datagram socket which is not bound to any address.
     ///
     /// # Examples
@@ -731,6 +963,11 @@ impl UnixDatagram {
         self.0.inner().connect(path)
     }
 
+    /// Connects the socket to the specified address.
+    pub fn connect_addr(&self, addr: &SocketAddr) -> io::Result<()> {
+        self.0.inner().connect_addr(addr)
+    }
+
     /// Creates a new independently owned handle to the underlying socket.
     ///
     /// The returned `UnixDatagram` is a reference to the same socket that this
@@ -898,7 +1135,32 @@ impl UnixDatagram {
             }
         }
 
-        let mut writer = net_impl::UnixSendTo::new(self, buf, path.as_ref())?;
+        let addr = SockAddr::unix(path)?;
+        let mut writer = net_impl::UnixSendTo::new(self, buf, addr);
+        yield_with_io(&writer, writer.is_coroutine);
+        writer.done()
+    }
+
+    /// Sends data on the socket to the specified address.
+    ///
+    /// On success, returns the number of bytes written.
+    pub fn send_to_addr(&self, buf: &[u8], addr: &SocketAddr) -> io::Result<usize> {
+        self.0.io_reset();
+        // this is an earlier return try for nonblocking write
+        match self.0.inner().send_to_addr(buf, addr) {
+            Ok(n) => return Ok(n),
+            Err(e) => {
+                // raw_os_error is faster than kind
+                let raw_err = e.raw_os_error();
+                if raw_err == Some(libc::EAGAIN) || raw_err == Some(libc::EWOULDBLOCK) {
+                    // do nothing here
+                } else {
+                    return Err(e);
+                }
+            }
+        }
+
+        let mut writer = net_impl::UnixSendTo::new(self, buf, to_sock_addr(addr)?);
         yield_with_io(&writer, writer.is_coroutine);
         writer.done()
     }
@@ -1029,6 +1291,101 @@ impl UnixDatagram {
         self.0.write_timeout()
     }
 
+    /// Sends the data with the file descriptors, the `SCM_RIGHTS` message.
+    ///
+    /// The descriptors are duplicated into the receiving process, they are
+    /// still owned by the caller.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use may::os::unix::net::UnixDatagram;
+    /// use std::os::unix::io::AsRawFd;
+    ///
+    /// let file = std::fs::File::open("/etc/hosts").unwrap();
+    /// let socket = UnixDatagram::bind("/tmp/sock").unwrap();
+    /// socket.send_with_fds(b"file", &[file.as_raw_fd()]).unwrap();
+    /// ```
+    pub fn send_with_fds(&self, buf: &[u8], fds: &[RawFd]) -> io::Result<usize> {
+        let mut cmsg = CmsgBuffer::new();
+        if !fds.is_empty() {
+            cmsg.push_rights(fds);
+        }
+        self.send_msg(&[io::IoSlice::new(buf)], &cmsg)
+    }
+
+    /// Receives the data with the file descriptors that are sent by
+    /// [`send_with_fds`], the descriptors are appended to `fds`.
+    ///
+    /// The received descriptors are closed on exec.
+    ///
+    /// [`send_with_fds`]: #method.send_with_fds
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use may::os::unix::net::UnixDatagram;
+    ///
+    /// let socket = UnixDatagram::bind("/tmp/sock").unwrap();
+    /// let mut buf = [0; 16];
+    /// let mut fds = Vec::new();
+    /// let n = socket.recv_with_fds(&mut buf, &mut fds).unwrap();
+    /// println!("received {} bytes and {} fds", n, fds.len());
+    /// ```
+    pub fn recv_with_fds(&self, buf: &mut [u8], fds: &mut Vec<OwnedFd>) -> io::Result<usize> {
+        let space = CmsgBuffer::space(SCM_MAX_FD * mem::size_of::<RawFd>());
+        let mut cmsg = CmsgBuffer::with_capacity(space);
+        let n = self.recv_msg(&mut [io::IoSliceMut::new(buf)], &mut cmsg)?;
+        take_fds(&cmsg, fds);
+        Ok(n)
+    }
+
+    /// Sends the buffers with the control messages.
+    pub fn send_msg(&self, bufs: &[io::IoSlice<'_>], cmsg: &CmsgBuffer) -> io::Result<usize> {
+        send_msg(
+            &self.0,
+            bufs,
+            cmsg,
+            None,
+            #[cfg(feature = "io_timeout")]
+            self.0.get_write_timeout(),
+        )
+    }
+
+    /// Receives into the buffers with the control messages.
+    ///
+    /// The received file descriptors in the [`ControlMessage::Rights`] are
+    /// owned by the caller.
+    ///
+    /// [`ControlMessage::Rights`]: crate::net::ControlMessage::Rights
+    pub fn recv_msg(
+        &self,
+        bufs: &mut [io::IoSliceMut<'_>],
+        cmsg: &mut CmsgBuffer,
+    ) -> io::Result<usize> {
+        let (n, _) = recv_msg(
+            &self.0,
+            bufs,
+            cmsg,
+            None,
+            #[cfg(feature = "io_timeout")]
+            self.0.get_read_timeout(),
+        )?;
+        Ok(n)
+    }
+
+    /// Receives the credentials of the sender with each message, the
+    /// `SO_PASSCRED` option.
+    ///
+    /// They are reported by [`recv_msg`] as [`ControlMessage::Credentials`].
+    ///
+    /// [`recv_msg`]: #method.recv_msg
+    /// [`ControlMessage::Credentials`]: crate::net::ControlMessage::Credentials
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    pub fn set_passcred(&self, on: bool) -> io::Result<()> {
+        set_bool_opt(self.as_raw_fd(), libc::SOL_SOCKET, libc::SO_PASSCRED, on)
+    }
+
     /// Returns the value of the `SO_ERROR` option.
     ///
     /// # Examples
@@ -1088,6 +1445,87 @@ impl io_impl::AsIoData for UnixDatagram {
     }
 }
 
+// convert the unix socket address to the socket2 address
+pub(crate) fn to_sock_addr(addr: &SocketAddr) -> io::Result<SockAddr> {
+    if let Some(path) = addr.as_pathname() {
+        return SockAddr::unix(path);
+    }
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    if let Some(name) = addr.as_abstract_name() {
+        // the abstract name starts with a nul byte
+        let mut path = vec![0];
+        path.extend_from_slice(name);
+        return SockAddr::unix(std::ffi::OsStr::from_bytes(&path));
+    }
+    Err(io::Error::new(
+        io::ErrorKind::InvalidInput,
+        "can't connect to an unnamed address",
+    ))
+}
+
+// the max number of file descriptors in one `SCM_RIGHTS` message
+const SCM_MAX_FD: usize = 253;
+
+// take the ownership of the received file descriptors
+fn take_fds(cmsg: &CmsgBuffer, fds: &mut Vec<OwnedFd>) {
+    for msg in cmsg.iter() {
+        if let ControlMessage::Rights(rights) = msg {
+            for fd in rights {
+                // MSG_CMSG_CLOEXEC is not supported
+                #[cfg(not(any(target_os = "linux", target_os = "android")))]
+                unsafe {
+                    libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC)
+                };
+                fds.push(unsafe { OwnedFd::from_raw_fd(fd) });
+            }
+        }
+    }
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+fn peer_cred(fd: RawFd) -> io::Result<UCred> {
+    let mut cred: libc::ucred = unsafe { mem::zeroed() };
+    let mut len = mem::size_of::<libc::ucred>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            fd,
+            libc::SOL_SOCKET,
+            libc::SO_PEERCRED,
+            &mut cred as *mut libc::ucred as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if ret != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(UCred {
+        pid: Some(cred.pid),
+        uid: cred.uid,
+        gid: cred.gid,
+    })
+}
+
+#[cfg(any(
+    target_os = "macos",
+    target_os = "ios",
+    target_os = "freebsd",
+    target_os = "openbsd",
+    target_os = "netbsd",
+    target_os = "dragonfly"
+))]
+fn peer_cred(fd: RawFd) -> io::Result<UCred> {
+    let mut uid = 0;
+    let mut gid = 0;
+    if unsafe { libc::getpeereid(fd, &mut uid, &mut gid) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(UCred {
+        pid: None,
+        uid,
+        gid,
+    })
+}
+
 #[cfg(all(test, not(target_os = "emscripten")))]
 mod test {
     use std::io::prelude::*;
@@ -1439,4 +1877,108 @@ mod test {
         // let n = s1.peek(&mut buf).unwrap();
         // assert_eq!(n, 0);
     }
+
+    #[test]
+    fn send_recv_fds() {
+        let dir = tmpdir();
+        let path = dir.path().join("file");
+        or_panic!(std::fs::write(&path, b"passed"));
+        let file = or_panic!(std::fs::File::open(&path));
+
+        let (s1, s2) = or_panic!(UnixStream::pair());
+        let thread = go!(move || {
+            let mut buf = [0; 4];
+            let mut fds = Vec::new();
+            let n = or_panic!(s2.recv_with_fds(&mut buf, &mut fds));
+            assert_eq!(&buf[..n], b"file");
+            assert_eq!(fds.len(), 1);
+            let mut content = String::new();
+            or_panic!(std::fs::File::from(fds.pop().unwrap()).read_to_string(&mut content));
+            content
+        });
+
+        assert_eq!(or_panic!(s1.send_with_fds(b"file", &[file.as_raw_fd()])), 4);
+        assert_eq!(thread.join().unwrap(), "passed");
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn peer_cred() {
+        let (s1, _s2) = or_panic!(UnixStream::pair());
+        let cred = or_panic!(s1.peer_cred());
+        assert_eq!(cred.pid, Some(unsafe { libc::getpid() }));
+        assert_eq!(cred.uid, unsafe { libc::getuid() });
+        assert_eq!(cred.gid, unsafe { libc::getgid() });
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn datagram_passcred() {
+        let (s1, s2) = or_panic!(UnixDatagram::pair());
+        or_panic!(s2.set_passcred(true));
+        or_panic!(s1.send(b"cred"));
+
+        let mut buf = [0; 4];
+        let mut cmsg = CmsgBuffer::with_capacity(64);
+        let n = or_panic!(s2.recv_msg(&mut [io::IoSliceMut::new(&mut buf)], &mut cmsg));
+        assert_eq!(&buf[..n], b"cred");
+        let cred = cmsg.iter().find_map(|msg| match msg {
+            ControlMessage::Credentials(cred) => Some(cred),
+            _ => None,
+        });
+        assert_eq!(cred.unwrap().pid, Some(unsafe { libc::getpid() }));
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    #[test]
+    fn abstract_address() {
+        let name = format!("may-abstract-{}", std::process::id());
+        let addr = or_panic!(SocketAddr::from_abstract_name(name.as_bytes()));
+        let listener = or_panic!(UnixListener::bind_addr(&addr));
+        let local = or_panic!(listener.local_addr());
+        assert_eq!(local.as_abstract_name(), Some(name.as_bytes()));
+
+        let thread = go!(move || {
+            let mut stream = or_panic!(listener.accept()).0;
+            or_panic!(stream.write_all(b"abstract"));
+        });
+
+        let mut stream = or_panic!(UnixStream::connect_addr(&addr));
+        let mut buf = String::new();
+        or_panic!(stream.read_to_string(&mut buf));
+        assert_eq!(buf, "abstract");
+        thread.join().unwrap();
+
+        let dgram_addr = or_panic!(SocketAddr::from_abstract_name(format!("{name}-dgram")));
+        let receiver = or_panic!(UnixDatagram::bind_addr(&dgram_addr));
+        let sender = or_panic!(UnixDatagram::unbound());
+        or_panic!(sender.send_to_addr(b"dgram", &dgram_addr));
+        let mut buf = [0; 8];
+        let n = or_panic!(receiver.recv(&mut buf));
+        assert_eq!(&buf[..n], b"dgram");
+    }
+
+    #[test]
+    fn split_reunite() {
+        let (a, b) = or_panic!(UnixStream::pair());
+        let (ar, mut aw) = or_panic!(a.split());
+        let (mut br, bw) = or_panic!(b.split());
+
+        or_panic!(aw.write_all(b"split"));
+        let mut buf = [0; 5];
+        or_panic!(br.read_exact(&mut buf));
+        assert_eq!(&buf, b"split");
+
+        // the parts are not from the same stream
+        let err = ar.reunite(bw).unwrap_err();
+        let (ar, bw) = (err.0, err.1);
+        let mut a = or_panic!(ar.reunite(aw));
+        let b = or_panic!(br.reunite(bw));
+
+        // dropping the stream closes the connection
+        drop(b);
+        let mut buf = vec![];
+        or_panic!(a.read_to_end(&mut buf));
+        assert!(buf.is_empty());
+    }
 }
@@ -0,0 +1,202 @@
+//! Unix anonymous pipe
+
+use std::fmt;
+use std::fs::File;
+use std::io::{self, IoSlice, IoSliceMut, Read, Write};
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, IntoRawFd, RawFd};
+#[cfg(feature = "io_timeout")]
+use std::time::Duration;
+
+use crate::io::{AsIoData, CoIo, IoData};
+
+/// The reading end of an anonymous pipe, created by [`pipe`].
+pub struct PipeReader(CoIo<File>);
+
+/// The writing end of an anonymous pipe, created by [`pipe`].
+pub struct PipeWriter(CoIo<File>);
+
+/// Creates an anonymous pipe, both ends are registered to the selector.
+///
+/// # Examples
+///
+/// ```no_run
+/// use may::os::unix::pipe;
+/// use std::io::{Read, Write};
+///
+/// let (mut reader, mut writer) = pipe().unwrap();
+/// writer.write_all(b"hello").unwrap();
+/// drop(writer);
+/// let mut s = String::new();
+/// reader.read_to_string(&mut s).unwrap();
+/// ```
+pub fn pipe() -> io::Result<(PipeReader, PipeWriter)> {
+    let mut fds = [0; 2];
+    #[cfg(any(target_os = "linux", target_os = "android", target_os = "freebsd"))]
+    {
+        if unsafe { libc::pipe2(fds.as_mut_ptr(), libc::O_CLOEXEC) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    #[cfg(not(any(target_os = "linux", target_os = "android", target_os = "freebsd")))]
+    {
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        for fd in fds {
+            if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } != 0 {
+                let err = io::Error::last_os_error();
+                unsafe { libc::close(fds[0]) };
+                unsafe { libc::close(fds[1]) };
+                return Err(err);
+            }
+        }
+    }
+
+    let (reader, writer) = unsafe { (File::from_raw_fd(fds[0]), File::from_raw_fd(fds[1])) };
+    Ok((
+        PipeReader(CoIo::new(reader)?),
+        PipeWriter(CoIo::new(writer)?),
+    ))
+}
+
+impl PipeReader {
+    /// Creates a new independently owned handle to the reading end.
+    pub fn try_clone(&self) -> io::Result<PipeReader> {
+        let file = self.0.inner().try_clone()?;
+        Ok(PipeReader(CoIo::new(file)?))
+    }
+
+    /// Sets the read timeout.
+    #[cfg(feature = "io_timeout")]
+    pub fn set_read_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(dur)
+    }
+
+    /// Returns the read timeout.
+    #[cfg(feature = "io_timeout")]
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+}
+
+impl PipeWriter {
+    /// Creates a new independently owned handle to the writing end.
+    pub fn try_clone(&self) -> io::Result<PipeWriter> {
+        let file = self.0.inner().try_clone()?;
+        Ok(PipeWriter(CoIo::new(file)?))
+    }
+
+    /// Sets the write timeout.
+    #[cfg(feature = "io_timeout")]
+    pub fn set_write_timeout(&self, dur: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(dur)
+    }
+
+    /// Returns the write timeout.
+    #[cfg(feature = "io_timeout")]
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+}
+
+impl Read for PipeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+
+    fn read_vectored(&mut self, bufs: &mut [IoSliceMut<'_>]) -> io::Result<usize> {
+        self.0.read_vectored(bufs)
+    }
+}
+
+impl Write for PipeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn write_vectored(&mut self, bufs: &[IoSlice<'_>]) -> io::Result<usize> {
+        self.0.write_vectored(bufs)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+macro_rules! pipe_fd {
+    ($name:ident) => {
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_struct(stringify!($name))
+                    .field("fd", &self.as_raw_fd())
+                    .finish()
+            }
+        }
+
+        impl AsRawFd for $name {
+            fn as_raw_fd(&self) -> RawFd {
+                self.0.as_raw_fd()
+            }
+        }
+
+        impl AsFd for $name {
+            fn as_fd(&self) -> BorrowedFd<'_> {
+                self.0.inner().as_fd()
+            }
+        }
+
+        impl AsIoData for $name {
+            fn as_io_data(&self) -> &IoData {
+                self.0.as_io_data()
+            }
+        }
+
+        impl IntoRawFd for $name {
+            fn into_raw_fd(self) -> RawFd {
+                self.0.into_raw_fd()
+            }
+        }
+    };
+}
+
+pipe_fd!(PipeReader);
+pipe_fd!(PipeWriter);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pipe_read_write() {
+        let (mut reader, mut writer) = pipe().unwrap();
+        let h = go!(move || {
+            let mut s = String::new();
+            reader.read_to_string(&mut s).unwrap();
+            s
+        });
+        // larger than the pipe buffer
+        let data = "may".repeat(100_000);
+        writer.write_all(data.as_bytes()).unwrap();
+        drop(writer);
+        assert_eq!(h.join().unwrap(), data);
+    }
+
+    #[test]
+    fn pipe_vectored() {
+        let (mut reader, mut writer) = pipe().unwrap();
+        let h = go!(move || {
+            let mut a = [0; 3];
+            let mut b = [0; 3];
+            let n = reader
+                .read_vectored(&mut [IoSliceMut::new(&mut a), IoSliceMut::new(&mut b)])
+                .unwrap();
+            assert_eq!(n, 6);
+            (a, b)
+        });
+        let n = writer
+            .write_vectored(&[IoSlice::new(b"abc"), IoSlice::new(b"def")])
+            .unwrap();
+        assert_eq!(n, 6);
+        assert_eq!(h.join().unwrap(), (*b"abc", *b"def"));
+    }
+}
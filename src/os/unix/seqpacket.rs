@@ -0,0 +1,399 @@
+//! Unix sequenced-packet sockets
+
+use std::fmt;
+use std::io::{self, Read, Write};
+use std::mem::ManuallyDrop;
+use std::net::Shutdown;
+use std::os::unix::io::{AsRawFd, FromRawFd, IntoRawFd, RawFd};
+use std::os::unix::net;
+use std::path::Path;
+#[cfg(feature = "io_timeout")]
+use std::time::Duration;
+
+use super::net::{to_sock_addr, SocketAddr};
+use crate::coroutine_impl::is_coroutine;
+use crate::io::sys::net as net_impl;
+use crate::io::{self as io_impl, CoIo};
+use crate::yield_now::yield_with_io;
+use socket2::{Domain, SockAddr, Socket, Type};
+
+// the unix socket address can only be got from the std socket
+fn std_socket(fd: RawFd) -> ManuallyDrop<net::UnixDatagram> {
+    ManuallyDrop::new(unsafe { net::UnixDatagram::from_raw_fd(fd) })
+}
+
+/// A Unix sequenced-packet socket server, listening for connections.
+///
+/// The connections are [`UnixSeqpacket`]s that keep the message boundaries.
+///
+/// # Examples
+///
+/// ```no_run
+/// use may::os::unix::net::UnixSeqpacketListener;
+///
+/// let listener = UnixSeqpacketListener::bind("/path/to/the/socket").unwrap();
+/// let (conn, _addr) = listener.accept().unwrap();
+/// let mut buf = [0; 1024];
+/// let n = conn.recv(&mut buf).unwrap();
+/// conn.send(&buf[..n]).unwrap();
+/// ```
+pub struct UnixSeqpacketListener(CoIo<Socket>);
+
+impl fmt::Debug for UnixSeqpacketListener {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let mut builder = fmt.debug_struct("UnixSeqpacketListener");
+        builder.field("fd", &self.as_raw_fd());
+        if let Ok(addr) = self.local_addr() {
+            builder.field("local", &addr);
+        }
+        builder.finish()
+    }
+}
+
+impl UnixSeqpacketListener {
+    /// Creates a new `UnixSeqpacketListener` bound to the specified socket.
+    pub fn bind<P: AsRef<Path>>(path: P) -> io::Result<UnixSeqpacketListener> {
+        Self::bind_sock_addr(&SockAddr::unix(path)?)
+    }
+
+    /// Creates a new `UnixSeqpacketListener` bound to the specified address,
+    /// like an abstract address.
+    pub fn bind_addr(addr: &SocketAddr) -> io::Result<UnixSeqpacketListener> {
+        Self::bind_sock_addr(&to_sock_addr(addr)?)
+    }
+
+    fn bind_sock_addr(addr: &SockAddr) -> io::Result<UnixSeqpacketListener> {
+        let socket = Socket::new(Domain::UNIX, Type::SEQPACKET, None)?;
+        socket.bind(addr)?;
+        socket.listen(128)?;
+        Ok(UnixSeqpacketListener(CoIo::new(socket)?))
+    }
+
+    /// Accepts a new incoming connection to this listener.
+    ///
+    /// The coroutine is blocked until a new connection is established.
+    pub fn accept(&self) -> io::Result<(UnixSeqpacket, SocketAddr)> {
+        let (socket, _) = net_impl::SocketIo::new(
+            &self.0,
+            || self.0.inner().accept(),
+            #[cfg(feature = "io_timeout")]
+            self.0.get_read_timeout(),
+        )
+        .done()?;
+        let addr = std_socket(socket.as_raw_fd()).peer_addr()?;
+        Ok((UnixSeqpacket(CoIo::new(socket)?), addr))
+    }
+
+    /// Returns the local socket address of this listener.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        std_socket(self.as_raw_fd()).local_addr()
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    pub fn try_clone(&self) -> io::Result<UnixSeqpacketListener> {
+        let socket = self.0.inner().try_clone()?;
+        Ok(UnixSeqpacketListener(CoIo::new(socket)?))
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.0.inner().take_error()
+    }
+}
+
+impl AsRawFd for UnixSeqpacketListener {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl FromRawFd for UnixSeqpacketListener {
+    unsafe fn from_raw_fd(fd: RawFd) -> UnixSeqpacketListener {
+        let socket = FromRawFd::from_raw_fd(fd);
+        UnixSeqpacketListener(CoIo::new(socket).expect("can't convert to UnixSeqpacketListener"))
+    }
+}
+
+impl IntoRawFd for UnixSeqpacketListener {
+    fn into_raw_fd(self) -> RawFd {
+        self.0.into_raw_fd()
+    }
+}
+
+impl io_impl::AsIoData for UnixSeqpacketListener {
+    fn as_io_data(&self) -> &io_impl::IoData {
+        self.0.as_io_data()
+    }
+}
+
+/// A Unix sequenced-packet socket.
+///
+/// It's connection oriented like [`UnixStream`] while each message is
+/// delivered as a whole like [`UnixDatagram`].
+///
+/// [`UnixStream`]: super::net::UnixStream
+/// [`UnixDatagram`]: super::net::UnixDatagram
+///
+/// # Examples
+///
+/// ```no_run
+/// use may::os::unix::net::UnixSeqpacket;
+///
+/// let conn = UnixSeqpacket::connect("/path/to/the/socket").unwrap();
+/// conn.send(b"hello").unwrap();
+/// let mut buf = [0; 1024];
+/// let n = conn.recv(&mut buf).unwrap();
+/// println!("{:?}", &buf[..n]);
+/// ```
+pub struct UnixSeqpacket(CoIo<Socket>);
+
+impl fmt::Debug for UnixSeqpacket {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let mut builder = fmt.debug_struct("UnixSeqpacket");
+        builder.field("fd", &self.as_raw_fd());
+        if let Ok(addr) = self.local_addr() {
+            builder.field("local", &addr);
+        }
+        if let Ok(addr) = self.peer_addr() {
+            builder.field("peer", &addr);
+        }
+        builder.finish()
+    }
+}
+
+impl UnixSeqpacket {
+    /// Connects to the socket named by `path`.
+    pub fn connect<P: AsRef<Path>>(path: P) -> io::Result<UnixSeqpacket> {
+        Self::connect_sock_addr(SockAddr::unix(path)?)
+    }
+
+    /// Connects to the socket specified by the address, like an abstract
+    /// address.
+    pub fn connect_addr(addr: &SocketAddr) -> io::Result<UnixSeqpacket> {
+        Self::connect_sock_addr(to_sock_addr(addr)?)
+    }
+
+    fn connect_sock_addr(addr: SockAddr) -> io::Result<UnixSeqpacket> {
+        if !is_coroutine() {
+            let socket = Socket::new(Domain::UNIX, Type::SEQPACKET, None)?;
+            socket.connect(&addr)?;
+            return Ok(UnixSeqpacket(CoIo::new(socket)?));
+        }
+
+        let mut c = net_impl::UnixStreamConnect::with_addr(addr, Type::SEQPACKET)?;
+
+        if c.check_connected()? {
+            return c.done_io().map(UnixSeqpacket);
+        }
+
+        yield_with_io(&c, c.is_coroutine);
+        c.done_io().map(UnixSeqpacket)
+    }
+
+    /// Creates an unnamed pair of connected sockets.
+    pub fn pair() -> io::Result<(UnixSeqpacket, UnixSeqpacket)> {
+        let (s1, s2) = Socket::pair(Domain::UNIX, Type::SEQPACKET, None)?;
+        Ok((UnixSeqpacket(CoIo::new(s1)?), UnixSeqpacket(CoIo::new(s2)?)))
+    }
+
+    /// Creates a new independently owned handle to the underlying socket.
+    pub fn try_clone(&self) -> io::Result<UnixSeqpacket> {
+        let socket = self.0.inner().try_clone()?;
+        Ok(UnixSeqpacket(CoIo::new(socket)?))
+    }
+
+    /// Returns the socket address of the local half of this connection.
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        std_socket(self.as_raw_fd()).local_addr()
+    }
+
+    /// Returns the socket address of the remote half of this connection.
+    pub fn peer_addr(&self) -> io::Result<SocketAddr> {
+        std_socket(self.as_raw_fd()).peer_addr()
+    }
+
+    /// Receives one message from the socket.
+    ///
+    /// On success, returns the number of bytes read, the rest of a message
+    /// that is larger than the buffer is discarded. Returns 0 when the peer
+    /// is closed.
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.io_reset();
+        // this is an earlier return try for nonblocking read
+        let mut socket = self.0.inner();
+        match socket.read(buf) {
+            Ok(n) => return Ok(n),
+            Err(e) => {
+                // raw_os_error is faster than kind
+                let raw_err = e.raw_os_error();
+                if raw_err == Some(libc::EAGAIN) || raw_err == Some(libc::EWOULDBLOCK) {
+                    // do nothing here
+                } else {
+                    return Err(e);
+                }
+            }
+        }
+
+        let mut reader = net_impl::SocketRead::new(
+            &self.0,
+            buf,
+            #[cfg(feature = "io_timeout")]
+            self.0.get_read_timeout(),
+        );
+        yield_with_io(&reader, reader.is_coroutine);
+        reader.done()
+    }
+
+    /// Sends the buffer as one message to the socket.
+    ///
+    /// On success, returns the number of bytes written.
+    pub fn send(&self, buf: &[u8]) -> io::Result<usize> {
+        self.0.io_reset();
+        // this is an earlier return try for nonblocking write
+        match self.0.inner().send(buf) {
+            Ok(n) => return Ok(n),
+            Err(e) => {
+                // raw_os_error is faster than kind
+                let raw_err = e.raw_os_error();
+                if raw_err == Some(libc::EAGAIN) || raw_err == Some(libc::EWOULDBLOCK) {
+                    // do nothing here
+                } else {
+                    return Err(e);
+                }
+            }
+        }
+
+        let mut writer = net_impl::SocketWrite::new(
+            &self.0,
+            buf,
+            #[cfg(feature = "io_timeout")]
+            self.0.get_write_timeout(),
+        );
+        yield_with_io(&writer, writer.is_coroutine);
+        writer.done()
+    }
+
+    /// Sets the read timeout for the socket.
+    #[cfg(feature = "io_timeout")]
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.0.set_read_timeout(timeout)
+    }
+
+    /// Sets the write timeout for the socket.
+    #[cfg(feature = "io_timeout")]
+    pub fn set_write_timeout(&self, timeout: Option<Duration>) -> io::Result<()> {
+        self.0.set_write_timeout(timeout)
+    }
+
+    /// Returns the read timeout of this socket.
+    #[cfg(feature = "io_timeout")]
+    pub fn read_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.read_timeout()
+    }
+
+    /// Returns the write timeout of this socket.
+    #[cfg(feature = "io_timeout")]
+    pub fn write_timeout(&self) -> io::Result<Option<Duration>> {
+        self.0.write_timeout()
+    }
+
+    /// Returns the value of the `SO_ERROR` option.
+    pub fn take_error(&self) -> io::Result<Option<io::Error>> {
+        self.0.inner().take_error()
+    }
+
+    /// Shuts down the read, write, or both halves of this connection.
+    pub fn shutdown(&self, how: Shutdown) -> io::Result<()> {
+        self.0.inner().shutdown(how)
+    }
+}
+
+impl Read for UnixSeqpacket {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.recv(buf)
+    }
+}
+
+impl Write for UnixSeqpacket {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.send(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl AsRawFd for UnixSeqpacket {
+    fn as_raw_fd(&self) -> RawFd {
+        self.0.as_raw_fd()
+    }
+}
+
+impl FromRawFd for UnixSeqpacket {
+    unsafe fn from_raw_fd(fd: RawFd) -> UnixSeqpacket {
+        let socket = FromRawFd::from_raw_fd(fd);
+        UnixSeqpacket(CoIo::new(socket).expect("can't convert to UnixSeqpacket"))
+    }
+}
+
+impl IntoRawFd for UnixSeqpacket {
+    fn into_raw_fd(self) -> RawFd {
+        self.0.into_raw_fd()
+    }
+}
+
+impl io_impl::AsIoData for UnixSeqpacket {
+    fn as_io_data(&self) -> &io_impl::IoData {
+        self.0.as_io_data()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::os::unix::net::SocketAddrExt;
+
+    #[test]
+    fn seqpacket_keeps_boundaries() {
+        let name = format!("may-seqpacket-{}", std::process::id());
+        let addr = SocketAddr::from_abstract_name(name.as_bytes()).unwrap();
+        let listener = UnixSeqpacketListener::bind_addr(&addr).unwrap();
+
+        let h = go!(move || {
+            let (conn, _) = listener.accept().unwrap();
+            let mut buf = [0; 16];
+            let mut msgs = vec![];
+            loop {
+                let n = conn.recv(&mut buf).unwrap();
+                if n == 0 {
+                    break;
+                }
+                msgs.push(buf[..n].to_vec());
+            }
+            msgs
+        });
+
+        let conn = UnixSeqpacket::connect_addr(&addr).unwrap();
+        assert_eq!(conn.send(b"hello").unwrap(), 5);
+        assert_eq!(conn.send(b"world!").unwrap(), 6);
+        drop(conn);
+        assert_eq!(h.join().unwrap(), [&b"hello"[..], b"world!"]);
+    }
+
+    #[test]
+    fn seqpacket_pair() {
+        let (s1, mut s2) = UnixSeqpacket::pair().unwrap();
+        let h = go!(move || {
+            let mut buf = [0; 3];
+            // the rest of the message is discarded
+            let n = s1.recv(&mut buf).unwrap();
+            s1.send(&buf[..n]).unwrap();
+        });
+        s2.write_all(b"truncated").unwrap();
+        let mut buf = [0; 16];
+        assert_eq!(s2.read(&mut buf).unwrap(), 3);
+        assert_eq!(&buf[..3], b"tru");
+        h.join().unwrap();
+    }
+}
@@ -27,7 +27,7 @@ pub struct Park {
     state: AtomicBool,
     // control how to deal with the cancellation, usually init one time
     check_cancel: AtomicBool,
-    // timeout settings in ms, 0 is none (park forever)
+    // timeout settings in ns, 0 is none (park forever)
     timeout: AtomicDuration,
     // timer handle, can be null
     timeout_handle: AtomicPtr<TimeoutHandle<Arc<AtomicOption<CoroutineImpl>>>>,
@@ -0,0 +1,497 @@
+//! Coroutine-friendly child process management
+//!
+//! This module mirrors `std::process`. The piped stdio of the child are
+//! registered to the selector as nonblocking pipes, and waiting for the
+//! child parks the coroutine instead of blocking the worker thread. On
+//! Linux the exit is watched by a pidfd, on other platforms the status is
+//! polled with a back off timer.
+#![cfg(unix)]
+
+use std::ffi::OsStr;
+use std::io::{self, Read, Write};
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, OwnedFd, RawFd};
+use std::path::Path;
+use std::process;
+#[cfg(not(target_os = "linux"))]
+use std::time::Duration;
+
+pub use std::process::{ExitStatus, Output, Stdio};
+
+use crate::coroutine_impl::is_coroutine;
+use crate::io::CoIo;
+#[cfg(target_os = "linux")]
+use crate::io::WaitIo;
+
+/// A process builder, the coroutine version of `std::process::Command`.
+#[derive(Debug)]
+pub struct Command {
+    inner: process::Command,
+    kill_on_drop: bool,
+    // which of the stdio are set by the caller, the others get the
+    // defaults of `spawn` or `output`
+    stdin_set: bool,
+    stdout_set: bool,
+    stderr_set: bool,
+}
+
+impl Command {
+    /// Constructs a new `Command` for launching the program at `program`.
+    pub fn new<S: AsRef<OsStr>>(program: S) -> Command {
+        Command::from(process::Command::new(program))
+    }
+
+    /// Adds an argument to pass to the program.
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Command {
+        self.inner.arg(arg);
+        self
+    }
+
+    /// Adds multiple arguments to pass to the program.
+    pub fn args<I, S>(&mut self, args: I) -> &mut Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.inner.args(args);
+        self
+    }
+
+    /// Inserts or updates an environment variable.
+    pub fn env<K, V>(&mut self, key: K, val: V) -> &mut Command
+    where
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.inner.env(key, val);
+        self
+    }
+
+    /// Inserts or updates multiple environment variables.
+    pub fn envs<I, K, V>(&mut self, vars: I) -> &mut Command
+    where
+        I: IntoIterator<Item = (K, V)>,
+        K: AsRef<OsStr>,
+        V: AsRef<OsStr>,
+    {
+        self.inner.envs(vars);
+        self
+    }
+
+    /// Removes an environment variable.
+    pub fn env_remove<K: AsRef<OsStr>>(&mut self, key: K) -> &mut Command {
+        self.inner.env_remove(key);
+        self
+    }
+
+    /// Clears all the environment variables.
+    pub fn env_clear(&mut self) -> &mut Command {
+        self.inner.env_clear();
+        self
+    }
+
+    /// Sets the working directory for the child process.
+    pub fn current_dir<P: AsRef<Path>>(&mut self, dir: P) -> &mut Command {
+        self.inner.current_dir(dir);
+        self
+    }
+
+    /// Configuration for the child process's standard input handle.
+    pub fn stdin<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Command {
+        self.inner.stdin(cfg);
+        self.stdin_set = true;
+        self
+    }
+
+    /// Configuration for the child process's standard output handle.
+    pub fn stdout<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Command {
+        self.inner.stdout(cfg);
+        self.stdout_set = true;
+        self
+    }
+
+    /// Configuration for the child process's standard error handle.
+    pub fn stderr<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Command {
+        self.inner.stderr(cfg);
+        self.stderr_set = true;
+        self
+    }
+
+    /// Kill the child process when the `Child` is dropped before exit.
+    ///
+    /// The killed process is reaped in a coroutine. The default is false.
+    pub fn kill_on_drop(&mut self, kill_on_drop: bool) -> &mut Command {
+        self.kill_on_drop = kill_on_drop;
+        self
+    }
+
+    /// Returns a mutable reference to the inner `std::process::Command`,
+    /// which can be used to set the platform specific options.
+    ///
+    /// The stdio set through it are overridden by the defaults of `output`.
+    pub fn as_std_mut(&mut self) -> &mut process::Command {
+        &mut self.inner
+    }
+
+    /// Executes the command as a child process, returning a handle to it.
+    pub fn spawn(&mut self) -> io::Result<Child> {
+        let mut child = self.inner.spawn()?;
+        // the pipes are closed if any of them failed to register
+        let stdin = child.stdin.take().map(ChildStdin::new).transpose()?;
+        let stdout = child.stdout.take().map(ChildStdout::new).transpose()?;
+        let stderr = child.stderr.take().map(ChildStderr::new).transpose()?;
+        Ok(Child {
+            #[cfg(target_os = "linux")]
+            pidfd: pidfd_open(child.id()),
+            inner: Some(child),
+            kill_on_drop: self.kill_on_drop,
+            stdin,
+            stdout,
+            stderr,
+        })
+    }
+
+    /// Executes the command as a child process, waiting for it to finish
+    /// and collecting all of its output.
+    ///
+    /// By default the stdout and stderr are captured and the stdin is set
+    /// to null, the stdio set by the caller are kept.
+    pub fn output(&mut self) -> io::Result<Output> {
+        let (stdin, stdout, stderr) = (!self.stdin_set, !self.stdout_set, !self.stderr_set);
+        if stdin {
+            self.inner.stdin(Stdio::null());
+        }
+        if stdout {
+            self.inner.stdout(Stdio::piped());
+        }
+        if stderr {
+            self.inner.stderr(Stdio::piped());
+        }
+        let child = self.spawn();
+        // restore the defaults of spawn for the stdio not set by the caller
+        if stdin {
+            self.inner.stdin(Stdio::inherit());
+        }
+        if stdout {
+            self.inner.stdout(Stdio::inherit());
+        }
+        if stderr {
+            self.inner.stderr(Stdio::inherit());
+        }
+        child?.wait_with_output()
+    }
+
+    /// Executes a command as a child process, waiting for it to finish
+    /// and collecting its status.
+    pub fn status(&mut self) -> io::Result<ExitStatus> {
+        self.spawn()?.wait()
+    }
+}
+
+impl From<process::Command> for Command {
+    fn from(inner: process::Command) -> Self {
+        Command {
+            inner,
+            kill_on_drop: false,
+            stdin_set: false,
+            stdout_set: false,
+            stderr_set: false,
+        }
+    }
+}
+
+/// Representation of a running or exited child process.
+#[derive(Debug)]
+pub struct Child {
+    // it's none only after dropped
+    inner: Option<process::Child>,
+    // the pidfd is readable when the child exits
+    #[cfg(target_os = "linux")]
+    pidfd: Option<CoIo<OwnedFd>>,
+    kill_on_drop: bool,
+    /// The handle for writing to the child's standard input.
+    pub stdin: Option<ChildStdin>,
+    /// The handle for reading from the child's standard output.
+    pub stdout: Option<ChildStdout>,
+    /// The handle for reading from the child's standard error.
+    pub stderr: Option<ChildStderr>,
+}
+
+impl Child {
+    fn inner(&mut self) -> &mut process::Child {
+        self.inner.as_mut().expect("child is dropped")
+    }
+
+    /// Returns the OS-assigned process identifier of the child.
+    pub fn id(&self) -> u32 {
+        self.inner.as_ref().expect("child is dropped").id()
+    }
+
+    /// Forces the child process to exit.
+    pub fn kill(&mut self) -> io::Result<()> {
+        self.inner().kill()
+    }
+
+    /// Attempts to collect the exit status of the child without blocking.
+    pub fn try_wait(&mut self) -> io::Result<Option<ExitStatus>> {
+        self.inner().try_wait()
+    }
+
+    /// Waits for the child to exit completely, returning its exit status.
+    ///
+    /// The stdin handle is closed before waiting to avoid deadlock. In
+    /// coroutine context the coroutine is parked until the child exits.
+    pub fn wait(&mut self) -> io::Result<ExitStatus> {
+        drop(self.stdin.take());
+        if !is_coroutine() {
+            return self.inner().wait();
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            let child = self.inner.as_mut().expect("child is dropped");
+            let pidfd = match self.pidfd.as_ref() {
+                Some(pidfd) => pidfd,
+                // the pidfd is not supported by the kernel
+                None => return crate::blocking::run(|| child.wait()),
+            };
+            loop {
+                if let Some(status) = child.try_wait()? {
+                    return Ok(status);
+                }
+                pidfd.wait_io();
+            }
+        }
+
+        #[cfg(not(target_os = "linux"))]
+        {
+            // poll the exit status with a back off timer
+            let mut delay = Duration::from_millis(1);
+            loop {
+                if let Some(status) = self.inner().try_wait()? {
+                    return Ok(status);
+                }
+                crate::coroutine::sleep(delay);
+                delay = (delay * 2).min(Duration::from_millis(100));
+            }
+        }
+    }
+
+    /// Simultaneously waits for the child to exit and collect all
+    /// remaining output on the stdout/stderr handles.
+    pub fn wait_with_output(mut self) -> io::Result<Output> {
+        drop(self.stdin.take());
+
+        // read the stderr in another coroutine to avoid deadlock
+        let stderr = self.stderr.take().map(|mut err| {
+            go!(move || {
+                let mut buf = Vec::new();
+                err.read_to_end(&mut buf).map(|_| buf)
+            })
+        });
+        let mut stdout = Vec::new();
+        if let Some(mut out) = self.stdout.take() {
+            out.read_to_end(&mut stdout)?;
+        }
+        let stderr = match stderr {
+            Some(h) => h
+                .join()
+                .map_err(|_| io::Error::other("failed to read stderr"))??,
+            None => Vec::new(),
+        };
+
+        let status = self.wait()?;
+        Ok(Output {
+            status,
+            stdout,
+            stderr,
+        })
+    }
+}
+
+impl Drop for Child {
+    fn drop(&mut self) {
+        if !self.kill_on_drop {
+            return;
+        }
+        let mut child = match self.inner.take() {
+            Some(child) => child,
+            None => return,
+        };
+        if let Ok(None) = child.try_wait() {
+            child.kill().ok();
+            // reap the killed process in a coroutine
+            let mut child = Child {
+                inner: Some(child),
+                #[cfg(target_os = "linux")]
+                pidfd: self.pidfd.take(),
+                kill_on_drop: false,
+                stdin: None,
+                stdout: None,
+                stderr: None,
+            };
+            go!(move || child.wait().ok());
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn pidfd_open(pid: u32) -> Option<CoIo<OwnedFd>> {
+    use std::os::fd::FromRawFd;
+
+    let fd = unsafe { libc::syscall(libc::SYS_pidfd_open, pid as libc::pid_t, 0) };
+    if fd < 0 {
+        // fallback to the blocking wait
+        return None;
+    }
+    let fd = unsafe { OwnedFd::from_raw_fd(fd as RawFd) };
+    CoIo::new(fd).ok()
+}
+
+macro_rules! child_pipe {
+    ($(#[$attr:meta])* $name:ident, $std:ident) => {
+        $(#[$attr])*
+        #[derive(Debug)]
+        pub struct $name(CoIo<process::$std>);
+
+        impl $name {
+            fn new(pipe: process::$std) -> io::Result<Self> {
+                Ok($name(CoIo::new(pipe)?))
+            }
+
+            /// Returns the inner nonblocking `CoIo` pipe.
+            pub fn into_inner(self) -> CoIo<process::$std> {
+                self.0
+            }
+        }
+
+        impl AsRawFd for $name {
+            fn as_raw_fd(&self) -> RawFd {
+                self.0.as_raw_fd()
+            }
+        }
+
+        impl AsFd for $name {
+            fn as_fd(&self) -> BorrowedFd<'_> {
+                self.0.inner().as_fd()
+            }
+        }
+    };
+}
+
+child_pipe!(
+    /// A handle to the child process's standard input.
+    ChildStdin,
+    ChildStdin
+);
+child_pipe!(
+    /// A handle to the child process's standard output.
+    ChildStdout,
+    ChildStdout
+);
+child_pipe!(
+    /// A handle to the child process's standard error.
+    ChildStderr,
+    ChildStderr
+);
+
+impl Write for ChildStdin {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+impl Read for ChildStdout {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl Read for ChildStderr {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn process_output() {
+        let h = go!(|| {
+            Command::new("sh")
+                .args(["-c", "echo out; echo err >&2; exit 3"])
+                .output()
+                .unwrap()
+        });
+        let output = h.join().unwrap();
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(output.stdout, b"out\n");
+        assert_eq!(output.stderr, b"err\n");
+    }
+
+    #[test]
+    fn process_output_keeps_stdio() {
+        let h = go!(|| {
+            let mut cmd = Command::new("sh");
+            cmd.args(["-c", "cat; echo err >&2"])
+                .stdin(Stdio::piped())
+                .stderr(Stdio::null());
+            let output = cmd.output().unwrap();
+            // the stdio set by the caller are kept for the next spawn
+            let mut child = cmd.spawn().unwrap();
+            let piped = (child.stdin.is_some(), child.stdout.is_some());
+            drop(child.stdin.take());
+            child.wait().unwrap();
+            (output, piped)
+        });
+        let (output, piped) = h.join().unwrap();
+        assert!(output.status.success());
+        assert!(output.stdout.is_empty());
+        assert!(output.stderr.is_empty());
+        assert_eq!(piped, (true, false));
+    }
+
+    #[test]
+    fn process_pipe() {
+        let h = go!(|| {
+            let mut child = Command::new("cat")
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .spawn()
+                .unwrap();
+            let mut stdin = child.stdin.take().unwrap();
+            stdin.write_all(b"hello may").unwrap();
+            drop(stdin);
+            let mut s = String::new();
+            child.stdout.take().unwrap().read_to_string(&mut s).unwrap();
+            (s, child.wait().unwrap())
+        });
+        let (s, status) = h.join().unwrap();
+        assert_eq!(s, "hello may");
+        assert!(status.success());
+    }
+
+    #[test]
+    fn process_kill_on_drop() {
+        let h = go!(|| {
+            let child = Command::new("sleep")
+                .arg("10")
+                .kill_on_drop(true)
+                .spawn()
+                .unwrap();
+            child.id()
+        });
+        let pid = h.join().unwrap() as libc::pid_t;
+        // the child is killed and reaped
+        let now = std::time::Instant::now();
+        while unsafe { libc::kill(pid, 0) } == 0 {
+            assert!(now.elapsed().as_secs() < 5, "child is not reaped");
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+    }
+}
@@ -13,7 +13,7 @@ use crate::io::{EventLoop, Selector};
 use crate::likely::likely;
 use crate::pool::CoroutinePool;
 use crate::sync::AtomicOption;
-use crate::timeout_list;
+use crate::timeout_list::{self, now, TimerWheel};
 use crate::yield_now::set_co_para;
 use may_queue::mpsc::Queue;
 
@@ -31,9 +31,18 @@ cfg_if::cfg_if! {
 thread_local! { pub static WORKER_ID: Cell<usize> = const { Cell::new(usize::MAX) }; }
 
 // here we use Arc<AtomicOption<>> for that in the select implementation
-// other event may try to consume the coroutine while timer thread consume it
+// other event may try to consume the coroutine while timer consume it
 type TimerData = Arc<AtomicOption<CoroutineImpl>>;
-type TimerThread = timeout_list::TimerThread<TimerData>;
+
+// timer function, run in the worker that owns the timer
+fn timer_event_handler(c: TimerData) {
+    // just re-push the co to the visit list
+    if let Some(mut co) = c.take() {
+        // set the timeout result for the coroutine
+        set_co_para(&mut co, io::Error::new(io::ErrorKind::TimedOut, "timeout"));
+        run_coroutine(co);
+    }
+}
 
 static mut SCHED: *const Scheduler = std::ptr::null();
 
@@ -43,23 +52,6 @@ fn init_scheduler() {
     let b: Box<Scheduler> = Scheduler::new(workers);
     unsafe { SCHED = Box::into_raw(b) };
 
-    // timer thread
-    thread::spawn(move || {
-        // timer function
-        let timer_event_handler = |c: Arc<AtomicOption<CoroutineImpl>>| {
-            // just re-push the co to the visit list
-            if let Some(mut co) = c.take() {
-                // set the timeout result for the coroutine
-                set_co_para(&mut co, io::Error::new(io::ErrorKind::TimedOut, "timeout"));
-                // s.schedule_global(c);
-                run_coroutine(co);
-            }
-        };
-
-        let s = unsafe { &*SCHED };
-        s.timer_thread.run(&timer_event_handler);
-    });
-
     let core_ids = core_affinity::get_core_ids().unwrap();
     let pin_cores = config().get_worker_pin();
     // io event loop thread
@@ -96,7 +88,8 @@ pub struct Scheduler {
     stealers: Vec<Steal<CoroutineImpl>>,
     global_queues: Vec<Queue<CoroutineImpl>>,
     event_loop: EventLoop,
-    timer_thread: TimerThread,
+    // coroutine timers for each worker, driven by the event loop
+    timers: Vec<TimerWheel<TimerData>>,
     pub pool: CoroutinePool,
     pub workers: usize,
 }
@@ -122,7 +115,7 @@ impl Scheduler {
             #[cfg(feature = "work_steal")]
             stealers,
             global_queues,
-            timer_thread: TimerThread::new(),
+            timers: Vec::from_iter((0..workers).map(|_| TimerWheel::new())),
             workers,
         })
     }
@@ -255,12 +248,49 @@ impl Scheduler {
         dur: Duration,
         co: Arc<AtomicOption<CoroutineImpl>>,
     ) -> timeout_list::TimeoutHandle<TimerData> {
-        self.timer_thread.add_timer(dur, co)
+        let id = WORKER_ID.get();
+        if id != usize::MAX {
+            // the event loop would recall the next expire after running tasks
+            return self.timers[id].add_timer(dur, co).0;
+        }
+
+        static NEXT_THREAD_ID: AtomicUsize = AtomicUsize::new(0);
+        let id = NEXT_THREAD_ID
+            .fetch_add(1, Ordering::Relaxed)
+            .rem_euclid(self.workers);
+        let (h, is_recal) = self.timers[id].add_timer(dur, co);
+        if is_recal {
+            // wake up the event loop thread to recall the next wait timeout
+            self.get_selector().wakeup(id);
+        }
+        h
     }
 
     #[inline]
     pub fn del_timer(&self, handle: timeout_list::TimeoutHandle<TimerData>) {
-        self.timer_thread.del_timer(handle);
+        handle.remove();
+    }
+
+    /// resume the coroutines that their timers expired
+    /// must be called in the worker thread
+    #[inline]
+    pub fn run_timers(&self, id: usize) {
+        self.timers[id].schedule_timer(now(), &timer_event_handler);
+    }
+
+    /// merge the next timer expire of the worker in ns
+    #[inline]
+    pub fn next_timer_expire(&self, id: usize, next_expire: Option<u64>) -> Option<u64> {
+        match (next_expire, self.timers[id].next_expire(now())) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+
+    /// if any coroutine timer of the worker is not aligned to milliseconds
+    #[inline]
+    pub fn has_fine_timers(&self, id: usize) -> bool {
+        self.timers[id].has_fine_timers()
     }
 
     #[inline]
@@ -1,16 +1,20 @@
 // modified from crossbeam
 
+use std::any::Any;
 use std::cell::RefCell;
 use std::fmt;
 use std::mem;
-use std::panic;
+use std::panic::{self, AssertUnwindSafe};
 use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::thread;
 
-use crate::coroutine_impl::{spawn_builder, Builder, Coroutine};
+use crate::coroutine_impl::{current, spawn_builder, Builder, Coroutine};
 use crate::join::JoinHandle;
 use crate::sync::AtomicOption;
+use generator::Error;
+use parking_lot::Mutex;
 
 /// Like `coroutine::spawn`, but without the closure bounds.
 pub unsafe fn spawn_unsafe<'a, F>(f: F) -> JoinHandle<()>
@@ -31,6 +35,49 @@ where
 
 pub struct Scope<'a> {
     dtors: RefCell<Option<DtorChain<'a>>>,
+    fail_fast: Option<Arc<FailFast>>,
+}
+
+// shared state of a fail fast scope
+struct FailFast {
+    // set when any child coroutine failed
+    failed: AtomicBool,
+    // the panic data of the first failed child coroutine
+    panic: AtomicOption<Box<dyn Any + Send>>,
+    // all the child coroutines that may need to be canceled
+    cos: Mutex<Vec<Coroutine>>,
+}
+
+impl FailFast {
+    fn new() -> Self {
+        FailFast {
+            failed: AtomicBool::new(false),
+            panic: AtomicOption::none(),
+            cos: Mutex::new(Vec::new()),
+        }
+    }
+
+    // register a child coroutine, cancel it directly if the scope already failed
+    fn register(&self, co: Coroutine) {
+        let mut cos = self.cos.lock();
+        if self.failed.load(Ordering::Acquire) {
+            unsafe { co.cancel() };
+        }
+        cos.push(co);
+    }
+
+    // record the first panic and cancel all the other child coroutines
+    fn fail(&self, me: &Coroutine, panic: Box<dyn Any + Send>) {
+        let cos = self.cos.lock();
+        if self.failed.swap(true, Ordering::AcqRel) {
+            // only the first failure is reported
+            return;
+        }
+        self.panic.store(panic);
+        for co in cos.iter().filter(|co| !co.ptr_eq(me)) {
+            unsafe { co.cancel() };
+        }
+    }
 }
 
 struct DtorChain<'a> {
@@ -44,16 +91,12 @@ enum JoinState {
 }
 
 impl JoinState {
-    fn join(&mut self) {
+    fn join(&mut self) -> thread::Result<()> {
         let mut state = JoinState::Joined;
         mem::swap(self, &mut state);
-        if let JoinState::Running(handle) = state {
-            let res = handle.join();
-
-            // TODO: when panic happened, the logic need to refine
-            if !thread::panicking() {
-                res.unwrap_or_else(|e| panic::resume_unwind(e));
-            }
+        match state {
+            JoinState::Running(handle) => handle.join(),
+            JoinState::Joined => Ok(()),
         }
     }
 }
@@ -63,6 +106,7 @@ pub struct ScopedJoinHandle<T> {
     inner: Rc<RefCell<JoinState>>,
     packet: Arc<AtomicOption<T>>,
     co: Coroutine,
+    fail_fast: bool,
 }
 
 /// Create a new `scope`, for deferred destructors.
@@ -75,12 +119,66 @@ where
 {
     let mut scope = Scope {
         dtors: RefCell::new(None),
+        fail_fast: None,
     };
     let ret = f(&scope);
     scope.drop_all();
     ret
 }
 
+/// Create a new fail fast `scope`.
+///
+/// This is the same as [`scope`] except that the first panic of any scoped
+/// coroutine would cancel all the other scoped coroutines, instead of waiting
+/// them to run to completion. The panic is returned as the scope result
+/// after all the scoped coroutines are joined.
+///
+/// A coroutine spawned by [`Scope::try_spawn`] fails the scope the same way
+/// when it returns an error, the error is boxed as the scope result.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate may;
+/// use may::coroutine;
+/// use std::time::Duration;
+///
+/// # fn main() {
+/// let ret = coroutine::scope_fail_fast(|s| {
+///     go!(s, || coroutine::sleep(Duration::from_secs(1000)));
+///     go!(s, || panic!("request failed"));
+/// });
+///
+/// assert!(ret.is_err());
+///
+/// let ret = coroutine::scope_fail_fast(|s| unsafe {
+///     go!(s, || coroutine::sleep(Duration::from_secs(1000)));
+///     s.try_spawn(|| "abc".parse::<u32>());
+/// });
+///
+/// let err = ret.unwrap_err();
+/// assert!(err.downcast_ref::<std::num::ParseIntError>().is_some());
+/// # }
+/// ```
+///
+/// [`scope`]: fn.scope.html
+pub fn scope_fail_fast<'a, F, R>(f: F) -> thread::Result<R>
+where
+    F: FnOnce(&Scope<'a>) -> R,
+{
+    let fail_fast = Arc::new(FailFast::new());
+    let mut scope = Scope {
+        dtors: RefCell::new(None),
+        fail_fast: Some(fail_fast.clone()),
+    };
+    let ret = f(&scope);
+    scope.drop_all();
+    match fail_fast.panic.take() {
+        Some(panic) => Err(panic),
+        None => Ok(ret),
+    }
+}
+
 impl fmt::Debug for Scope<'_> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Scope {{ ... }}")
@@ -140,34 +238,65 @@ impl<'a> Scope<'a> {
     /// scope exits.
     fn spawn_impl<F, T>(&self, f: F, builder: Builder) -> ScopedJoinHandle<T>
     where
-        F: FnOnce() -> T + Send + 'a,
+        F: FnOnce() -> thread::Result<T> + Send + 'a,
         T: Send + 'a,
     {
         let their_packet = Arc::new(AtomicOption::none());
         let my_packet = their_packet.clone();
 
-        let join_handle = unsafe {
-            spawn_unsafe_builder(
-                move || {
-                    their_packet.store(f());
-                },
-                builder,
-            )
+        let join_handle = match self.fail_fast.clone() {
+            None => unsafe {
+                spawn_unsafe_builder(
+                    move || match f() {
+                        Ok(ret) => their_packet.store(ret),
+                        // the error is reported as a panic when joined
+                        Err(e) => panic::resume_unwind(e),
+                    },
+                    builder,
+                )
+            },
+            Some(fail_fast) => unsafe {
+                spawn_unsafe_builder(
+                    move || match panic::catch_unwind(AssertUnwindSafe(f)) {
+                        Ok(Ok(ret)) => their_packet.store(ret),
+                        Ok(Err(e)) => fail_fast.fail(&current(), e),
+                        Err(e) => {
+                            // canceled by the scope, just continue the unwind
+                            if let Some(Error::Cancel) = e.downcast_ref::<Error>() {
+                                panic::resume_unwind(e);
+                            }
+                            fail_fast.fail(&current(), e);
+                        }
+                    },
+                    builder,
+                )
+            },
         };
 
         let co = join_handle.coroutine().clone();
+        if let Some(ref fail_fast) = self.fail_fast {
+            fail_fast.register(co.clone());
+        }
+
+        let fail_fast = self.fail_fast.is_some();
         let deferred_handle = Rc::new(RefCell::new(JoinState::Running(join_handle)));
         let my_handle = deferred_handle.clone();
 
         self.defer(move || {
-            let mut state = deferred_handle.borrow_mut();
-            state.join();
+            let res = deferred_handle.borrow_mut().join();
+
+            // for fail fast scope the failure is already recorded in the scope
+            // TODO: when panic happened, the logic need to refine
+            if !fail_fast && !thread::panicking() {
+                res.unwrap_or_else(|e| panic::resume_unwind(e));
+            }
         });
 
         ScopedJoinHandle {
             inner: my_handle,
             packet: my_packet,
             co,
+            fail_fast,
         }
     }
 
@@ -183,7 +312,7 @@ impl<'a> Scope<'a> {
         F: FnOnce() -> T + Send + 'a,
         T: Send + 'a,
     {
-        self.spawn_impl(f, Builder::new())
+        self.spawn_impl(move || Ok(f()), Builder::new())
     }
 
     pub unsafe fn spawn_with_builder<F, T>(&self, f: F, builder: Builder) -> ScopedJoinHandle<T>
@@ -191,15 +320,50 @@ impl<'a> Scope<'a> {
         F: FnOnce() -> T + Send + 'a,
         T: Send + 'a,
     {
-        self.spawn_impl(f, builder)
+        self.spawn_impl(move || Ok(f()), builder)
+    }
+
+    /// Create a scoped coroutine that may fail with an error.
+    ///
+    /// In a fail fast scope, the returned error fails the scope like a panic,
+    /// the other scoped coroutines are canceled and the error is returned as
+    /// the scope result. In a normal scope the error is raised as a panic
+    /// when the coroutine is joined.
+    pub unsafe fn try_spawn<F, T, E>(&self, f: F) -> ScopedJoinHandle<T>
+    where
+        F: FnOnce() -> Result<T, E> + Send + 'a,
+        T: Send + 'a,
+        E: Send + 'static,
+    {
+        let f = move || f().map_err(|e| Box::new(e) as Box<dyn Any + Send>);
+        self.spawn_impl(f, Builder::new())
     }
 }
 
 impl<T> ScopedJoinHandle<T> {
     /// Join the scoped coroutine, returning the result it produced.
+    ///
+    /// In a fail fast scope, this would panic if the coroutine failed or was
+    /// canceled, use [`try_join`](Self::try_join) to get an error instead.
     pub fn join(self) -> T {
-        self.inner.borrow_mut().join();
-        self.packet.take().unwrap()
+        let fail_fast = self.fail_fast;
+        match self.try_join() {
+            Ok(ret) => ret,
+            Err(e) if !fail_fast => panic::resume_unwind(e),
+            Err(_) => panic!("scoped coroutine failed in a fail fast scope"),
+        }
+    }
+
+    /// Join the scoped coroutine, returning the failure as an error.
+    ///
+    /// If the coroutine panicked, the panic is returned as the error. In a
+    /// fail fast scope, a failed or canceled coroutine also returns an error,
+    /// the first failure itself is reported as the scope result.
+    pub fn try_join(self) -> thread::Result<T> {
+        self.inner.borrow_mut().join()?;
+        self.packet.take().ok_or_else(|| {
+            Box::new("scoped coroutine failed in a fail fast scope") as Box<dyn Any + Send>
+        })
     }
 
     /// Get the underlying coroutine handle.
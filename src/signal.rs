@@ -0,0 +1,290 @@
+//! Unix signal handling for coroutines
+//!
+//! The signal handler writes the signal number to a self-pipe that is
+//! registered in the selector, a dispatcher coroutine reads the pipe and
+//! forwards the signals to every `Signals` listener that subscribes them.
+//! The handler replaces the previous handler of the signal, and the
+//! signal is no longer handled by the default action once registered.
+#![cfg(unix)]
+
+use std::collections::HashSet;
+use std::io::{self, Read};
+use std::os::raw::c_int;
+use std::os::unix::io::AsRawFd;
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicI32, AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+
+pub use libc::{
+    SIGALRM, SIGCHLD, SIGCONT, SIGHUP, SIGINT, SIGPIPE, SIGQUIT, SIGTERM, SIGTSTP, SIGUSR1,
+    SIGUSR2, SIGWINCH,
+};
+
+use crate::io::CoIo;
+use crate::sync::mpsc::{self, Receiver, Sender};
+use nix::errno::Errno;
+use parking_lot::Mutex;
+
+// the signals that can't be caught or should not be caught
+const FORBIDDEN: &[c_int] = &[
+    libc::SIGKILL,
+    libc::SIGSTOP,
+    libc::SIGILL,
+    libc::SIGFPE,
+    libc::SIGSEGV,
+    libc::SIGBUS,
+];
+
+// the write end of the self-pipe, used in the signal handler
+static PIPE_FD: AtomicI32 = AtomicI32::new(-1);
+
+struct Listener {
+    id: usize,
+    signals: HashSet<c_int>,
+    tx: Sender<c_int>,
+}
+
+struct Registry {
+    listeners: Mutex<Vec<Listener>>,
+    // the signals that the handler is installed
+    installed: Mutex<HashSet<c_int>>,
+    next_id: AtomicUsize,
+    // keep the write end open
+    _writer: UnixStream,
+}
+
+fn registry() -> io::Result<&'static Registry> {
+    static REGISTRY: OnceLock<io::Result<Registry>> = OnceLock::new();
+    REGISTRY
+        .get_or_init(Registry::new)
+        .as_ref()
+        .map_err(|e| io::Error::new(e.kind(), e.to_string()))
+}
+
+impl Registry {
+    fn new() -> io::Result<Self> {
+        let (reader, writer) = UnixStream::pair()?;
+        // the handler must never block
+        writer.set_nonblocking(true)?;
+        let mut reader = CoIo::new(reader)?;
+        PIPE_FD.store(writer.as_raw_fd(), Ordering::Release);
+
+        // dispatch the signals to the listeners
+        go!(move || {
+            let mut buf = [0u8; 64];
+            loop {
+                let n = match reader.read(&mut buf) {
+                    Ok(0) => {
+                        error!("signal pipe closed");
+                        break;
+                    }
+                    Ok(n) => n,
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                    Err(e) => {
+                        error!("failed to read signal pipe, err={e}");
+                        break;
+                    }
+                };
+                let registry = registry().expect("signal registry");
+                let listeners = registry.listeners.lock();
+                for &sig in &buf[..n] {
+                    let sig = sig as c_int;
+                    for l in listeners.iter().filter(|l| l.signals.contains(&sig)) {
+                        l.tx.send(sig).ok();
+                    }
+                }
+            }
+
+            // the error would repeat, stop the dispatching and drop the
+            // senders so that the listeners see it
+            PIPE_FD.store(-1, Ordering::Release);
+            if let Ok(registry) = registry() {
+                registry.listeners.lock().clear();
+            }
+        });
+
+        Ok(Registry {
+            listeners: Mutex::new(Vec::new()),
+            installed: Mutex::new(HashSet::new()),
+            next_id: AtomicUsize::new(0),
+            _writer: writer,
+        })
+    }
+
+    // install the handler for the signal if not yet
+    fn install(&self, sig: c_int) -> io::Result<()> {
+        // the handler passes the signal number in one byte
+        if FORBIDDEN.contains(&sig) || !(1..=u8::MAX as c_int).contains(&sig) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("signal {sig} can't be handled"),
+            ));
+        }
+
+        let mut installed = self.installed.lock();
+        if installed.contains(&sig) {
+            return Ok(());
+        }
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            action.sa_sigaction = handler as extern "C" fn(c_int) as libc::sighandler_t;
+            action.sa_flags = libc::SA_RESTART;
+            libc::sigemptyset(&mut action.sa_mask);
+            if libc::sigaction(sig, &action, std::ptr::null_mut()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        installed.insert(sig);
+        Ok(())
+    }
+}
+
+extern "C" fn handler(sig: c_int) {
+    // only async-signal-safe calls are allowed here
+    let errno = Errno::last_raw();
+    let fd = PIPE_FD.load(Ordering::Acquire);
+    if fd < 0 {
+        return;
+    }
+    // checked in install
+    let b = sig as u8;
+    unsafe { libc::write(fd, &b as *const u8 as *const _, 1) };
+    Errno::set_raw(errno);
+}
+
+/// A listener of a set of unix signals
+///
+/// Each `Signals` receives its own copy of the signals it subscribes, so
+/// independent listeners can co-exist. The signals that arrive at nearly
+/// the same time may be coalesced by the OS.
+///
+/// # Examples
+///
+/// ```no_run
+/// use may::signal::{Signals, SIGHUP, SIGTERM};
+///
+/// let signals = Signals::new(&[SIGTERM, SIGHUP]).unwrap();
+/// for sig in &signals {
+///     if sig == SIGTERM {
+///         break;
+///     }
+/// }
+/// ```
+pub struct Signals {
+    id: usize,
+    rx: Receiver<c_int>,
+}
+
+impl Signals {
+    /// Creates a listener for the signals.
+    ///
+    /// Returns an error for the signals that can't be handled, such as
+    /// `SIGKILL` and `SIGSEGV`.
+    pub fn new(signals: &[c_int]) -> io::Result<Signals> {
+        let registry = registry()?;
+        if PIPE_FD.load(Ordering::Acquire) < 0 {
+            return Err(io::Error::other("signal dispatcher stopped"));
+        }
+        for &sig in signals {
+            registry.install(sig)?;
+        }
+        let (tx, rx) = mpsc::channel();
+        let id = registry.next_id.fetch_add(1, Ordering::Relaxed);
+        registry.listeners.lock().push(Listener {
+            id,
+            signals: signals.iter().copied().collect(),
+            tx,
+        });
+        Ok(Signals { id, rx })
+    }
+
+    /// Subscribes one more signal.
+    pub fn add_signal(&self, sig: c_int) -> io::Result<()> {
+        let registry = registry()?;
+        registry.install(sig)?;
+        let mut listeners = registry.listeners.lock();
+        if let Some(l) = listeners.iter_mut().find(|l| l.id == self.id) {
+            l.signals.insert(sig);
+        }
+        Ok(())
+    }
+
+    /// Waits for the next signal.
+    ///
+    /// Returns `None` if the signal dispatcher is stopped by an
+    /// unrecoverable error of the signal pipe.
+    pub fn recv(&self) -> Option<c_int> {
+        // the sender is only dropped when the dispatcher stops
+        self.rx.recv().ok()
+    }
+
+    /// Returns the pending signal without waiting.
+    pub fn try_recv(&self) -> Option<c_int> {
+        self.rx.try_recv().ok()
+    }
+
+    /// Waits for the next signal with a timeout.
+    pub fn recv_timeout(&self, timeout: Duration) -> Option<c_int> {
+        self.rx.recv_timeout(timeout).ok()
+    }
+}
+
+impl Drop for Signals {
+    fn drop(&mut self) {
+        // the handler is kept installed for the other listeners
+        if let Ok(registry) = registry() {
+            registry.listeners.lock().retain(|l| l.id != self.id);
+        }
+    }
+}
+
+impl std::fmt::Debug for Signals {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Signals").field("id", &self.id).finish()
+    }
+}
+
+/// An iterator over the received signals, it ends only when the signal
+/// dispatcher is stopped
+impl Iterator for &Signals {
+    type Item = c_int;
+
+    fn next(&mut self) -> Option<c_int> {
+        self.recv()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn raise(sig: c_int) {
+        unsafe { libc::kill(libc::getpid(), sig) };
+    }
+
+    #[test]
+    fn signal_listeners() {
+        let a = Signals::new(&[SIGUSR1]).unwrap();
+        let b = Signals::new(&[SIGUSR1, SIGUSR2]).unwrap();
+        let h = go!(move || (b.recv(), b.recv()));
+        raise(SIGUSR1);
+        assert_eq!(a.recv_timeout(Duration::from_secs(5)), Some(SIGUSR1));
+        raise(SIGUSR2);
+        let (x, y) = h.join().unwrap();
+        assert_eq!((x, y), (Some(SIGUSR1), Some(SIGUSR2)));
+        // a doesn't subscribe SIGUSR2
+        assert_eq!(a.recv_timeout(Duration::from_millis(50)), None);
+    }
+
+    #[test]
+    fn signal_forbidden() {
+        let err = Signals::new(&[libc::SIGKILL]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        // out of the range of the signal numbers
+        let err = Signals::new(&[256 + SIGUSR1]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        let err = Signals::new(&[0]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
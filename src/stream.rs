@@ -0,0 +1,203 @@
+use std::fmt;
+use std::panic;
+
+use crate::coroutine_impl::{park, spawn};
+use crate::join::JoinHandle;
+use crate::sync::mpsc::{channel, Receiver, Sender};
+
+/// the producer side of a [`Stream`], passed to the stream body
+pub struct Yielder<T> {
+    // wait for the consumer to pull the next item
+    req: Receiver<()>,
+    // deliver the item to the consumer
+    tx: Sender<T>,
+}
+
+impl<T> Yielder<T> {
+    /// hand the item to the consumer
+    ///
+    /// the producer coroutine is suspended until the consumer pulls
+    /// the next item. if the stream is dropped the producer would be
+    /// cancelled by a `Cancel` panic at this point.
+    pub fn yield_(&self, item: T) {
+        if self.tx.send(item).is_err() {
+            // the stream is gone, just wait for the cancel
+            park();
+        }
+        // block until the next pull
+        if self.req.recv().is_err() {
+            park();
+        }
+    }
+}
+
+impl<T> fmt::Debug for Yielder<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("Yielder { .. }")
+    }
+}
+
+/// a lazy iterator whose items are produced by a coroutine
+///
+/// created by [`stream`]
+pub struct Stream<T> {
+    // request the producer for the next item
+    req: Sender<()>,
+    rx: Receiver<T>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl<T> Iterator for Stream<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.handle.as_ref()?;
+        if self.req.send(()).is_ok() {
+            if let Ok(item) = self.rx.recv() {
+                return Some(item);
+            }
+        }
+        // the producer is done, propagate the panic if any
+        let handle = self.handle.take().unwrap();
+        if let Err(panic) = handle.join() {
+            panic::resume_unwind(panic);
+        }
+        None
+    }
+}
+
+impl<T> Drop for Stream<T> {
+    fn drop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            if !handle.is_done() {
+                unsafe { handle.coroutine().cancel() };
+            }
+            handle.join().ok();
+        }
+    }
+}
+
+impl<T> fmt::Debug for Stream<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.pad("Stream { .. }")
+    }
+}
+
+/// create a lazy stream that produces items in a coroutine
+///
+/// the body runs as a normal coroutine, so it can do blocking IO, and
+/// hands out items through the [`Yielder`]. the body doesn't start until
+/// the first item is pulled, and is suspended after each item until the
+/// next one is pulled. dropping the stream cancels the body.
+///
+/// a panic in the body is propagated to the consumer.
+///
+/// # Safety
+///
+/// the body runs in a coroutine, see [`spawn`](fn.spawn.html)
+///
+/// # Examples
+///
+/// ```
+/// use may::coroutine;
+///
+/// let s = unsafe {
+///     coroutine::stream(|yielder| {
+///         for i in 0..10 {
+///             yielder.yield_(i);
+///         }
+///     })
+/// };
+/// assert_eq!(s.take(3).collect::<Vec<_>>(), vec![0, 1, 2]);
+/// ```
+pub unsafe fn stream<F, T>(f: F) -> Stream<T>
+where
+    F: FnOnce(&Yielder<T>) + Send + 'static,
+    T: Send + 'static,
+{
+    let (req_tx, req_rx) = channel();
+    let (tx, rx) = channel();
+    let handle = spawn(move || {
+        // wait for the first pull
+        if req_rx.recv().is_err() {
+            return;
+        }
+        let yielder = Yielder { req: req_rx, tx };
+        f(&yielder);
+    });
+
+    Stream {
+        req: req_tx,
+        rx,
+        handle: Some(handle),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::net::{TcpListener, TcpStream};
+    use std::io::{Read, Write};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    #[test]
+    fn stream_backpressure() {
+        let produced = Arc::new(AtomicUsize::new(0));
+        let p = produced.clone();
+        let mut s = unsafe {
+            stream(move |y| {
+                for i in 0.. {
+                    p.fetch_add(1, Ordering::SeqCst);
+                    y.yield_(i);
+                }
+            })
+        };
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert_eq!(produced.load(Ordering::SeqCst), 0);
+        assert_eq!(s.next(), Some(0));
+        assert_eq!(s.next(), Some(1));
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert_eq!(produced.load(Ordering::SeqCst), 2);
+        // cancel the endless producer
+        drop(s);
+    }
+
+    #[test]
+    fn stream_io() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        go!(move || {
+            let (mut conn, _) = listener.accept().unwrap();
+            conn.write_all(b"hello stream").unwrap();
+        });
+
+        let s = unsafe {
+            stream(move |y| {
+                let mut conn = TcpStream::connect(addr).unwrap();
+                let mut buf = [0; 4];
+                loop {
+                    let n = conn.read(&mut buf).unwrap();
+                    if n == 0 {
+                        break;
+                    }
+                    y.yield_(buf[..n].to_vec());
+                }
+            })
+        };
+        let data: Vec<u8> = s.flatten().collect();
+        assert_eq!(data, b"hello stream");
+    }
+
+    #[test]
+    #[should_panic(expected = "stream panic")]
+    fn stream_panic() {
+        let s = unsafe {
+            stream(|y| {
+                y.yield_(1);
+                panic!("stream panic");
+            })
+        };
+        assert_eq!(s.count(), 1);
+    }
+}
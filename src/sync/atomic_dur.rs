@@ -1,18 +1,18 @@
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::Duration;
 
-// atomic duration in milli seconds
+// atomic duration in nano seconds
 #[derive(Debug)]
-pub struct AtomicDuration(AtomicUsize);
+pub struct AtomicDuration(AtomicU64);
 
 impl AtomicDuration {
     pub fn new(dur: Option<Duration>) -> Self {
         let dur = match dur {
             None => 0,
-            Some(d) => d.as_millis() as usize,
+            Some(d) => d.as_nanos().min(u64::MAX as u128) as u64,
         };
 
-        AtomicDuration(AtomicUsize::new(dur))
+        AtomicDuration(AtomicU64::new(dur))
     }
 
     #[inline]
@@ -20,7 +20,7 @@ impl AtomicDuration {
     pub fn get(&self) -> Option<Duration> {
         match self.0.load(Ordering::Relaxed) {
             0 => None,
-            d => Some(Duration::from_millis(d as u64)),
+            d => Some(Duration::from_nanos(d)),
         }
     }
 
@@ -28,7 +28,7 @@ impl AtomicDuration {
     pub fn store(&self, dur: Option<Duration>) {
         let timeout = match dur {
             None => 0,
-            Some(d) => d.as_millis() as usize,
+            Some(d) => d.as_nanos().min(u64::MAX as u128) as u64,
         };
 
         self.0.store(timeout, Ordering::Relaxed);
@@ -38,7 +38,7 @@ impl AtomicDuration {
     pub fn take(&self) -> Option<Duration> {
         match self.0.swap(0, Ordering::Relaxed) {
             0 => None,
-            d => Some(Duration::from_millis(d as u64)),
+            d => Some(Duration::from_nanos(d)),
         }
     }
 }
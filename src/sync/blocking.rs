@@ -2,6 +2,8 @@ use parking_lot::{Condvar, Mutex};
 
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+#[cfg(feature = "futures")]
+use std::task::Waker;
 use std::time::Duration;
 
 use crate::coroutine_impl::is_coroutine;
@@ -53,6 +55,9 @@ impl ThreadPark {
 pub enum Parker {
     Coroutine(Park),
     Thread(ThreadPark),
+    // an async task that can't be parked, only woken up
+    #[cfg(feature = "futures")]
+    Waker(Waker),
 }
 
 #[derive(Debug)]
@@ -75,6 +80,14 @@ impl Blocker {
         Blocker { parker }
     }
 
+    /// create a blocker that wakes up the async task when unparked
+    #[cfg(feature = "futures")]
+    pub fn from_waker(waker: Waker) -> Self {
+        Blocker {
+            parker: Parker::Waker(waker),
+        }
+    }
+
     /// get the internal shared blocker
     pub fn current() -> Arc<Self> {
         Arc::new(Self::new(false))
@@ -85,6 +98,8 @@ impl Blocker {
         match self.parker {
             Parker::Coroutine(ref co) => co.park_timeout(timeout),
             Parker::Thread(ref t) => t.park_timeout(timeout),
+            #[cfg(feature = "futures")]
+            Parker::Waker(_) => panic!("can't park an async task"),
         }
     }
 
@@ -93,6 +108,8 @@ impl Blocker {
         match self.parker {
             Parker::Coroutine(ref co) => co.unpark(),
             Parker::Thread(ref t) => t.unpark(),
+            #[cfg(feature = "futures")]
+            Parker::Waker(ref w) => w.wake_by_ref(),
         }
     }
 }
@@ -7,13 +7,53 @@
 //! would not see that the same data any more
 
 use std::fmt;
+#[cfg(feature = "futures")]
+use std::pin::Pin;
+#[cfg(feature = "futures")]
+use std::sync::atomic::AtomicBool;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError};
 use std::sync::Arc;
+#[cfg(feature = "futures")]
+use std::task::{Context, Poll, Waker};
 use std::time::Duration;
 
 use super::Semphore;
 use crossbeam::queue::SegQueue;
+#[cfg(feature = "futures")]
+use futures_core::Stream;
+#[cfg(feature = "futures")]
+use futures_sink::Sink;
+#[cfg(feature = "futures")]
+use parking_lot::Mutex;
+
+/// /////////////////////////////////////////////////////////////////////////////
+/// RxWaker
+/// /////////////////////////////////////////////////////////////////////////////
+// the waker slot of a receiver, it's queued at most once in the waker list
+#[cfg(feature = "futures")]
+#[derive(Default)]
+struct RxWaker {
+    waker: Mutex<Option<Waker>>,
+    queued: AtomicBool,
+}
+
+#[cfg(feature = "futures")]
+impl RxWaker {
+    // return false if there is no waker, e.g. the receiver is dropped
+    fn wake(&self) -> bool {
+        // clear the flag first so that a waker registered after the take
+        // would queue the slot again
+        self.queued.store(false, Ordering::Release);
+        match self.waker.lock().take() {
+            Some(w) => {
+                w.wake();
+                true
+            }
+            None => false,
+        }
+    }
+}
 
 /// /////////////////////////////////////////////////////////////////////////////
 /// InnerQueue
@@ -22,6 +62,9 @@ struct InnerQueue<T> {
     queue: SegQueue<T>,
     // thread/coroutine for wake up
     sem: Semphore,
+    // async tasks for wake up, they would compete for the data
+    #[cfg(feature = "futures")]
+    wakers: SegQueue<Arc<RxWaker>>,
     // The number of tx channels which are currently using this queue.
     tx_ports: AtomicUsize,
     // if rx is dropped
@@ -33,6 +76,8 @@ impl<T> InnerQueue<T> {
         InnerQueue {
             queue: SegQueue::new(),
             sem: Semphore::new(0),
+            #[cfg(feature = "futures")]
+            wakers: SegQueue::new(),
             tx_ports: AtomicUsize::new(1),
             rx_ports: AtomicUsize::new(1),
         }
@@ -45,9 +90,56 @@ impl<T> InnerQueue<T> {
 
         self.queue.push(t);
         self.sem.post();
+        #[cfg(feature = "futures")]
+        self.wake_one();
         Ok(())
     }
 
+    // wake up one pending async task for the new data, if the data is
+    // taken by others the task would register again
+    #[cfg(feature = "futures")]
+    fn wake_one(&self) {
+        while let Some(w) = self.wakers.pop() {
+            if w.wake() {
+                return;
+            }
+        }
+    }
+
+    // wake up all the pending async tasks when the channel is disconnected
+    #[cfg(feature = "futures")]
+    fn wake_all(&self) {
+        while let Some(w) = self.wakers.pop() {
+            w.wake();
+        }
+    }
+
+    // async version of recv, register the task waker to the waker list
+    #[cfg(feature = "futures")]
+    fn poll_recv(&self, slot: &Arc<RxWaker>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        match self.try_recv() {
+            Err(TryRecvError::Empty) => {}
+            data => return Poll::Ready(data.ok()),
+        }
+
+        // register the waker, the slot of the receiver is only queued once
+        {
+            let mut waker = slot.waker.lock();
+            match waker.as_ref() {
+                Some(w) if w.will_wake(cx.waker()) => {}
+                _ => *waker = Some(cx.waker().clone()),
+            }
+        }
+        if !slot.queued.swap(true, Ordering::AcqRel) {
+            self.wakers.push(slot.clone());
+        }
+        // re-check the queue, a spurious wake up is harmless
+        match self.try_recv() {
+            Err(TryRecvError::Empty) => Poll::Pending,
+            data => Poll::Ready(data.ok()),
+        }
+    }
+
     pub fn recv(&self, dur: Option<Duration>) -> Result<T, RecvTimeoutError> {
         match self.try_recv() {
             Ok(data) => return Ok(data),
@@ -102,6 +194,8 @@ impl<T> InnerQueue<T> {
                 while self.sem.get_value() == 0 {
                     self.sem.post();
                 }
+                #[cfg(feature = "futures")]
+                self.wake_all();
             }
             n if n > 1 => {}
             n => panic!("bad number of tx_ports left {n}"),
@@ -133,6 +227,8 @@ impl<T> Drop for InnerQueue<T> {
 
 pub struct Receiver<T> {
     inner: Arc<InnerQueue<T>>,
+    #[cfg(feature = "futures")]
+    waker: Arc<RxWaker>,
 }
 
 unsafe impl<T: Send> Send for Receiver<T> {}
@@ -151,7 +247,8 @@ pub struct IntoIter<T> {
 }
 
 pub struct Sender<T> {
-    inner: Arc<InnerQueue<T>>,
+    // none if the sender is closed as a sink
+    inner: Option<Arc<InnerQueue<T>>>,
 }
 
 unsafe impl<T: Send> Send for Sender<T> {}
@@ -168,29 +265,44 @@ pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
 
 impl<T> Sender<T> {
     fn new(inner: Arc<InnerQueue<T>>) -> Sender<T> {
-        Sender { inner }
+        Sender { inner: Some(inner) }
     }
 
     pub fn send(&self, t: T) -> Result<(), SendError<T>> {
-        self.inner.send(t)
+        match self.inner {
+            Some(ref inner) => inner.send(t),
+            None => Err(SendError(t)),
+        }
     }
 
     /// return how many elements in the queue that are not consumed by receivers
     pub fn pressure(&self) -> usize {
-        self.inner.sem.get_value()
+        self.inner.as_ref().map_or(0, |inner| inner.sem.get_value())
+    }
+
+    // disconnect the sender, the receiver sees it when all the senders are closed
+    fn close(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            inner.drop_tx();
+        }
     }
 }
 
 impl<T> Clone for Sender<T> {
     fn clone(&self) -> Sender<T> {
-        self.inner.clone_tx();
-        Sender::new(self.inner.clone())
+        match self.inner {
+            Some(ref inner) => {
+                inner.clone_tx();
+                Sender::new(inner.clone())
+            }
+            None => Sender { inner: None },
+        }
     }
 }
 
 impl<T> Drop for Sender<T> {
     fn drop(&mut self) {
-        self.inner.drop_tx();
+        self.close();
     }
 }
 
@@ -206,7 +318,11 @@ impl<T> fmt::Debug for Sender<T> {
 
 impl<T> Receiver<T> {
     fn new(inner: Arc<InnerQueue<T>>) -> Receiver<T> {
-        Receiver { inner }
+        Receiver {
+            inner,
+            #[cfg(feature = "futures")]
+            waker: Arc::default(),
+        }
     }
 
     pub fn try_recv(&self) -> Result<T, TryRecvError> {
@@ -283,6 +399,14 @@ impl<T> Clone for Receiver<T> {
 
 impl<T> Drop for Receiver<T> {
     fn drop(&mut self) {
+        #[cfg(feature = "futures")]
+        {
+            // the receiver may be woken up for the data, pass it to another one
+            self.waker.waker.lock().take();
+            if !self.inner.queue.is_empty() {
+                self.inner.wake_one();
+            }
+        }
         self.inner.drop_rx();
     }
 }
@@ -293,6 +417,45 @@ impl<T> fmt::Debug for Receiver<T> {
     }
 }
 
+////////////////////////////////////////////////////////////////////////////////
+// Async support
+////////////////////////////////////////////////////////////////////////////////
+
+/// The receiver can be consumed as a stream by async code,
+/// the stream ends when all the senders are dropped
+#[cfg(feature = "futures")]
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.inner.poll_recv(&self.waker, cx)
+    }
+}
+
+/// The sender can be used as a sink by async code,
+/// the channel is unbounded so it's always ready to send
+#[cfg(feature = "futures")]
+impl<T> Sink<T> for Sender<T> {
+    type Error = SendError<T>;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        self.send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().close();
+        Poll::Ready(Ok(()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1034,4 +1197,47 @@ mod tests {
         }
         assert!(rx1.try_recv().is_err());
     }
+
+    #[test]
+    #[cfg(feature = "futures")]
+    fn stream_waker_registered_once() {
+        use std::task::Wake;
+
+        struct CountWaker(AtomicUsize);
+
+        impl Wake for CountWaker {
+            fn wake(self: Arc<Self>) {
+                self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        let (tx, mut rx) = channel::<i32>();
+        let mut rx1 = rx.clone();
+        let count = Arc::new(CountWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(count.clone());
+        let mut cx = Context::from_waker(&waker);
+
+        for _ in 0..100 {
+            assert!(Pin::new(&mut rx).poll_next(&mut cx).is_pending());
+            assert!(Pin::new(&mut rx1).poll_next(&mut cx).is_pending());
+        }
+        // one slot for each receiver
+        assert_eq!(rx.inner.wakers.len(), 2);
+
+        // only the first receiver is woken up for the data
+        tx.send(1).unwrap();
+        assert_eq!(count.0.load(Ordering::SeqCst), 1);
+        assert_eq!(rx.inner.wakers.len(), 1);
+        assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Ready(Some(1)));
+        assert!(Pin::new(&mut rx).poll_next(&mut cx).is_pending());
+        assert_eq!(rx.inner.wakers.len(), 2);
+
+        // the dropped receiver passes the wake up to the other one
+        tx.send(2).unwrap();
+        assert_eq!(count.0.load(Ordering::SeqCst), 2);
+        drop(rx1);
+        assert_eq!(count.0.load(Ordering::SeqCst), 3);
+        assert!(rx.inner.wakers.is_empty());
+        assert_eq!(Pin::new(&mut rx).poll_next(&mut cx), Poll::Ready(Some(2)));
+    }
 }
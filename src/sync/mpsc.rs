@@ -2,14 +2,22 @@
 //! please ref the doc from std::sync::mpsc
 use std::fmt;
 use std::panic::{RefUnwindSafe, UnwindSafe};
+#[cfg(feature = "futures")]
+use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc::{RecvError, RecvTimeoutError, SendError, TryRecvError};
 use std::sync::Arc;
+#[cfg(feature = "futures")]
+use std::task::{Context, Poll};
 use std::time::{Duration, Instant};
 
 use super::{AtomicOption, Blocker};
 use crate::likely::{likely, unlikely};
 
+#[cfg(feature = "futures")]
+use futures_core::Stream;
+#[cfg(feature = "futures")]
+use futures_sink::Sink;
 use may_queue::mpsc::Queue;
 
 // TODO: SyncSender
@@ -72,6 +80,27 @@ impl<T> InnerQueue<T> {
         self.try_recv()
     }
 
+    // async version of recv, register the task waker as the blocker
+    #[cfg(feature = "futures")]
+    pub fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        match self.try_recv() {
+            Err(TryRecvError::Empty) => {}
+            data => return Poll::Ready(data.ok()),
+        }
+
+        // register the waker
+        self.to_wake
+            .store(Arc::new(Blocker::from_waker(cx.waker().clone())));
+        // re-check the queue
+        match self.try_recv() {
+            Err(TryRecvError::Empty) => Poll::Pending,
+            data => {
+                self.to_wake.clear();
+                Poll::Ready(data.ok())
+            }
+        }
+    }
+
     #[inline]
     pub fn try_recv(&self) -> Result<T, TryRecvError> {
         match self.queue.pop() {
@@ -101,6 +130,9 @@ impl<T> InnerQueue<T> {
 
     pub fn drop_port(&self) {
         self.port_dropped.store(true, Ordering::Release);
+        // the waker of a pending stream is left registered
+        #[cfg(feature = "futures")]
+        self.to_wake.clear();
         // clear all the data
         while self.queue.pop().is_some() {}
     }
@@ -133,7 +165,8 @@ pub struct IntoIter<T> {
 }
 
 pub struct Sender<T> {
-    inner: Arc<InnerQueue<T>>,
+    // none if the sender is closed as a sink
+    inner: Option<Arc<InnerQueue<T>>>,
 }
 
 unsafe impl<T: Send> Send for Sender<T> {}
@@ -152,24 +185,39 @@ pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
 
 impl<T> Sender<T> {
     fn new(inner: Arc<InnerQueue<T>>) -> Sender<T> {
-        Sender { inner }
+        Sender { inner: Some(inner) }
     }
 
     pub fn send(&self, t: T) -> Result<(), SendError<T>> {
-        self.inner.send(t).map_err(SendError)
+        match self.inner {
+            Some(ref inner) => inner.send(t).map_err(SendError),
+            None => Err(SendError(t)),
+        }
+    }
+
+    // disconnect the sender, the receiver sees it when all the senders are closed
+    fn close(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            inner.drop_chan();
+        }
     }
 }
 
 impl<T> Clone for Sender<T> {
     fn clone(&self) -> Sender<T> {
-        self.inner.clone_chan();
-        Sender::new(self.inner.clone())
+        match self.inner {
+            Some(ref inner) => {
+                inner.clone_chan();
+                Sender::new(inner.clone())
+            }
+            None => Sender { inner: None },
+        }
     }
 }
 
 impl<T> Drop for Sender<T> {
     fn drop(&mut self) {
-        self.inner.drop_chan();
+        self.close();
     }
 }
 
@@ -290,6 +338,45 @@ impl<T> fmt::Debug for Receiver<T> {
     }
 }
 
+////////////////////////////////////////////////////////////////////////////////
+// Async support
+////////////////////////////////////////////////////////////////////////////////
+
+/// The receiver can be consumed as a stream by async code,
+/// the stream ends when all the senders are dropped
+#[cfg(feature = "futures")]
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.inner.poll_recv(cx)
+    }
+}
+
+/// The sender can be used as a sink by async code,
+/// the channel is unbounded so it's always ready to send
+#[cfg(feature = "futures")]
+impl<T> Sink<T> for Sender<T> {
+    type Error = SendError<T>;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        self.send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().close();
+        Poll::Ready(Ok(()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,9 +2,13 @@
 use std::fmt;
 use std::num::NonZeroUsize;
 use std::panic::{RefUnwindSafe, UnwindSafe};
+#[cfg(feature = "futures")]
+use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::mpsc::{RecvError, SendError, TryRecvError};
 use std::sync::Arc;
+#[cfg(feature = "futures")]
+use std::task::{Context, Poll, Waker};
 use std::thread::Thread;
 
 use super::AtomicOption;
@@ -13,6 +17,10 @@ use crate::likely::{likely, unlikely};
 use crate::scheduler::get_scheduler;
 use crate::yield_now::{yield_now, yield_with};
 
+#[cfg(feature = "futures")]
+use futures_core::Stream;
+#[cfg(feature = "futures")]
+use futures_sink::Sink;
 use may_queue::spsc::Queue;
 
 struct Park<'a, T> {
@@ -69,6 +77,7 @@ impl<T> EventSource for Park<'_, T> {
     }
 }
 
+// the lowest bit marks a thread handle, the second bit marks an async waker
 struct Blocker {
     handle: NonZeroUsize,
 }
@@ -87,6 +96,14 @@ impl Blocker {
         Blocker { handle }
     }
 
+    #[inline]
+    #[cfg(feature = "futures")]
+    fn new_waker(waker: Waker) -> Self {
+        let mut handle = NonZeroUsize::new(Box::into_raw(Box::new(waker)) as usize).unwrap();
+        handle |= 2;
+        Blocker { handle }
+    }
+
     #[inline]
     fn into_coroutine(self) -> CoroutineImpl {
         let co = unsafe { CoroutineImpl::from_raw(self.handle.get() as *mut _) };
@@ -101,8 +118,21 @@ impl Blocker {
         *thread
     }
 
+    #[inline]
+    #[cfg(feature = "futures")]
+    fn into_waker(self) -> Waker {
+        let waker: Box<Waker> = unsafe { Box::from_raw((self.handle.get() & !2) as *mut _) };
+        std::mem::forget(self);
+        *waker
+    }
+
     #[inline]
     fn unpark(self) {
+        #[cfg(feature = "futures")]
+        if (self.handle.get() & 2) != 0 {
+            return self.into_waker().wake();
+        }
+
         if (self.handle.get() & 1) == 0 {
             let co = self.into_coroutine();
             get_scheduler().schedule(co);
@@ -115,6 +145,12 @@ impl Blocker {
 
 impl Drop for Blocker {
     fn drop(&mut self) {
+        #[cfg(feature = "futures")]
+        if (self.handle.get() & 2) != 0 {
+            let _waker: Box<Waker> = unsafe { Box::from_raw((self.handle.get() & !2) as *mut _) };
+            return;
+        }
+
         if (self.handle.get() & 1) == 0 {
             // let co = self.into_coroutine();
             unreachable!()
@@ -186,6 +222,26 @@ impl<T> InnerQueue<T> {
         self.try_recv()
     }
 
+    // async version of recv, register the task waker as the blocker
+    #[cfg(feature = "futures")]
+    pub fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        match self.try_recv() {
+            Err(TryRecvError::Empty) => {}
+            data => return Poll::Ready(data.ok()),
+        }
+
+        // register the waker
+        self.wait_co.store(Blocker::new_waker(cx.waker().clone()));
+        // re-check the queue
+        match self.try_recv() {
+            Err(TryRecvError::Empty) => Poll::Pending,
+            data => {
+                self.wait_co.clear();
+                Poll::Ready(data.ok())
+            }
+        }
+    }
+
     #[inline]
     pub fn try_recv(&self) -> Result<T, TryRecvError> {
         match self.queue.pop() {
@@ -235,7 +291,8 @@ pub struct IntoIter<T> {
 }
 
 pub struct Sender<T> {
-    inner: Arc<InnerQueue<T>>,
+    // none if the sender is closed as a sink
+    inner: Option<Arc<InnerQueue<T>>>,
 }
 
 unsafe impl<T: Send> Send for Sender<T> {}
@@ -254,17 +311,27 @@ pub fn channel<T>() -> (Sender<T>, Receiver<T>) {
 
 impl<T> Sender<T> {
     fn new(inner: Arc<InnerQueue<T>>) -> Sender<T> {
-        Sender { inner }
+        Sender { inner: Some(inner) }
     }
 
     pub fn send(&self, t: T) -> Result<(), SendError<T>> {
-        self.inner.send(t).map_err(SendError)
+        match self.inner {
+            Some(ref inner) => inner.send(t).map_err(SendError),
+            None => Err(SendError(t)),
+        }
+    }
+
+    // disconnect the sender, the receiver sees it when all the senders are closed
+    fn close(&mut self) {
+        if let Some(inner) = self.inner.take() {
+            inner.drop_chan();
+        }
     }
 }
 
 impl<T> Drop for Sender<T> {
     fn drop(&mut self) {
-        self.inner.drop_chan();
+        self.close();
     }
 }
 
@@ -358,6 +425,45 @@ impl<T> fmt::Debug for Receiver<T> {
     }
 }
 
+////////////////////////////////////////////////////////////////////////////////
+// Async support
+////////////////////////////////////////////////////////////////////////////////
+
+/// The receiver can be consumed as a stream by async code,
+/// the stream ends when all the senders are dropped
+#[cfg(feature = "futures")]
+impl<T> Stream for Receiver<T> {
+    type Item = T;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<T>> {
+        self.inner.poll_recv(cx)
+    }
+}
+
+/// The sender can be used as a sink by async code,
+/// the channel is unbounded so it's always ready to send
+#[cfg(feature = "futures")]
+impl<T> Sink<T> for Sender<T> {
+    type Error = SendError<T>;
+
+    fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+        self.send(item)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().close();
+        Poll::Ready(Ok(()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,378 @@
+//! Utilities for tracking time in coroutine context
+//!
+//! all the blocking APIs work in both coroutine and thread context
+//!
+use std::error::Error;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use parking_lot::Mutex;
+
+use crate::sync::Blocker;
+
+pub use crate::sleep::sleep;
+
+/// block the current coroutine until the deadline is reached
+///
+/// return immediately if the deadline is already passed
+pub fn sleep_until(deadline: Instant) {
+    let now = Instant::now();
+    if deadline > now {
+        sleep(deadline - now);
+    }
+}
+
+// the deadline after the duration from the start, the duration could be
+// too large like `Duration::MAX`, then it's about 30 years later instead
+fn deadline_after(start: Instant, dur: Duration) -> Instant {
+    start
+        .checked_add(dur)
+        .or_else(|| start.checked_add(Duration::from_secs(86400 * 365 * 30)))
+        .unwrap_or(start)
+}
+
+/// Defines the behavior of an [`Interval`] when it misses a tick
+///
+/// a tick is missed when the interval is not polled in time, e.g. the
+/// work between two `tick` calls takes longer than the period.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum MissedTickBehavior {
+    /// fire the missed ticks as fast as possible until catch up
+    #[default]
+    Burst,
+    /// restart the interval from the time of the missed tick
+    Delay,
+    /// skip the missed ticks and fire on the next multiple of period
+    Skip,
+}
+
+/// A ticker that yields at a fixed period
+///
+/// the ticks are scheduled based on the start time, so the interval
+/// doesn't drift over time.
+///
+/// # Examples
+///
+/// ```
+/// use may::time;
+/// use std::time::Duration;
+///
+/// let mut interval = time::interval(Duration::from_millis(10));
+/// // the first tick completes immediately
+/// interval.tick();
+/// interval.tick();
+/// ```
+#[derive(Debug)]
+pub struct Interval {
+    // the next tick deadline
+    next: Instant,
+    period: Duration,
+    missed_tick_behavior: MissedTickBehavior,
+}
+
+/// create an [`Interval`] that the first tick completes immediately
+///
+/// panic if the period is zero
+pub fn interval(period: Duration) -> Interval {
+    interval_at(Instant::now(), period)
+}
+
+/// create an [`Interval`] that the first tick completes at `start`
+///
+/// panic if the period is zero
+pub fn interval_at(start: Instant, period: Duration) -> Interval {
+    assert!(!period.is_zero(), "interval period must be non-zero");
+    Interval {
+        next: start,
+        period,
+        missed_tick_behavior: MissedTickBehavior::default(),
+    }
+}
+
+impl Interval {
+    /// wait until the next tick and return the scheduled time of it
+    pub fn tick(&mut self) -> Instant {
+        sleep_until(self.next);
+        let scheduled = self.next;
+        let now = Instant::now();
+        let next = deadline_after(scheduled, self.period);
+        self.next = if now < next {
+            next
+        } else {
+            match self.missed_tick_behavior {
+                MissedTickBehavior::Burst => next,
+                MissedTickBehavior::Delay => deadline_after(now, self.period),
+                MissedTickBehavior::Skip => {
+                    let period = self.period.as_nanos();
+                    let missed = (now - scheduled).as_nanos() / period;
+                    let nanos = u64::try_from((missed + 1) * period).unwrap_or(u64::MAX);
+                    deadline_after(scheduled, Duration::from_nanos(nanos))
+                }
+            }
+        };
+        scheduled
+    }
+
+    /// reset the interval that the next tick completes after one period
+    pub fn reset(&mut self) {
+        self.next = deadline_after(Instant::now(), self.period);
+    }
+
+    /// return the period of the interval
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// return the missed tick behavior
+    pub fn missed_tick_behavior(&self) -> MissedTickBehavior {
+        self.missed_tick_behavior
+    }
+
+    /// set the missed tick behavior
+    pub fn set_missed_tick_behavior(&mut self, behavior: MissedTickBehavior) {
+        self.missed_tick_behavior = behavior;
+    }
+}
+
+/// The error returned by [`Timer::wait`] when the timer is canceled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Canceled;
+
+impl fmt::Display for Canceled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        "timer canceled".fmt(f)
+    }
+}
+
+impl Error for Canceled {}
+
+struct TimerState {
+    // None means the timer is canceled
+    deadline: Option<Instant>,
+    // the waiters that need to recheck the deadline
+    waiters: Vec<Arc<Blocker>>,
+}
+
+impl TimerState {
+    fn wake_all(&mut self) {
+        for w in self.waiters.drain(..) {
+            w.unpark();
+        }
+    }
+}
+
+// remove the waiter when leave the wait, even when canceled
+struct WaitGuard<'a> {
+    state: &'a Mutex<TimerState>,
+    blocker: Arc<Blocker>,
+}
+
+impl Drop for WaitGuard<'_> {
+    fn drop(&mut self) {
+        let mut state = self.state.lock();
+        state.waiters.retain(|w| !Arc::ptr_eq(w, &self.blocker));
+    }
+}
+
+/// A one-shot timer that can be reset or canceled
+///
+/// the timer can be cloned and shared, any thread or coroutine can wait
+/// on it, and it works in `select!` like other blocking APIs.
+///
+/// # Examples
+///
+/// ```
+/// # #[macro_use] extern crate may;
+/// # fn main() {
+/// use may::sync::mpsc;
+/// use may::time::Timer;
+/// use std::time::Duration;
+///
+/// let (_tx, rx) = mpsc::channel::<u32>();
+/// let timer = Timer::new(Duration::from_millis(10));
+/// let id = select!(
+///     _ = rx.recv() => println!("got a message"),
+///     _ = timer.wait() => println!("timeout")
+/// );
+/// assert_eq!(id, 1);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Timer {
+    state: Arc<Mutex<TimerState>>,
+}
+
+impl Timer {
+    /// create a timer that expires after the duration
+    pub fn new(dur: Duration) -> Self {
+        Timer::at(deadline_after(Instant::now(), dur))
+    }
+
+    /// create a timer that expires at the deadline
+    pub fn at(deadline: Instant) -> Self {
+        Timer {
+            state: Arc::new(Mutex::new(TimerState {
+                deadline: Some(deadline),
+                waiters: Vec::new(),
+            })),
+        }
+    }
+
+    /// block until the timer expires
+    ///
+    /// return `Err(Canceled)` if the timer is canceled before expired
+    pub fn wait(&self) -> Result<(), Canceled> {
+        let guard = WaitGuard {
+            state: &self.state,
+            blocker: Blocker::current(),
+        };
+        loop {
+            let dur = {
+                let mut state = self.state.lock();
+                let deadline = state.deadline.ok_or(Canceled)?;
+                let now = Instant::now();
+                if deadline <= now {
+                    return Ok(());
+                }
+                if !state.waiters.iter().any(|w| Arc::ptr_eq(w, &guard.blocker)) {
+                    state.waiters.push(guard.blocker.clone());
+                }
+                deadline - now
+            };
+            // wake up either by timeout or by reset/cancel
+            guard.blocker.park(Some(dur)).ok();
+        }
+    }
+
+    /// reset the timer to expire after the duration
+    ///
+    /// a canceled or expired timer would be re-armed
+    pub fn reset(&self, dur: Duration) {
+        self.reset_at(deadline_after(Instant::now(), dur));
+    }
+
+    /// reset the timer to expire at the deadline
+    pub fn reset_at(&self, deadline: Instant) {
+        let mut state = self.state.lock();
+        state.deadline = Some(deadline);
+        state.wake_all();
+    }
+
+    /// cancel the timer, all the waiters return `Err(Canceled)`
+    pub fn cancel(&self) {
+        let mut state = self.state.lock();
+        state.deadline = None;
+        state.wake_all();
+    }
+
+    /// return the deadline of the timer, `None` if canceled
+    pub fn deadline(&self) -> Option<Instant> {
+        self.state.lock().deadline
+    }
+
+    /// return true if the timer is expired
+    pub fn is_expired(&self) -> bool {
+        self.deadline().is_some_and(|t| t <= Instant::now())
+    }
+}
+
+impl fmt::Debug for Timer {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Timer")
+            .field("deadline", &self.deadline())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn interval_no_drift() {
+        let start = Instant::now();
+        let mut interval = interval_at(start, Duration::from_millis(10));
+        for i in 0..5 {
+            let t = interval.tick();
+            assert_eq!(t, start + Duration::from_millis(10) * i);
+        }
+        assert!(start.elapsed() >= Duration::from_millis(40));
+    }
+
+    #[test]
+    fn interval_missed_tick() {
+        let period = Duration::from_millis(10);
+        let start = Instant::now();
+        let mut burst = interval_at(start, period);
+        let mut delay = interval_at(start, period);
+        delay.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut skip = interval_at(start, period);
+        skip.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+        for i in [&mut burst, &mut delay, &mut skip] {
+            i.tick();
+        }
+        std::thread::sleep(Duration::from_millis(35));
+
+        assert_eq!(burst.tick(), start + period);
+        assert_eq!(burst.tick(), start + period * 2);
+
+        assert_eq!(delay.tick(), start + period);
+        assert!(delay.tick() >= start + Duration::from_millis(45));
+
+        // the ticks are still aligned to the start time
+        assert_eq!(skip.tick(), start + period);
+        let t = skip.tick();
+        assert!(t >= start + period * 4);
+        assert_eq!((t - start).as_nanos() % period.as_nanos(), 0);
+    }
+
+    #[test]
+    fn timer_reset_cancel() {
+        let timer = Timer::new(Duration::from_millis(10));
+        let now = Instant::now();
+        assert_eq!(timer.wait(), Ok(()));
+        assert!(now.elapsed() >= Duration::from_millis(9));
+        assert!(timer.is_expired());
+
+        // reset in another coroutine while waiting
+        timer.reset(Duration::from_secs(10));
+        let t = timer.clone();
+        go!(move || {
+            crate::coroutine::sleep(Duration::from_millis(10));
+            t.reset(Duration::from_millis(10));
+        });
+        let now = Instant::now();
+        let h = {
+            let t = timer.clone();
+            go!(move || t.wait())
+        };
+        assert_eq!(h.join().unwrap(), Ok(()));
+        assert!(now.elapsed() < Duration::from_secs(1));
+
+        // cancel wakes up all the waiters
+        timer.reset(Duration::from_secs(10));
+        let t = timer.clone();
+        let h = go!(move || t.wait());
+        std::thread::sleep(Duration::from_millis(10));
+        timer.cancel();
+        assert_eq!(h.join().unwrap(), Err(Canceled));
+        assert_eq!(timer.wait(), Err(Canceled));
+    }
+
+    #[test]
+    fn huge_duration() {
+        let now = Instant::now();
+        let timer = Timer::new(Duration::MAX);
+        assert!(timer.deadline().unwrap() > now + Duration::from_secs(86400 * 365));
+        assert!(!timer.is_expired());
+        timer.reset(Duration::MAX);
+        assert!(!timer.is_expired());
+
+        let mut interval = interval_at(now, Duration::MAX);
+        assert_eq!(interval.tick(), now);
+        interval.reset();
+        assert!(interval.next > now + Duration::from_secs(86400 * 365));
+    }
+}
@@ -1,19 +1,32 @@
-use std::cmp;
-use std::collections::{BinaryHeap, HashMap};
-use std::mem;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::sync::Arc;
-use std::thread;
+use std::cell::UnsafeCell;
+use std::ptr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
 use std::time::{Duration, Instant};
 
-use may_queue::mpsc::Queue;
-use may_queue::mpsc_list_v1::Entry;
-use may_queue::mpsc_list_v1::Queue as TimeoutQueue;
-use parking_lot::{Mutex, RwLock};
-
-use crate::sync::AtomicOption;
-
-const HASH_CAP: usize = 1024;
+use parking_lot::Mutex;
+
+use crate::config::config;
+
+// the resolution of the timer wheel in ns
+const TICK_NS: u64 = 1_000;
+// coarse timers are aligned to milliseconds
+const COARSE_NS: u64 = 1_000_000;
+// each level of the wheel has 64 slots
+const SLOT_BITS: usize = 6;
+const SLOTS: usize = 1 << SLOT_BITS;
+const LEVELS: usize = 8;
+// the max ticks that the wheel can hold without wrapping around
+// it's about 8.9 years with the 1us tick
+const MAX_TICKS: u64 = 1 << (SLOT_BITS * LEVELS);
+// the list index of the timers that expired when added
+const PENDING: usize = LEVELS * SLOTS;
+
+// if the expire tick is not aligned to milliseconds
+#[inline]
+fn is_fine(when: u64) -> bool {
+    !when.is_multiple_of(COARSE_NS / TICK_NS)
+}
 
 #[inline]
 fn get_instant() -> &'static Instant {
@@ -28,287 +41,380 @@ pub fn now() -> u64 {
     get_instant().elapsed().as_nanos() as u64
 }
 
-// timeout event data
-pub struct TimeoutData<T> {
-    time: u64,   // the wall clock in ns that the timer expires
-    pub data: T, // the data associate with the timeout event
+// the position of a timer node in the wheel
+struct Link<T> {
+    prev: *const TimerNode<T>,
+    next: *const TimerNode<T>,
+    // the tick that the timer expires
+    when: u64,
+    // the list index in the wheel
+    idx: usize,
 }
 
-// timeout handler which can be removed/cancelled
-pub type TimeoutHandle<T> = Entry<TimeoutData<T>>;
-
-struct TimeoutQueueWrapper<T> {
-    inner: TimeoutQueue<TimeoutData<T>>,
-    in_use: AtomicUsize,
+// timer node that shared by the wheel and the timeout handle
+struct TimerNode<T> {
+    // the wheel that the node belongs to, it may be dropped first
+    wheel: Weak<Mutex<Wheel<T>>>,
+    // link and data are protected by the wheel lock
+    link: UnsafeCell<Link<T>>,
+    data: UnsafeCell<Option<T>>,
+    // if the node is still in the wheel
+    linked: AtomicBool,
 }
 
-impl<T> TimeoutQueueWrapper<T> {
-    fn new() -> Self {
-        TimeoutQueueWrapper {
-            inner: TimeoutQueue::new(),
-            in_use: AtomicUsize::new(0),
-        }
-    }
-}
-
-type IntervalList<T> = Arc<TimeoutQueueWrapper<T>>;
+/// timeout handler which can be removed/cancelled
+///
+/// the timer is treated as expired after the wheel is dropped
+pub struct TimeoutHandle<T>(Arc<TimerNode<T>>);
 
-// this is the data type that used by the binary heap to get the latest timer
-struct IntervalEntry<T> {
-    time: u64,             // the head timeout value in the list, should be latest
-    list: IntervalList<T>, // point to the interval list
-    interval: u64,
-}
+unsafe impl<T: Send> Send for TimeoutHandle<T> {}
+unsafe impl<T: Send> Sync for TimeoutHandle<T> {}
 
-impl<T> IntervalEntry<T> {
-    // trigger the timeout event with the supplying function
-    // return next expire time
-    pub fn pop_timeout<F>(&self, now: u64, f: &F) -> Option<u64>
+impl<T> TimeoutHandle<T> {
+    /// modify the associated data if the timer is not expired
+    #[inline]
+    #[cfg_attr(not(feature = "io_timeout"), allow(dead_code))]
+    pub fn with_mut_data<F>(&self, f: F)
     where
-        F: Fn(T),
+        F: FnOnce(&mut T),
     {
-        let p = |v: &TimeoutData<T>| v.time <= now;
-        while let Some(timeout) = self.list.inner.pop_if(&p) {
-            f(timeout.data);
+        let Some(wheel) = self.0.wheel.upgrade() else {
+            return;
+        };
+        let _wheel = wheel.lock();
+        if let Some(data) = unsafe { &mut *self.0.data.get() } {
+            f(data);
         }
-        unsafe { self.list.inner.peek() }.map(|t| t.time)
     }
-}
 
-impl<T> PartialEq for IntervalEntry<T> {
-    fn eq(&self, other: &Self) -> bool {
-        self.time == other.time
+    /// judge if the timer is still in the wheel
+    #[inline]
+    pub fn is_link(&self) -> bool {
+        self.0.linked.load(Ordering::Acquire)
     }
-}
 
-impl<T> Eq for IntervalEntry<T> {}
+    /// remove the timer from the wheel and return the associated data
+    ///
+    /// return `None` if the timer is already expired
+    pub fn remove(self) -> Option<T> {
+        let wheel = self.0.wheel.upgrade()?;
+        let mut wheel = wheel.lock();
+        if !self.is_link() {
+            return None;
+        }
+        drop(wheel.unlink(Arc::as_ptr(&self.0)));
+        unsafe { &mut *self.0.data.get() }.take()
+    }
 
-impl<T> PartialOrd for IntervalEntry<T> {
-    fn partial_cmp(&self, other: &Self) -> Option<cmp::Ordering> {
-        Some(self.cmp(other))
+    #[inline]
+    pub fn into_ptr(self) -> *mut Self {
+        Arc::into_raw(self.0) as *mut Self
     }
-}
 
-impl<T> cmp::Ord for IntervalEntry<T> {
-    fn cmp(&self, other: &Self) -> cmp::Ordering {
-        other.time.cmp(&self.time)
+    /// # Safety
+    ///
+    /// Must use the ptr that from `TimeoutHandle::into_ptr`
+    #[inline]
+    pub unsafe fn from_ptr(ptr: *mut Self) -> Self {
+        TimeoutHandle(Arc::from_raw(ptr as *const TimerNode<T>))
     }
 }
 
-// the timeout list data structure
-pub struct TimeOutList<T> {
-    // interval based hash map, protected by rw lock
-    interval_map: RwLock<HashMap<u64, IntervalList<T>>>,
-    // a priority queue, each element is the head of a mpsc queue
-    timer_bh: Mutex<BinaryHeap<IntervalEntry<T>>>,
+// the hashed hierarchical timing wheel
+//
+// level n has 64 slots and each slot covers 64^n ticks, a timer is put in
+// the lowest level that its expire tick shares all the higher bits with
+// the elapsed tick. when a higher level slot is due, the timers in it are
+// cascaded to the lower levels.
+struct Wheel<T> {
+    // the tick that the wheel has processed
+    elapsed: u64,
+    // the earliest tick that the wheel need to be processed
+    next_wake: u64,
+    // bitmap of the non-empty slots for each level
+    occupied: [u64; LEVELS],
+    // list heads of all the slots, plus the pending list
+    heads: Vec<*const TimerNode<T>>,
+    // the number of timers that are not aligned to milliseconds
+    fine: usize,
 }
 
-impl<T> TimeOutList<T> {
-    pub fn new() -> Self {
-        TimeOutList {
-            interval_map: RwLock::new(HashMap::with_capacity(HASH_CAP)),
-            timer_bh: Mutex::new(BinaryHeap::new()),
+unsafe impl<T: Send> Send for Wheel<T> {}
+
+impl<T> Wheel<T> {
+    fn new() -> Self {
+        Wheel {
+            elapsed: now() / TICK_NS,
+            next_wake: u64::MAX,
+            occupied: [0; LEVELS],
+            heads: vec![ptr::null(); PENDING + 1],
+            fine: 0,
         }
     }
 
-    fn install_timer_bh(&self, entry: IntervalEntry<T>) {
-        if entry.list.in_use.fetch_add(1, Ordering::AcqRel) == 0 {
-            self.timer_bh.lock().push(entry);
+    // the level that the tick should be put in
+    #[inline]
+    fn level_for(&self, when: u64) -> usize {
+        let mut masked = (self.elapsed ^ when) | (SLOTS as u64 - 1);
+        if masked >= MAX_TICKS {
+            masked = MAX_TICKS - 1;
         }
+        (63 - masked.leading_zeros() as usize) / SLOT_BITS
     }
 
-    // add a timeout event to the list
-    // this can be called in any thread
-    // return true if we need to recall next expire
-    pub fn add_timer(&self, dur: Duration, data: T) -> (TimeoutHandle<T>, bool) {
-        let interval = dur.as_nanos() as u64;
-        let time = now() + interval;
-        //println!("add timer = {:?}", time);
-
-        let timeout = TimeoutData { time, data };
-
-        let interval_list = {
-            // use the read lock protect
-            let interval_map_r = self.interval_map.read();
-            (*interval_map_r).get(&interval).cloned()
-            // drop the read lock here
+    // link the node into the proper slot, the wheel takes one ref of the node
+    fn push(&mut self, node: Arc<TimerNode<T>>) {
+        let link = unsafe { &mut *node.link.get() };
+        let idx = if link.when <= self.elapsed {
+            PENDING
+        } else {
+            let level = self.level_for(link.when);
+            let slot = (link.when >> (level * SLOT_BITS)) as usize % SLOTS;
+            self.occupied[level] |= 1 << slot;
+            level * SLOTS + slot
         };
 
-        if let Some(interval_list) = interval_list {
-            let (handle, is_head) = interval_list.inner.push(timeout);
-            if is_head {
-                // install the interval list to the binary heap
-                self.install_timer_bh(IntervalEntry {
-                    time,
-                    interval,
-                    list: interval_list,
-                });
-            }
-            return (handle, is_head);
+        node.linked.store(true, Ordering::Release);
+        let ptr = Arc::into_raw(node);
+        let head = self.heads[idx];
+        if !head.is_null() {
+            unsafe { (*(*head).link.get()).prev = ptr };
         }
+        link.prev = ptr::null();
+        link.next = head;
+        link.idx = idx;
+        self.heads[idx] = ptr;
+    }
 
-        // if the interval list is not there, get the write locker to install the list
-        // use the write lock protect
-        let mut interval_map_w = self.interval_map.write();
-        // recheck the interval list in case other thread may install it
-        if let Some(interval_list) = (*interval_map_w).get(&interval) {
-            let (handle, is_head) = interval_list.inner.push(timeout);
-            if is_head {
-                // this rarely happens
-                self.install_timer_bh(IntervalEntry {
-                    time,
-                    interval,
-                    list: interval_list.clone(),
-                });
-            }
-            return (handle, is_head);
+    // unlink the node from the wheel and return the wheel ref
+    fn unlink(&mut self, ptr: *const TimerNode<T>) -> Arc<TimerNode<T>> {
+        let node = unsafe { Arc::from_raw(ptr) };
+        let link = unsafe { &mut *node.link.get() };
+        if link.prev.is_null() {
+            self.heads[link.idx] = link.next;
+        } else {
+            unsafe { (*(*link.prev).link.get()).next = link.next };
+        }
+        if !link.next.is_null() {
+            unsafe { (*(*link.next).link.get()).prev = link.prev };
         }
+        if self.heads[link.idx].is_null() && link.idx != PENDING {
+            self.occupied[link.idx / SLOTS] &= !(1 << (link.idx % SLOTS));
+        }
+        if is_fine(link.when) {
+            self.fine -= 1;
+        }
+        node.linked.store(false, Ordering::Release);
+        node
+    }
 
-        let interval_list = Arc::new(TimeoutQueueWrapper::<T>::new());
-        let ret = interval_list.inner.push(timeout).0;
-        (*interval_map_w).insert(interval, interval_list.clone());
-        // drop the write lock here
-        mem::drop(interval_map_w);
-
-        // install the new interval list to the binary heap
-        self.install_timer_bh(IntervalEntry {
-            time,
-            interval,
-            list: interval_list,
-        });
+    // detach the whole list, return the head
+    #[inline]
+    fn take_list(&mut self, idx: usize) -> *const TimerNode<T> {
+        if idx != PENDING {
+            self.occupied[idx / SLOTS] &= !(1 << (idx % SLOTS));
+        }
+        std::mem::replace(&mut self.heads[idx], ptr::null())
+    }
+
+    // find out the next due slot, return the list index and its start tick
+    fn next_expiration(&self) -> Option<(usize, u64)> {
+        for level in 0..LEVELS {
+            let occupied = self.occupied[level];
+            if occupied == 0 {
+                continue;
+            }
 
-        (ret, true)
+            let slot_range = 1u64 << (level * SLOT_BITS);
+            let level_range = slot_range << SLOT_BITS;
+            let now_slot = (self.elapsed / slot_range) as usize % SLOTS;
+            let zeros = occupied.rotate_right(now_slot as u32).trailing_zeros() as usize;
+            let slot = (zeros + now_slot) % SLOTS;
+
+            let level_start = self.elapsed & !(level_range - 1);
+            let mut deadline = level_start + slot as u64 * slot_range;
+            if deadline <= self.elapsed {
+                // only happens for the top level that wraps around
+                deadline += level_range;
+            }
+            return Some((level * SLOTS + slot, deadline));
+        }
+        None
     }
 
-    // schedule in the timer thread
-    // this will remove all the expired timeout event
-    // and call the supplied function with registered data
-    // return the time in ns for the next expiration
-    pub fn schedule_timer<F: Fn(T)>(&self, now: u64, f: &F) -> Option<u64> {
+    // move out all the timers that expired before the tick
+    fn poll(&mut self, tick: u64, expired: &mut Vec<T>) {
+        let mut head = self.take_list(PENDING);
         loop {
-            // first peek the BH to see if there is any timeout event
-            let mut entry = {
-                let mut timer_bh = self.timer_bh.lock();
-                let top_entry = timer_bh.peek()?;
-
-                // the latest timeout event not happened yet
-                if top_entry.time > now {
-                    return Some(top_entry.time - now);
-                }
-
-                // find out one entry
-                let entry = timer_bh.pop().unwrap();
-                entry.list.in_use.store(0, Ordering::Release);
-                entry
-            };
-
-            // consume all the timeout event
-            // the binary heap can be modified here
-            // during running the timeout handler
-            match entry.pop_timeout(now, f) {
-                Some(time) => {
-                    if entry.list.in_use.fetch_add(1, Ordering::AcqRel) == 0 {
-                        // re-push the entry
-                        entry.time = time;
-                        self.timer_bh.lock().push(entry);
+            while !head.is_null() {
+                let node = unsafe { Arc::from_raw(head) };
+                let link = unsafe { &*node.link.get() };
+                head = link.next;
+                if link.when <= self.elapsed {
+                    if is_fine(link.when) {
+                        self.fine -= 1;
                     }
+                    node.linked.store(false, Ordering::Release);
+                    expired.extend(unsafe { &mut *node.data.get() }.take());
+                } else {
+                    // cascade to the lower level
+                    self.push(node);
                 }
+            }
 
-                None => {
-                    // if the interval list is empty, need to delete it
-                    let mut interval_map_w = self.interval_map.write();
-                    // recheck if the interval list is empty, other thread may append data to it
-                    if entry.list.inner.is_empty() {
-                        // if the len of the hash map is big enough just leave the queue there
-                        if (*interval_map_w).len() > HASH_CAP {
-                            // the list is really empty now, we can safely remove it
-                            (*interval_map_w).remove(&entry.interval);
-                        }
-                    } else if entry.list.in_use.fetch_add(1, Ordering::AcqRel) == 0 {
-                        // release the w lock first, we don't need it any more
-                        mem::drop(interval_map_w);
-                        // the list is push some data by other thread
-                        entry.time = unsafe { entry.list.inner.peek() }.unwrap().time;
-                        self.timer_bh.lock().push(entry);
-                    }
+            match self.next_expiration() {
+                Some((idx, deadline)) if deadline <= tick => {
+                    self.elapsed = deadline;
+                    head = self.take_list(idx);
                 }
+                _ => break,
+            }
+        }
+
+        self.elapsed = self.elapsed.max(tick);
+        self.next_wake = self.next_expiration().map_or(u64::MAX, |(_, t)| t);
+    }
+}
+
+impl<T> Drop for Wheel<T> {
+    fn drop(&mut self) {
+        for idx in 0..=PENDING {
+            let mut head = self.take_list(idx);
+            while !head.is_null() {
+                let node = unsafe { Arc::from_raw(head) };
+                head = unsafe { &*node.link.get() }.next;
+                node.linked.store(false, Ordering::Release);
             }
         }
     }
 }
 
-pub struct TimerThread<T> {
-    timer_list: TimeOutList<T>,
-    // collect the remove request
-    remove_list: Queue<TimeoutHandle<T>>,
-    // the timer thread wakeup handler
-    wakeup: AtomicOption<Arc<thread::Thread>>,
+/// the timer wheel that can be shared across threads
+///
+/// insert and cancel are O(1), and expired timers are processed in batch
+pub struct TimerWheel<T> {
+    // the handles refer to it weakly
+    wheel: Arc<Mutex<Wheel<T>>>,
+    // if the sub-millisecond timers keep their precision
+    high_res: bool,
 }
 
-impl<T> TimerThread<T> {
+impl<T> TimerWheel<T> {
     pub fn new() -> Self {
-        TimerThread {
-            timer_list: TimeOutList::new(),
-            remove_list: Queue::new(),
-            wakeup: AtomicOption::none(),
-        }
+        // only the epoll event loop could wait for the sub-millisecond timeout
+        let linux = cfg!(any(target_os = "linux", target_os = "android"));
+        Self::with_high_res(linux && config().get_high_res_timer())
     }
 
-    pub fn add_timer(&self, dur: Duration, data: T) -> TimeoutHandle<T> {
-        let (h, is_recal) = self.timer_list.add_timer(dur, data);
-        // wake up the timer thread if it's a new queue
-        if is_recal {
-            if let Some(t) = self.wakeup.take() {
-                t.unpark();
-            }
+    pub fn with_high_res(high_res: bool) -> Self {
+        TimerWheel {
+            wheel: Arc::new(Mutex::new(Wheel::new())),
+            high_res,
         }
-        h
     }
 
-    pub fn del_timer(&self, handle: TimeoutHandle<T>) {
-        self.remove_list.push(handle);
-        if let Some(t) = self.wakeup.take() {
-            t.unpark();
+    // add a timeout event to the wheel
+    // this can be called in any thread
+    // return true if we need to recall next expire
+    pub fn add_timer(&self, dur: Duration, data: T) -> (TimeoutHandle<T>, bool) {
+        self.add_timer_with_slack(dur, Duration::ZERO, data)
+    }
+
+    // add a timeout event that may expire up to `slack` late
+    // the deadline is rounded up to the slack grid, so that the timers
+    // added around the same time are coalesced into one expiry
+    pub fn add_timer_with_slack(
+        &self,
+        dur: Duration,
+        slack: Duration,
+        data: T,
+    ) -> (TimeoutHandle<T>, bool) {
+        // only the timers with sub-millisecond part need the high resolution
+        // others are aligned to milliseconds so that they expire together
+        let align = if self.high_res && !dur.subsec_nanos().is_multiple_of(1_000_000) {
+            TICK_NS
+        } else {
+            COARSE_NS
+        };
+        // round up, never expire before the duration
+        let mut deadline = now() + dur.as_nanos() as u64;
+        let slack = slack.as_nanos() as u64;
+        if slack > 0 {
+            deadline = deadline.next_multiple_of(slack);
         }
+        let deadline = deadline.next_multiple_of(align);
+        let when = deadline.div_ceil(TICK_NS);
+        let node = Arc::new(TimerNode {
+            wheel: Arc::downgrade(&self.wheel),
+            link: UnsafeCell::new(Link {
+                prev: ptr::null(),
+                next: ptr::null(),
+                when,
+                idx: 0,
+            }),
+            data: UnsafeCell::new(Some(data)),
+            linked: AtomicBool::new(false),
+        });
+
+        let mut wheel = self.wheel.lock();
+        wheel.push(node.clone());
+        if is_fine(when) {
+            wheel.fine += 1;
+        }
+        let is_recal = when < wheel.next_wake;
+        if is_recal {
+            wheel.next_wake = when;
+        }
+        (TimeoutHandle(node), is_recal)
     }
 
-    // the timer thread function
-    pub fn run<F: Fn(T)>(&self, f: &F) {
-        let current_thread = Arc::new(thread::current());
-        loop {
-            while let Some(h) = self.remove_list.pop() {
-                h.remove();
-            }
-            // we must register the thread handle first
-            // or there will be no signal to wakeup the timer thread
-            self.wakeup.store(current_thread.clone());
+    // this will remove all the expired timeout event
+    // and call the supplied function with registered data
+    // return the time in ns for the next expiration
+    pub fn schedule_timer<F: Fn(T)>(&self, now: u64, f: &F) -> Option<u64> {
+        let mut expired = Vec::new();
+        self.wheel.lock().poll(now / TICK_NS, &mut expired);
+        // run the handlers without the lock, they may add new timers
+        for data in expired {
+            f(data);
+        }
 
-            if !self.remove_list.is_empty() {
-                if let Some(t) = self.wakeup.take() {
-                    t.unpark();
-                }
-            }
+        self.next_expire(now)
+    }
 
-            match self.timer_list.schedule_timer(now(), f) {
-                Some(time) => thread::park_timeout(Duration::from_nanos(time)),
-                None => thread::park(),
-            }
+    // return the time in ns for the next expiration
+    pub fn next_expire(&self, now: u64) -> Option<u64> {
+        match self.wheel.lock().next_wake {
+            u64::MAX => None,
+            next => Some((next * TICK_NS).saturating_sub(now)),
         }
     }
+
+    // return true if any timer is not aligned to milliseconds
+    pub fn has_fine_timers(&self) -> bool {
+        self.wheel.lock().fine > 0
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::sync::mpsc::channel;
+    use std::thread;
 
     #[test]
     fn test_timeout_list() {
-        let timer = Arc::new(TimerThread::<usize>::new());
+        let timer = Arc::new(TimerWheel::<usize>::new());
         let t = timer.clone();
         let f = |data: usize| {
             println!("timeout data:{data:?}");
         };
-        thread::spawn(move || t.run(&f));
+        thread::spawn(move || loop {
+            match t.schedule_timer(now(), &f) {
+                Some(time) => thread::park_timeout(Duration::from_nanos(time)),
+                None => thread::park_timeout(Duration::from_millis(10)),
+            }
+        });
         let t1 = timer.clone();
         thread::spawn(move || {
             t1.add_timer(Duration::from_millis(1000), 50);
@@ -324,4 +430,140 @@ mod tests {
 
         thread::sleep(Duration::from_millis(1500));
     }
+
+    #[test]
+    fn test_timer_wheel() {
+        let wheel = TimerWheel::<u64>::new();
+        let start = now();
+        // cover multiple levels
+        for ms in [0, 1, 3, 63, 64, 100, 4095, 4096] {
+            wheel.add_timer(Duration::from_millis(ms), ms);
+        }
+        let removed = wheel.add_timer(Duration::from_millis(50), 50).0;
+        assert_eq!(removed.remove(), Some(50));
+
+        let (tx, rx) = channel();
+        let f = |ms: u64| {
+            // never expire earlier
+            assert!(now() - start >= ms * 1_000_000);
+            tx.send(ms).unwrap();
+        };
+        while let Some(next) = wheel.schedule_timer(now(), &f) {
+            thread::sleep(Duration::from_nanos(next));
+        }
+        drop(tx);
+        let fired: Vec<_> = rx.iter().collect();
+        assert_eq!(fired, [0, 1, 3, 63, 64, 100, 4095, 4096]);
+    }
+
+    #[test]
+    fn test_timer_wheel_cancel() {
+        let wheel = TimerWheel::<usize>::new();
+        let handles: Vec<_> = (0..100)
+            .map(|i| wheel.add_timer(Duration::from_millis(i as u64 % 10), i).0)
+            .collect();
+        for (i, h) in handles.into_iter().enumerate() {
+            if i % 2 == 0 {
+                assert_eq!(h.remove(), Some(i));
+            }
+        }
+
+        thread::sleep(Duration::from_millis(10));
+        let (tx, rx) = channel();
+        assert_eq!(wheel.schedule_timer(now(), &|i| tx.send(i).unwrap()), None);
+        drop(tx);
+        assert!(rx.iter().all(|i| i % 2 == 1));
+    }
+
+    #[test]
+    fn test_timer_wheel_dropped() {
+        let wheel = TimerWheel::<usize>::new();
+        let (h, _) = wheel.add_timer(Duration::from_secs(1), 1);
+        drop(wheel);
+        // the handle outlives the wheel
+        assert!(!h.is_link());
+        h.with_mut_data(|_| unreachable!());
+        assert_eq!(h.remove(), None);
+    }
+
+    #[test]
+    fn test_timer_wheel_long() {
+        let wheel = TimerWheel::<u32>::with_high_res(false);
+        let start = now();
+        // beyond the range of 6 levels, 2^36 ticks
+        let dur = Duration::from_secs(30 * 3600);
+        wheel.add_timer(dur, 1);
+        assert_ne!(wheel.wheel.lock().occupied[6], 0);
+
+        let end = start + dur.as_nanos() as u64;
+        let fired = std::cell::Cell::new(false);
+        let f = |_| fired.set(true);
+        assert!(wheel.schedule_timer(end - 1_000_000, &f).is_some());
+        assert!(!fired.get());
+        assert!(wheel.schedule_timer(end + 2_000_000, &f).is_none());
+        assert!(fired.get());
+    }
+
+    #[test]
+    fn test_timer_wheel_high_res() {
+        let dur = Duration::from_micros(100);
+        let wheel = TimerWheel::<u32>::with_high_res(true);
+        wheel.add_timer(dur, 1);
+        let next = wheel.next_expire(now()).unwrap();
+        assert!(next <= 100_000);
+
+        // coarse timers are aligned to milliseconds
+        let wheel = TimerWheel::<u32>::with_high_res(false);
+        wheel.add_timer(dur, 1);
+        let now = now();
+        let next = wheel.next_expire(now).unwrap();
+        assert!(next >= 100_000);
+        assert_eq!((now + next) % 1_000_000, 0);
+
+        let wheel = TimerWheel::<u32>::with_high_res(true);
+        wheel.add_timer(Duration::from_millis(2), 1);
+        let now = self::now();
+        assert_eq!((now + wheel.next_expire(now).unwrap()) % 1_000_000, 0);
+        assert!(!wheel.has_fine_timers());
+
+        // the fine timers are tracked until removed or expired
+        let (h, _) = wheel.add_timer(dur, 2);
+        assert!(wheel.has_fine_timers());
+        assert_eq!(h.remove(), Some(2));
+        assert!(!wheel.has_fine_timers());
+        wheel.add_timer(dur, 3);
+        assert!(wheel.has_fine_timers());
+        wheel.schedule_timer(now + 1_000_000, &|v| assert_eq!(v, 3));
+        assert!(!wheel.has_fine_timers());
+    }
+
+    #[test]
+    fn test_timer_wheel_slack() {
+        let slack = Duration::from_millis(5);
+        let wheel = TimerWheel::<u64>::with_high_res(false);
+        let start = now();
+        // the timers spread in 1ms are coalesced
+        for i in 0..100 {
+            let dur = Duration::from_millis(10) + Duration::from_micros(i * 10);
+            wheel.add_timer_with_slack(dur, slack, i);
+        }
+
+        let fired = std::cell::RefCell::new(Vec::new());
+        let mut now = start;
+        while let Some(next) = wheel.schedule_timer(now, &|i| fired.borrow_mut().push((now, i))) {
+            now += next;
+        }
+
+        let fired = fired.into_inner();
+        assert_eq!(fired.len(), 100);
+        // the deadlines may straddle one slack boundary at most
+        let mut batches: Vec<_> = fired.iter().map(|(t, _)| *t).collect();
+        batches.dedup();
+        assert!(batches.len() <= 2);
+        for (t, i) in fired {
+            let deadline = start + 10_000_000 + i * 10_000;
+            assert!(t >= deadline);
+            assert!(t <= deadline + 6_000_000);
+        }
+    }
 }
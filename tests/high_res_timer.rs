@@ -0,0 +1,57 @@
+// the high resolution timer is only supported by the epoll event loop
+#![cfg(target_os = "linux")]
+
+#[macro_use]
+extern crate may;
+
+use std::time::{Duration, Instant};
+
+use may::coroutine;
+
+// sleep `dur` for `n` times in a coroutine, return the total time
+fn sleep_loop(dur: Duration, n: u32) -> Duration {
+    let j = go!(move || {
+        let now = Instant::now();
+        for _ in 0..n {
+            let start = Instant::now();
+            coroutine::sleep(dur);
+            assert!(start.elapsed() >= dur);
+        }
+        now.elapsed()
+    });
+    j.join().unwrap()
+}
+
+#[test]
+fn high_res_sleep() {
+    may::config().set_high_res_timer(true);
+
+    // a coarse timer would sleep at least 1ms each time, the same as
+    // the whole millisecond sleeps, compare them instead of a fixed bound
+    let micros = sleep_loop(Duration::from_micros(100), 50);
+    let millis = sleep_loop(Duration::from_millis(1), 50);
+    assert!(micros < millis, "micros = {micros:?}, millis = {millis:?}");
+
+    // sub-millisecond park timeout is not treated as no timeout
+    let j = go!(|| {
+        let now = Instant::now();
+        coroutine::park_timeout(Duration::from_micros(200));
+        now.elapsed()
+    });
+    let elapsed = j.join().unwrap();
+    assert!(elapsed >= Duration::from_micros(200));
+}
+
+#[test]
+fn high_res_whole_millis() {
+    may::config().set_high_res_timer(true);
+
+    // the sub-millisecond timer is cancelled before the timerfd fires
+    let j = go!(|| coroutine::park_timeout(Duration::from_micros(500)));
+    j.coroutine().unpark();
+    j.join().unwrap();
+
+    // the whole millisecond timers are waited by epoll_wait
+    let elapsed = sleep_loop(Duration::from_millis(2), 10);
+    assert!(elapsed >= Duration::from_millis(20));
+}
@@ -0,0 +1,122 @@
+#![cfg(all(feature = "io_uring", target_os = "linux"))]
+#[macro_use]
+extern crate may;
+
+use std::io::{IoSlice, Read, Write};
+use std::time::Duration;
+
+use may::coroutine;
+use may::net::{TcpListener, TcpStream, UdpSocket};
+
+#[test]
+fn uring_tcp_echo() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = go!(move || {
+        let (mut conn, peer) = listener.accept().unwrap();
+        let mut buf = [0; 64];
+        loop {
+            let n = conn.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            conn.write_all(&buf[..n]).unwrap();
+        }
+        peer
+    });
+
+    let client = go!(move || {
+        // let the server wait in accept and read
+        coroutine::sleep(Duration::from_millis(10));
+        let mut conn = TcpStream::connect(addr).unwrap();
+        coroutine::sleep(Duration::from_millis(10));
+        let bufs = [IoSlice::new(b"hello "), IoSlice::new(b"uring")];
+        assert_eq!(conn.write_vectored(&bufs).unwrap(), 11);
+        let mut buf = [0; 11];
+        conn.read_exact(&mut buf).unwrap();
+        assert_eq!(&buf, b"hello uring");
+        conn.local_addr().unwrap()
+    });
+
+    let local = client.join().unwrap();
+    assert_eq!(server.join().unwrap(), local);
+}
+
+#[test]
+fn uring_thread_io() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    go!(move || {
+        coroutine::sleep(Duration::from_millis(10));
+        let mut conn = TcpStream::connect(addr).unwrap();
+        coroutine::sleep(Duration::from_millis(10));
+        conn.write_all(b"from coroutine").unwrap();
+    });
+
+    // the thread io is submitted by the proxy coroutine
+    let (mut conn, _) = listener.accept().unwrap();
+    let mut buf = Vec::new();
+    conn.read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, b"from coroutine");
+}
+
+#[test]
+fn uring_udp() {
+    let a = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let b = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let a_addr = a.local_addr().unwrap();
+    let b_addr = b.local_addr().unwrap();
+
+    let h = go!(move || {
+        let mut buf = [0; 16];
+        let (n, from) = b.recv_from(&mut buf).unwrap();
+        (buf[..n].to_vec(), from)
+    });
+
+    coroutine::sleep(Duration::from_millis(10));
+    assert_eq!(a.send_to(b"ping", b_addr).unwrap(), 4);
+    let (data, from) = h.join().unwrap();
+    assert_eq!(data, b"ping");
+    assert_eq!(from, a_addr);
+}
+
+#[test]
+#[cfg(feature = "io_timeout")]
+fn uring_read_timeout() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let h = go!(move || {
+        let mut conn = TcpStream::connect(addr).unwrap();
+        conn.set_read_timeout(Some(Duration::from_millis(50)))
+            .unwrap();
+        let now = std::time::Instant::now();
+        let err = conn.read(&mut [0; 8]).unwrap_err();
+        (err.kind(), now.elapsed())
+    });
+
+    let (_conn, _) = listener.accept().unwrap();
+    let (kind, elapsed) = h.join().unwrap();
+    assert_eq!(kind, std::io::ErrorKind::TimedOut);
+    assert!(elapsed >= Duration::from_millis(50));
+}
+
+#[test]
+#[cfg(feature = "io_cancel")]
+fn uring_cancel_read() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let h = go!(move || {
+        let mut conn = TcpStream::connect(addr).unwrap();
+        let _ = conn.read(&mut [0; 8]);
+        unreachable!("the read should be canceled");
+    });
+
+    let (_conn, _) = listener.accept().unwrap();
+    coroutine::sleep(Duration::from_millis(10));
+    unsafe { h.coroutine().cancel() };
+    assert!(h.join().is_err());
+}
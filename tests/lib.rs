@@ -192,6 +192,77 @@ fn scoped_coroutine() {
     assert_eq!(array[2], 4);
 }
 
+#[test]
+fn scoped_fail_fast() {
+    let now = Instant::now();
+    let mut done = false;
+    let ret = coroutine::scope_fail_fast(|scope| {
+        go!(scope, || {
+            coroutine::sleep(Duration::from_secs(10));
+            done = true;
+        });
+        go!(scope, || {
+            coroutine::sleep(Duration::from_millis(10));
+            panic!("fail fast");
+        });
+    });
+
+    match ret {
+        Ok(_) => panic!("scope should return the panic"),
+        Err(panic) => assert_eq!(panic.downcast_ref::<&str>(), Some(&"fail fast")),
+    }
+    assert!(!done);
+    assert!(now.elapsed() < Duration::from_secs(10));
+
+    let ret = coroutine::scope_fail_fast(|scope| {
+        let h = go!(scope, || 42);
+        h.join()
+    });
+    assert_eq!(ret.unwrap(), 42);
+
+    // join the canceled coroutine in a fail fast scope
+    let ret = coroutine::scope_fail_fast(|scope| {
+        let h = go!(scope, || coroutine::sleep(Duration::from_secs(10)));
+        go!(scope, || panic!("fail fast"));
+        h.try_join().is_err()
+    });
+    match ret {
+        Ok(_) => panic!("scope should return the panic"),
+        Err(panic) => assert_eq!(panic.downcast_ref::<&str>(), Some(&"fail fast")),
+    }
+
+    // the error returned by the coroutine fails the scope
+    let mut done = false;
+    let ret = coroutine::scope_fail_fast(|scope| unsafe {
+        go!(scope, || {
+            coroutine::sleep(Duration::from_secs(10));
+            done = true;
+        });
+        scope
+            .try_spawn(|| {
+                coroutine::sleep(Duration::from_millis(10));
+                Err::<(), _>("fail fast")
+            })
+            .try_join()
+            .is_err()
+    });
+    match ret {
+        Ok(_) => panic!("scope should return the error"),
+        Err(e) => assert_eq!(e.downcast_ref::<&str>(), Some(&"fail fast")),
+    }
+    assert!(!done);
+
+    // join the failed coroutine in a fail fast scope
+    let ret = coroutine::scope_fail_fast(|scope| {
+        let h = go!(scope, || -> i32 { panic!("fail fast") });
+        h.try_join().is_err()
+    });
+    match ret {
+        Ok(_) => panic!("scope should return the panic"),
+        Err(panic) => assert_eq!(panic.downcast_ref::<&str>(), Some(&"fail fast")),
+    }
+}
+
 #[test]
 fn yield_from_gen() {
     let mut a = 0;
@@ -271,6 +342,39 @@ fn park_timeout() {
     assert_eq!(a, 10);
 }
 
+#[test]
+fn long_sleep_cancel() {
+    // longer than the max timeout of epoll_wait
+    let j = go!(|| coroutine::sleep(Duration::from_secs(30 * 86400)));
+
+    // let the worker wait on the long timer
+    thread::sleep(Duration::from_millis(50));
+
+    unsafe { j.coroutine().cancel() };
+    assert!(j.join().is_err());
+}
+
+#[test]
+fn park_timeout_with_long_timer() {
+    // keep the long timers in the workers
+    let sleepers = (0..8)
+        .map(|_| go!(|| coroutine::sleep(Duration::from_secs(30 * 86400))))
+        .collect::<Vec<_>>();
+    thread::sleep(Duration::from_millis(20));
+
+    let h = go!(|| {
+        let now = Instant::now();
+        coroutine::park_timeout(Duration::from_millis(50));
+        now.elapsed()
+    });
+    assert!(h.join().unwrap() >= Duration::from_millis(50));
+
+    for h in sleepers {
+        unsafe { h.coroutine().cancel() };
+        assert!(h.join().is_err());
+    }
+}
+
 #[test]
 fn test_sleep() {
     let now = Instant::now();
@@ -350,3 +454,95 @@ fn go_with_macro() {
         assert_eq!(stack_size, 10240);
     }
 }
+
+#[test]
+fn block_on_join_handle() {
+    let j = go!(|| {
+        let h1 = go!(|| {
+            coroutine::sleep(Duration::from_millis(10));
+            40
+        });
+        let h2 = go!(|| 2);
+        coroutine::block_on(async { h1.await.unwrap() + h2.await.unwrap() })
+    });
+    assert_eq!(j.join().unwrap(), 42);
+
+    // block on in thread context
+    let h = go!(|| panic!("panic inside coroutine"));
+    assert!(coroutine::block_on(h).is_err());
+}
+
+#[test]
+#[cfg(feature = "futures")]
+fn channel_stream_sink() {
+    use futures_core::Stream;
+    use futures_sink::Sink;
+    use std::future::poll_fn;
+    use std::pin::Pin;
+
+    async fn sum<S: Stream<Item = i32> + Unpin>(mut s: S) -> i32 {
+        let mut sum = 0;
+        while let Some(v) = poll_fn(|cx| Pin::new(&mut s).poll_next(cx)).await {
+            sum += v;
+        }
+        sum
+    }
+
+    // the sink is closed but not dropped, it's returned to the caller
+    fn feed<S: Sink<i32> + Unpin + Send + 'static>(mut s: S) -> coroutine::JoinHandle<S> {
+        go!(move || {
+            for i in 1..=10 {
+                coroutine::sleep(Duration::from_millis(1));
+                Pin::new(&mut s).start_send(i).ok().unwrap();
+            }
+            coroutine::block_on(poll_fn(|cx| Pin::new(&mut s).poll_close(cx)))
+                .ok()
+                .unwrap();
+            s
+        })
+    }
+
+    let (tx, rx) = may::sync::mpsc::channel();
+    let h = feed(tx);
+    assert_eq!(coroutine::block_on(sum(rx)), 55);
+    let mut tx = h.join().unwrap();
+    assert!(Pin::new(&mut tx).start_send(1).is_err());
+
+    let (tx, rx) = may::sync::spsc::channel();
+    let h = feed(tx);
+    assert_eq!(coroutine::block_on(sum(rx)), 55);
+    let mut tx = h.join().unwrap();
+    assert!(Pin::new(&mut tx).start_send(1).is_err());
+
+    let (tx, rx) = may::sync::mpmc::channel();
+    let h = feed(tx);
+    assert_eq!(coroutine::block_on(sum(rx)), 55);
+    let mut tx = h.join().unwrap();
+    assert!(Pin::new(&mut tx).start_send(1).is_err());
+}
+
+#[test]
+fn split_generic() {
+    use may::io::SplitIo;
+    use may::net::{TcpListener, TcpStream};
+    use std::io::{Read, Write};
+
+    // the split parts are usable with only the `SplitIo` bound
+    fn echo<T: SplitIo + Read + Write>(s: T) -> std::io::Result<()> {
+        let (mut r, mut w) = s.split()?;
+        let mut buf = [0; 5];
+        r.read_exact(&mut buf)?;
+        w.write_all(&buf)
+    }
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let h = go!(move || echo(listener.accept().unwrap().0));
+
+    let mut s = TcpStream::connect(addr).unwrap();
+    s.write_all(b"split").unwrap();
+    let mut buf = [0; 5];
+    s.read_exact(&mut buf).unwrap();
+    assert_eq!(&buf, b"split");
+    h.join().unwrap().unwrap();
+}
@@ -0,0 +1,24 @@
+#![cfg(unix)]
+#[macro_use]
+extern crate may;
+
+use std::time::Duration;
+
+use may::coroutine;
+use may::signal::{Signals, SIGWINCH};
+
+#[test]
+fn signal_select() {
+    let signals = Signals::new(&[SIGWINCH]).unwrap();
+    let h = go!(move || {
+        let mut got = None;
+        select!(
+            sig = signals.recv() => got = sig,
+            _ = coroutine::sleep(Duration::from_secs(5)) => {}
+        );
+        got
+    });
+    coroutine::sleep(Duration::from_millis(10));
+    unsafe { libc::kill(libc::getpid(), SIGWINCH) };
+    assert_eq!(h.join().unwrap(), Some(SIGWINCH));
+}
@@ -0,0 +1,38 @@
+#![cfg(all(unix, feature = "io_timeout"))]
+#[macro_use]
+extern crate may;
+
+use std::io::{ErrorKind, Read};
+use std::time::{Duration, Instant};
+
+use may::net::{TcpListener, TcpStream, UdpSocket};
+
+// the slack is a global config, so it's tested in its own binary
+#[test]
+fn precise_timer() {
+    may::config().set_timer_slack(Duration::from_secs(1));
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let mut tcp = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let _peer = listener.accept().unwrap();
+    tcp.set_read_timeout(Some(Duration::from_millis(10)))
+        .unwrap();
+    tcp.set_precise_timer(true);
+
+    let udp = UdpSocket::bind("127.0.0.1:0").unwrap();
+    udp.set_read_timeout(Some(Duration::from_millis(10)))
+        .unwrap();
+    udp.set_precise_timer(true);
+
+    // the slack is ignored by the precise sockets
+    let h = go!(move || {
+        let now = Instant::now();
+        let tcp_err = tcp.read(&mut [0; 1]).unwrap_err();
+        let udp_err = udp.recv(&mut [0; 1]).unwrap_err();
+        (now.elapsed(), tcp_err.kind(), udp_err.kind())
+    });
+    let (elapsed, tcp_kind, udp_kind) = h.join().unwrap();
+    assert_eq!(tcp_kind, ErrorKind::TimedOut);
+    assert_eq!(udp_kind, ErrorKind::TimedOut);
+    assert!(elapsed < Duration::from_millis(500), "{elapsed:?}");
+}